@@ -0,0 +1,72 @@
+use std::cell::{Ref, RefMut};
+
+use crate::{
+    Error,
+    ecs::{Entity, Schedule, World},
+    config::Config,
+    event::{Event, EventBus, WindowState},
+    game::Player,
+    input::Input,
+    renderer::Renderer,
+    scene::Scene,
+    time::Time,
+    window::Window,
+};
+
+// everything user code can touch from inside the game loop
+pub struct Context<'a> {
+    pub scene: &'a mut Scene,
+    pub world: &'a mut World,
+    pub schedule: &'a mut Schedule,
+    pub renderer: &'a mut Renderer,
+    pub window: &'a mut Window,
+    pub config: &'a mut Config,
+    pub player: Entity,
+}
+impl Context<'_> {
+    pub fn input(&self) -> Ref<'_, Input> {
+        self.world.resource::<Input>().unwrap()
+    }
+    pub fn window_state(&self) -> Ref<'_, WindowState> {
+        self.world.resource::<WindowState>().unwrap()
+    }
+    pub fn events(&self) -> RefMut<'_, EventBus> {
+        self.world.resource_mut::<EventBus>().unwrap()
+    }
+    pub fn time(&self) -> Ref<'_, Time> {
+        self.world.resource::<Time>().unwrap()
+    }
+    // for pausing or scaling time
+    pub fn time_mut(&self) -> RefMut<'_, Time> {
+        self.world.resource_mut::<Time>().unwrap()
+    }
+    pub fn player(&self) -> Option<RefMut<'_, Player>> {
+        self.world.get_mut::<Player>(self.player)
+    }
+    // after rebinding: stores the current bindings in the config and writes it to its file
+    pub fn save_config(&mut self) -> Result<(), Error> {
+        let map = self.input().map().clone();
+        self.config.input = map;
+        self.config.save_in_place()
+    }
+    // the loop finishes the current frame, then on_exit runs
+    pub fn quit(&mut self) {
+        self.window.set_should_close(true);
+    }
+}
+
+// the game itself, Game::start calls these in this order every frame:
+// on_event for each window event, Input systems,
+// fixed_update and Physics systems once per fixed step (0 or more times),
+// Update systems, update, RenderExtract systems, rendering, render_ui
+pub trait App {
+    // called once before the first frame, the gl context is ready here
+    fn init(&mut self, _ctx: &mut Context) -> Result<(), Error> { Ok(()) }
+    // ctx.time().fixed_delta() seconds of simulation
+    fn fixed_update(&mut self, _ctx: &mut Context) {}
+    fn update(&mut self, _ctx: &mut Context) {}
+    // after the scene is drawn, before the buffers are swapped
+    fn render_ui(&mut self, _ctx: &mut Context) {}
+    fn on_event(&mut self, _ctx: &mut Context, _event: &Event) {}
+    fn on_exit(&mut self, _ctx: &mut Context) {}
+}
@@ -0,0 +1,371 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, input::InputMap, window::WindowMode};
+
+// everything that used to be a constant, loaded from a toml file:
+//
+//   title = "enigne"
+//   width = 1280
+//   vsync = false
+//   [input.actions]
+//   screenshot = ["F12", "Ctrl+P"]
+//
+// missing keys keep their defaults, unknown keys are an error.
+// ENGINE_<KEY>=value (ENGINE_ variables that aren't a key are skipped) and --<key>=value (or --<key> value) override the file,
+// nested keys use `__` and `.`: ENGINE_INPUT__ACTIONS__QUIT='["Q"]', --input.actions.quit='["Q"]'
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    // "windowed", "borderless" or "fullscreen", on monitor (0 is the primary one)
+    pub window_mode: WindowMode,
+    pub monitor: usize,
+    pub vsync: bool,
+    // image file under asset_root
+    pub icon: Option<PathBuf>,
+    // 0 turns multisampling off
+    pub msaa_samples: u32,
+    pub background_color: [f32; 4],
+
+    // degrees, vertical
+    pub fov: f32,
+    // replaces the camera_sense the player was made with, when set
+    pub mouse_sensitivity: Option<f32>,
+    // time stops while another window has focus
+    pub pause_on_focus_loss: bool,
+    // named actions and axes, see input/mod.rs for how bindings are written
+    pub input: InputMap,
+
+    // shaders and textures are looked up relative to this
+    pub asset_root: PathBuf,
+    // F12 saves screenshots here
+    pub screenshot_dir: String,
+    // F9 starts and stops input recordings, they are saved here
+    pub replay_dir: String,
+
+    // hidden window (or no window system at all, via osmesa) rendering into an offscreen framebuffer,
+    // time advances by headless_frame_time seconds every frame instead of following the wall clock
+    pub headless: bool,
+    pub headless_frame_time: f64,
+
+    // the file this was loaded from, save_in_place writes back there
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+
+    // per run, from --record and --replay <file>: record input from the first frame
+    // (saved on exit), or play a recording back instead of live input
+    #[serde(skip)]
+    pub record: bool,
+    #[serde(skip)]
+    pub replay: Option<PathBuf>,
+}
+// the top level keys of the file
+const KEYS: &[&str] = &[
+    "title", "width", "height", "window_mode", "monitor", "vsync", "icon", "msaa_samples", "background_color",
+    "fov", "mouse_sensitivity", "pause_on_focus_loss", "input",
+    "asset_root", "screenshot_dir", "replay_dir", "headless", "headless_frame_time",
+];
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            title: "enigne".to_string(),
+            width: 300,
+            height: 300,
+            window_mode: WindowMode::Windowed,
+            monitor: 0,
+            vsync: true,
+            icon: None,
+            msaa_samples: 0,
+            background_color: [0.5, 0.3, 0.3, 1.0],
+            fov: 90.0,
+            mouse_sensitivity: None,
+            pause_on_focus_loss: true,
+            input: InputMap::default(),
+            asset_root: PathBuf::from("src"),
+            screenshot_dir: "screenshots".to_string(),
+            replay_dir: "replays".to_string(),
+            headless: false,
+            headless_frame_time: 1.0/60.0,
+            path: None,
+            record: false,
+            replay: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self { width, height, headless: true, ..Self::default() }
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, Error> {
+        let config: Self = toml::from_str(source)
+            .map_err(|e| Error::ConfigError(e.message().to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+    pub fn to_toml(&self) -> Result<String, Error> {
+        toml::to_string_pretty(self).map_err(|e| Error::ConfigError(e.to_string()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| Error::ConfigError(format!("couldn't read {}: {}", path.display(), e)))?;
+        let config = Self::from_toml(&source).map_err(|e| match e {
+            Error::ConfigError(msg) => Error::ConfigError(format!("{}: {}", path.display(), msg)),
+            e => e,
+        })?;
+        Ok(Self { path: Some(path.to_path_buf()), ..config })
+    }
+    // defaults when the file doesn't exist yet, errors if it exists but is broken.
+    // either way save_in_place writes to path
+    pub fn load_or_default(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        if path.exists() { Self::load(path) } else { Ok(Self { path: Some(path.to_path_buf()), ..Self::default() }) }
+    }
+    // the file, then ENGINE_* variables, then command line arguments
+    pub fn load_with_overrides(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut config = Self::load_or_default(path)?;
+        config.apply_env(std::env::vars())?;
+        config.apply_args(std::env::args().skip(1))?;
+        Ok(config)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::ConfigError(format!("couldn't create {}: {}", dir.display(), e)))?;
+        }
+        std::fs::write(path, self.to_toml()?)
+            .map_err(|e| Error::ConfigError(format!("couldn't write {}: {}", path.display(), e)))
+    }
+    pub fn save_in_place(&self) -> Result<(), Error> {
+        match &self.path {
+            Some(path) => self.save(path),
+            None => Err(Error::ConfigError("config wasn't loaded from a file".to_string())),
+        }
+    }
+
+    // sets one setting from text, the way it would be written in the file.
+    // anything that isn't valid toml is taken as a string, so --title=my game works unquoted
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        let value = match format!("value = {}", value).parse::<toml::Table>() {
+            Ok(mut table) => table.remove("value").unwrap(),
+            Err(_) => toml::Value::String(value.to_string()),
+        };
+
+        let mut root = toml::Table::try_from(&*self).map_err(|e| Error::ConfigError(e.to_string()))?;
+        let mut table = &mut root;
+        let mut parts = key.split('.').peekable();
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                table.insert(part.to_string(), value);
+                break
+            }
+            table = table.entry(part)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or(Error::ConfigError(format!("{} is not a table", part)))?;
+        }
+
+        let config: Self = root.try_into()
+            .map_err(|e: toml::de::Error| Error::ConfigError(format!("{}: {}", key, e.message())))?;
+        config.validate()?;
+        // these aren't part of the file
+        *self = Self {
+            path: self.path.take(),
+            record: self.record,
+            replay: self.replay.take(),
+            ..config
+        };
+        Ok(())
+    }
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) -> Result<(), Error> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix("ENGINE_") else { continue };
+            let key = key.to_lowercase().replace("__", ".");
+            // some other program's variable, only bad values for real keys are an error
+            if !KEYS.contains(&key.split('.').next().unwrap()) { continue }
+            self.set(&key, &value)?;
+        }
+        Ok(())
+    }
+    // --key=value, --key value, a lone --flag means true. anything not starting with -- is skipped
+    pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<(), Error> {
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let Some(arg) = arg.strip_prefix("--") else { continue };
+            match arg.split_once('=') {
+                Some((key, value)) => self.set_arg(key, value)?,
+                None => match args.next_if(|next| !next.starts_with("--")) {
+                    Some(value) => self.set_arg(arg, &value)?,
+                    None => self.set_arg(arg, "true")?,
+                },
+            }
+        }
+        Ok(())
+    }
+    // the per run options only exist on the command line
+    fn set_arg(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key {
+            "record" => self.record = value.parse()
+                .map_err(|_| Error::ConfigError(format!("record: expected true or false, got {}", value)))?,
+            "replay" => self.replay = Some(PathBuf::from(value)),
+            _ => self.set(key, value)?,
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let fail = |msg: String| Err(Error::ConfigError(msg));
+        if self.width == 0 || self.height == 0 {
+            return fail(format!("resolution must not be 0, got {}x{}", self.width, self.height))
+        }
+        if !matches!(self.msaa_samples, 0 | 1 | 2 | 4 | 8 | 16) {
+            return fail(format!("msaa_samples must be 0, 1, 2, 4, 8 or 16, got {}", self.msaa_samples))
+        }
+        if !(self.fov > 0.0 && self.fov < 180.0) {
+            return fail(format!("fov must be between 0 and 180 degrees, got {}", self.fov))
+        }
+        if let Some(sense) = self.mouse_sensitivity
+            && !(sense.is_finite() && sense > 0.0) {
+            return fail(format!("mouse_sensitivity must be positive, got {}", sense))
+        }
+        if self.background_color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return fail(format!("background_color components must be in 0..1, got {:?}", self.background_color))
+        }
+        if !(self.headless_frame_time.is_finite() && self.headless_frame_time > 0.0) {
+            return fail(format!("headless_frame_time must be positive, got {}", self.headless_frame_time))
+        }
+        self.input.validate()
+    }
+
+    // path of an asset under asset_root
+    pub fn asset(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.asset_root.join(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glfw::Key;
+
+    use crate::{config::{Config, KEYS}, input::Binding, window::WindowMode};
+
+    #[test]
+    fn defaults_round_trip() {
+        let config = Config::default();
+        assert_eq!(Config::from_toml(&config.to_toml().unwrap()).unwrap(), config);
+    }
+
+    #[test]
+    fn partial_file() {
+        let config = Config::from_toml("
+            title = \"test\"
+            width = 800
+            vsync = false
+            [input.actions]
+            quit = [\"Q\", \"Ctrl+C\"]
+        ").unwrap();
+        assert_eq!(config.title, "test");
+        assert_eq!((config.width, config.height), (800, Config::default().height));
+        assert!(!config.vsync);
+        assert_eq!(config.input.actions["quit"], vec![Binding::key(Key::Q), "Ctrl+C".parse().unwrap()]);
+        // an actions table replaces the default actions, the axes stay
+        assert!(!config.input.actions.contains_key("screenshot"));
+        assert!(config.input.axes.contains_key("move_x"));
+    }
+
+    #[test]
+    fn invalid_files() {
+        assert!(Config::from_toml("widht = 800").is_err());
+        assert!(Config::from_toml("width = 0").is_err());
+        assert!(Config::from_toml("msaa_samples = 3").is_err());
+        assert!(Config::from_toml("fov = 200.0").is_err());
+        assert!(Config::from_toml("headless_frame_time = 0.0").is_err());
+        assert!(Config::from_toml("[input.actions]\nquit = [\"NotAKey\"]").is_err());
+        assert!(Config::from_toml("[input.actions]\nquit = [\"W\"]\npause = [\"W\"]").is_err());
+    }
+
+    #[test]
+    fn overrides() {
+        let mut config = Config::default();
+        config.apply_env([
+            ("ENGINE_WIDTH".to_string(), "640".to_string()),
+            ("ENGINE_INPUT__ACTIONS__QUIT".to_string(), "[\"Q\"]".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+            ("ENGINE_SOMETHING_ELSE".to_string(), "1".to_string()),
+        ]).unwrap();
+        config.apply_args(
+            ["--title=my game", "--window_mode=borderless", "--msaa_samples", "4", "--fov=60"].map(String::from)
+        ).unwrap();
+        assert_eq!(config.width, 640);
+        assert_eq!(config.input.actions["quit"], vec![Binding::key(Key::Q)]);
+        assert_eq!(config.input.actions["screenshot"], vec![Binding::key(Key::F12)]);
+        assert_eq!(config.title, "my game");
+        assert_eq!(config.window_mode, WindowMode::Borderless);
+        assert_eq!(config.msaa_samples, 4);
+        assert_eq!(config.fov, 60.0);
+
+        config.apply_args(["--record", "--replay", "bug.json", "--width=20"].map(String::from)).unwrap();
+        assert!(config.record);
+        assert_eq!(config.replay, Some("bug.json".into()));
+        assert_eq!(config.width, 20);
+        assert!(!config.to_toml().unwrap().contains("bug.json"));
+
+        assert!(config.apply_env([("ENGINE_WIDTH".to_string(), "wide".to_string())]).is_err());
+    }
+
+    #[test]
+    fn keys_match_the_file() {
+        let config = Config { icon: Some("icon.png".into()), mouse_sensitivity: Some(0.5), ..Config::default() };
+        let table = toml::Table::try_from(&config).unwrap();
+        let mut keys: Vec<_> = table.keys().map(String::as_str).collect();
+        let mut expected = KEYS.to_vec();
+        keys.sort();
+        expected.sort();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn headless_from_file_and_args() {
+        let config = Config::from_toml("headless = true\nheadless_frame_time = 0.02").unwrap();
+        assert!(config.headless);
+        assert_eq!(config.headless_frame_time, 0.02);
+
+        let mut config = Config::default();
+        config.apply_args(["--headless", "--headless_frame_time=0.5"].map(String::from)).unwrap();
+        assert!(config.headless);
+        assert_eq!(config.headless_frame_time, 0.5);
+        assert!(config.apply_args(["--headless_frame_time=-1"].map(String::from)).is_err());
+        assert_eq!(config.headless_frame_time, 0.5);
+    }
+
+    #[test]
+    fn bad_override_keeps_config() {
+        let mut config = Config::headless(10, 10);
+        assert!(config.set("width", "wide").is_err());
+        assert!(config.set("height", "0").is_err());
+        assert_eq!((config.width, config.height), (10, 10));
+        config.set("width", "20").unwrap();
+        assert!(config.headless);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("engine_config_{}.toml", std::process::id()));
+        let mut config = Config::default();
+        config.set("mouse_sensitivity", "0.25").unwrap();
+        config.save(&path).unwrap();
+        let loaded = Config::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.path.as_ref(), Some(&path));
+        assert_eq!(Config { path: None, ..loaded }, config);
+    }
+}
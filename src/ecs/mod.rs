@@ -0,0 +1,252 @@
+mod storage;
+mod query;
+mod schedule;
+
+pub use storage::SparseSet;
+pub use query::Query;
+pub use schedule::{Schedule, Stage, System};
+
+use std::{
+    any::{Any, TypeId},
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+};
+
+use storage::Storage;
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+// every 'static type can be a component, one storage per type
+// storages and resources sit in RefCells so queries can borrow several of them at once
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, RefCell<Box<dyn Storage>>>,
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+}
+impl World {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity { index, generation: self.generations[index as usize] }
+            },
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity { index: (self.generations.len()-1) as u32, generation: 0 }
+            }
+        }
+    }
+    pub fn despawn(&mut self, entity: Entity) -> Result<(), Error> {
+        if !self.is_alive(entity) { return Err(Error::EntityError("entity doesn't exist")) }
+        for storage in self.storages.values_mut() {
+            storage.get_mut().remove_entity(entity);
+        }
+        let index = entity.index as usize;
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free.push(entity.index);
+        Ok(())
+    }
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index).is_some_and(|a| *a) && self.generations[index] == entity.generation
+    }
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive.iter().enumerate()
+            .filter(|(_, a)| **a)
+            .map(|(i, _)| Entity { index: i as u32, generation: self.generations[i] })
+    }
+    pub fn len(&self) -> usize { self.alive.len() - self.free.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    // returns the old component if the entity already had one of this type
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Result<Option<T>, Error> {
+        if !self.is_alive(entity) { return Err(Error::EntityError("entity doesn't exist")) }
+        let storage = self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(SparseSet::<T>::new())))
+            .get_mut();
+        Ok(downcast_mut::<T>(storage.as_mut()).insert(entity, component))
+    }
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        let storage = self.storages.get_mut(&TypeId::of::<T>())?.get_mut();
+        downcast_mut::<T>(storage.as_mut()).remove(entity)
+    }
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.storage::<T>().is_some_and(|s| s.contains(entity))
+    }
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.storage::<T>()?, |s| s.get(entity)).ok()
+    }
+    pub fn get_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.storage_mut::<T>()?, |s| s.get_mut(entity)).ok()
+    }
+
+    pub fn storage<T: 'static>(&self) -> Option<Ref<'_, SparseSet<T>>> {
+        let cell = self.storages.get(&TypeId::of::<T>())?;
+        Some(Ref::map(cell.borrow(), |s| s.as_any().downcast_ref::<SparseSet<T>>().unwrap()))
+    }
+    pub fn storage_mut<T: 'static>(&self) -> Option<RefMut<'_, SparseSet<T>>> {
+        let cell = self.storages.get(&TypeId::of::<T>())?;
+        Some(RefMut::map(cell.borrow_mut(), |s| downcast_mut::<T>(s.as_mut())))
+    }
+
+    // calls f for every entity that has all of the queried components
+    // e.g. world.query::<(&mut Transform, &Velocity)>(|entity, (transform, velocity)| ...)
+    pub fn query<Q: Query>(&self, mut f: impl FnMut(Entity, Q::Item<'_>)) {
+        let Some(mut guard) = Q::borrow(self) else { return };
+        let entities = Q::entities(&guard).unwrap_or_else(|| self.entities().collect());
+        for entity in entities {
+            if Q::contains(&guard, entity) {
+                f(entity, Q::fetch(&mut guard, entity));
+            }
+        }
+    }
+    // entities matching the query, for when the world has to be changed while walking them
+    pub fn query_entities<Q: Query>(&self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        self.query::<Q>(|e, _| entities.push(e));
+        entities
+    }
+
+    // global singletons like input or frame time, one per type
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) {
+        self.resources.insert(TypeId::of::<T>(), RefCell::new(Box::new(resource)));
+    }
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        let resource = self.resources.remove(&TypeId::of::<T>())?.into_inner();
+        resource.downcast::<T>().ok().map(|r| *r)
+    }
+    pub fn resource<T: 'static>(&self) -> Option<Ref<'_, T>> {
+        let cell = self.resources.get(&TypeId::of::<T>())?;
+        Some(Ref::map(cell.borrow(), |r| r.downcast_ref::<T>().unwrap()))
+    }
+    pub fn resource_mut<T: 'static>(&self) -> Option<RefMut<'_, T>> {
+        let cell = self.resources.get(&TypeId::of::<T>())?;
+        Some(RefMut::map(cell.borrow_mut(), |r| r.downcast_mut::<T>().unwrap()))
+    }
+}
+
+fn downcast_mut<T: 'static>(storage: &mut dyn Storage) -> &mut SparseSet<T> {
+    storage.as_any_mut().downcast_mut::<SparseSet<T>>().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ecs::World;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32);
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32);
+    struct Frozen;
+
+    #[test]
+    fn spawn_and_despawn() {
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(a, Position(1.0)).unwrap();
+        world.insert(b, Position(2.0)).unwrap();
+        assert_eq!(world.len(), 2);
+
+        world.despawn(a).unwrap();
+        assert!(!world.is_alive(a));
+        assert!(world.get::<Position>(a).is_none());
+        assert!(world.despawn(a).is_err());
+        assert!(world.insert(a, Position(0.0)).is_err());
+
+        // index is reused with a new generation
+        let c = world.spawn();
+        assert_ne!(a, c);
+        assert!(!world.has::<Position>(c));
+        assert_eq!(*world.get::<Position>(b).unwrap(), Position(2.0));
+    }
+
+    #[test]
+    fn insert_replace_remove() {
+        let mut world = World::new();
+        let e = world.spawn();
+        assert_eq!(world.insert(e, Position(1.0)).unwrap(), None);
+        assert_eq!(world.insert(e, Position(2.0)).unwrap(), Some(Position(1.0)));
+        world.get_mut::<Position>(e).unwrap().0 += 1.0;
+        assert_eq!(*world.get::<Position>(e).unwrap(), Position(3.0));
+        assert_eq!(world.remove::<Position>(e), Some(Position(3.0)));
+        assert!(!world.has::<Position>(e));
+        assert_eq!(world.remove::<Velocity>(e), None);
+    }
+
+    #[test]
+    fn query_tuples() {
+        let mut world = World::new();
+        let moving = world.spawn();
+        world.insert(moving, Position(0.0)).unwrap();
+        world.insert(moving, Velocity(2.0)).unwrap();
+        let still = world.spawn();
+        world.insert(still, Position(5.0)).unwrap();
+        let frozen = world.spawn();
+        world.insert(frozen, Position(1.0)).unwrap();
+        world.insert(frozen, Velocity(1.0)).unwrap();
+        world.insert(frozen, Frozen).unwrap();
+
+        world.query::<(&mut Position, &Velocity, Option<&Frozen>)>(|_, (pos, vel, frozen)| {
+            if frozen.is_none() { pos.0 += vel.0; }
+        });
+        assert_eq!(*world.get::<Position>(moving).unwrap(), Position(2.0));
+        assert_eq!(*world.get::<Position>(still).unwrap(), Position(5.0));
+        assert_eq!(*world.get::<Position>(frozen).unwrap(), Position(1.0));
+
+        let mut count = 0;
+        world.query::<&Position>(|_, _| count += 1);
+        assert_eq!(count, 3);
+        assert_eq!(world.query_entities::<(&Velocity, &Frozen)>(), vec![frozen]);
+        // a component type that was never inserted matches nothing
+        assert!(world.query_entities::<(&Position, &String)>().is_empty());
+    }
+
+    #[test]
+    fn query_after_swap_remove() {
+        let mut world = World::new();
+        let entities: Vec<_> = (0..4).map(|i| {
+            let e = world.spawn();
+            world.insert(e, Position(i as f32)).unwrap();
+            e
+        }).collect();
+        world.despawn(entities[1]).unwrap();
+        let mut sum = 0.0;
+        world.query::<&Position>(|_, p| sum += p.0);
+        assert_eq!(sum, 5.0);
+        assert_eq!(*world.get::<Position>(entities[3]).unwrap(), Position(3.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn conflicting_borrows_panic() {
+        let mut world = World::new();
+        let e = world.spawn();
+        world.insert(e, Position(0.0)).unwrap();
+        world.query::<(&mut Position, &Position)>(|_, _| {});
+    }
+
+    #[test]
+    fn resources() {
+        let mut world = World::new();
+        assert!(world.resource::<u32>().is_none());
+        world.insert_resource(3_u32);
+        *world.resource_mut::<u32>().unwrap() += 1;
+        assert_eq!(*world.resource::<u32>().unwrap(), 4);
+        assert_eq!(world.remove_resource::<u32>(), Some(4));
+        assert!(world.resource::<u32>().is_none());
+    }
+}
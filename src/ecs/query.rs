@@ -0,0 +1,81 @@
+use std::cell::{Ref, RefMut};
+
+use crate::ecs::{Entity, World, storage::SparseSet};
+
+// something that can be fetched for an entity: &T, &mut T, Option<&T> or a tuple of those
+// the storages stay borrowed for the whole query, so (&mut A, &A) panics like a RefCell would
+pub trait Query {
+    type Guard<'w>;
+    type Item<'a>;
+
+    // None if a required component type has never been inserted
+    fn borrow(world: &World) -> Option<Self::Guard<'_>>;
+    // entities to walk, the first required component drives the iteration
+    fn entities(guard: &Self::Guard<'_>) -> Option<Vec<Entity>>;
+    fn contains(guard: &Self::Guard<'_>, entity: Entity) -> bool;
+    fn fetch<'a>(guard: &'a mut Self::Guard<'_>, entity: Entity) -> Self::Item<'a>;
+}
+
+impl<T: 'static> Query for &T {
+    type Guard<'w> = Ref<'w, SparseSet<T>>;
+    type Item<'a> = &'a T;
+
+    fn borrow(world: &World) -> Option<Self::Guard<'_>> { world.storage::<T>() }
+    fn entities(guard: &Self::Guard<'_>) -> Option<Vec<Entity>> { Some(guard.entities().to_vec()) }
+    fn contains(guard: &Self::Guard<'_>, entity: Entity) -> bool { guard.contains(entity) }
+    fn fetch<'a>(guard: &'a mut Self::Guard<'_>, entity: Entity) -> Self::Item<'a> {
+        guard.get(entity).unwrap()
+    }
+}
+
+impl<T: 'static> Query for &mut T {
+    type Guard<'w> = RefMut<'w, SparseSet<T>>;
+    type Item<'a> = &'a mut T;
+
+    fn borrow(world: &World) -> Option<Self::Guard<'_>> { world.storage_mut::<T>() }
+    fn entities(guard: &Self::Guard<'_>) -> Option<Vec<Entity>> { Some(guard.entities().to_vec()) }
+    fn contains(guard: &Self::Guard<'_>, entity: Entity) -> bool { guard.contains(entity) }
+    fn fetch<'a>(guard: &'a mut Self::Guard<'_>, entity: Entity) -> Self::Item<'a> {
+        guard.get_mut(entity).unwrap()
+    }
+}
+
+impl<T: 'static> Query for Option<&T> {
+    type Guard<'w> = Option<Ref<'w, SparseSet<T>>>;
+    type Item<'a> = Option<&'a T>;
+
+    fn borrow(world: &World) -> Option<Self::Guard<'_>> { Some(world.storage::<T>()) }
+    fn entities(_: &Self::Guard<'_>) -> Option<Vec<Entity>> { None }
+    fn contains(_: &Self::Guard<'_>, _: Entity) -> bool { true }
+    fn fetch<'a>(guard: &'a mut Self::Guard<'_>, entity: Entity) -> Self::Item<'a> {
+        guard.as_ref().and_then(|g| g.get(entity))
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($q:ident $i:tt),*) => {
+        impl<$($q: Query),*> Query for ($($q,)*) {
+            type Guard<'w> = ($($q::Guard<'w>,)*);
+            type Item<'a> = ($($q::Item<'a>,)*);
+
+            fn borrow(world: &World) -> Option<Self::Guard<'_>> {
+                Some(($($q::borrow(world)?,)*))
+            }
+            fn entities(guard: &Self::Guard<'_>) -> Option<Vec<Entity>> {
+                None$(.or_else(|| $q::entities(&guard.$i)))*
+            }
+            fn contains(guard: &Self::Guard<'_>, entity: Entity) -> bool {
+                true $(&& $q::contains(&guard.$i, entity))*
+            }
+            fn fetch<'a>(guard: &'a mut Self::Guard<'_>, entity: Entity) -> Self::Item<'a> {
+                ($($q::fetch(&mut guard.$i, entity),)*)
+            }
+        }
+    };
+}
+impl_query_tuple!(A 0);
+impl_query_tuple!(A 0, B 1);
+impl_query_tuple!(A 0, B 1, C 2);
+impl_query_tuple!(A 0, B 1, C 2, D 3);
+impl_query_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_query_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
@@ -0,0 +1,89 @@
+use crate::ecs::World;
+
+// stages run in this order every frame, the same as Game::step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Input,
+    // once per fixed step in the game, so zero or more times a frame. Schedule::run runs it once
+    Physics,
+    Update,
+    RenderExtract,
+}
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::Input, Stage::Physics, Stage::Update, Stage::RenderExtract];
+}
+
+pub trait System {
+    fn run(&mut self, world: &mut World);
+}
+impl<F: FnMut(&mut World)> System for F {
+    fn run(&mut self, world: &mut World) { self(world) }
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    stages: [Vec<Box<dyn System>>; 4],
+}
+impl Schedule {
+    pub fn new() -> Self { Self::default() }
+
+    // systems in the same stage run in the order they were added
+    pub fn add_system(&mut self, stage: Stage, system: impl System + 'static) -> &mut Self {
+        self.stages[stage as usize].push(Box::new(system));
+        self
+    }
+    pub fn run_stage(&mut self, stage: Stage, world: &mut World) {
+        for system in self.stages[stage as usize].iter_mut() {
+            system.run(world);
+        }
+    }
+    pub fn run(&mut self, world: &mut World) {
+        for stage in Stage::ALL { self.run_stage(stage, world); }
+    }
+    pub fn len(&self) -> usize { self.stages.iter().map(|s| s.len()).sum() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ecs::{Schedule, Stage, World};
+
+    struct Log(Vec<&'static str>);
+
+    #[test]
+    fn stages_run_in_order() {
+        let mut world = World::new();
+        world.insert_resource(Log(Vec::new()));
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::RenderExtract, |w: &mut World| w.resource_mut::<Log>().unwrap().0.push("extract"))
+            .add_system(Stage::Update, |w: &mut World| w.resource_mut::<Log>().unwrap().0.push("update1"))
+            .add_system(Stage::Input, |w: &mut World| w.resource_mut::<Log>().unwrap().0.push("input"))
+            .add_system(Stage::Physics, |w: &mut World| w.resource_mut::<Log>().unwrap().0.push("physics"))
+            .add_system(Stage::Update, |w: &mut World| w.resource_mut::<Log>().unwrap().0.push("update2"));
+        assert_eq!(schedule.len(), 5);
+
+        schedule.run(&mut world);
+        assert_eq!(
+            world.resource::<Log>().unwrap().0,
+            vec!["input", "physics", "update1", "update2", "extract"]
+        );
+    }
+
+    #[test]
+    fn systems_keep_state() {
+        struct Counter(u32);
+        let mut world = World::new();
+        world.insert_resource(Counter(0));
+
+        let mut calls = 0;
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, move |w: &mut World| {
+            calls += 1;
+            w.resource_mut::<Counter>().unwrap().0 = calls;
+        });
+        for _ in 0..3 { schedule.run(&mut world); }
+        assert_eq!(world.resource::<Counter>().unwrap().0, 3);
+    }
+}
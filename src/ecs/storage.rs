@@ -0,0 +1,78 @@
+use std::any::Any;
+
+use crate::ecs::Entity;
+
+// type erased so the world can keep every component type in one map
+pub trait Storage: Any {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+// sparse[entity index] => position in dense/data, iteration only touches the packed arrays
+pub struct SparseSet<T> {
+    sparse: Vec<Option<usize>>,
+    dense: Vec<Entity>,
+    data: Vec<T>,
+}
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self { sparse: Vec::new(), dense: Vec::new(), data: Vec::new() }
+    }
+}
+impl<T> SparseSet<T> {
+    pub fn new() -> Self { Self::default() }
+
+    // replaces and returns the old value if the entity already had one
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        let index = entity.index as usize;
+        if index >= self.sparse.len() { self.sparse.resize(index+1, None); }
+        match self.sparse[index] {
+            Some(d) if self.dense[d] == entity => Some(std::mem::replace(&mut self.data[d], value)),
+            _ => {
+                self.sparse[index] = Some(self.dense.len());
+                self.dense.push(entity);
+                self.data.push(value);
+                None
+            }
+        }
+    }
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let d = self.dense_index(entity)?;
+        self.sparse[entity.index as usize] = None;
+        self.dense.swap_remove(d);
+        let value = self.data.swap_remove(d);
+        // the last element moved into the hole
+        if let Some(moved) = self.dense.get(d) {
+            self.sparse[moved.index as usize] = Some(d);
+        }
+        Some(value)
+    }
+
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        self.sparse.get(entity.index as usize).copied().flatten()
+            .filter(|d| self.dense[*d] == entity)
+    }
+    pub fn contains(&self, entity: Entity) -> bool { self.dense_index(entity).is_some() }
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.dense_index(entity).map(|d| &self.data[d])
+    }
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.dense_index(entity).map(|d| &mut self.data[d])
+    }
+
+    pub fn entities(&self) -> &[Entity] { &self.dense }
+    pub fn len(&self) -> usize { self.dense.len() }
+    pub fn is_empty(&self) -> bool { self.dense.is_empty() }
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.dense.iter().copied().zip(self.data.iter())
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.dense.iter().copied().zip(self.data.iter_mut())
+    }
+}
+impl<T: 'static> Storage for SparseSet<T> {
+    fn remove_entity(&mut self, entity: Entity) { self.remove(entity); }
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
@@ -0,0 +1,226 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    marker::PhantomData,
+};
+
+// one queue per event type, any 'static type is an event. sending also calls the immediate
+// subscribers right away, queued events stay readable for this frame and the next,
+// so a system that runs before the sender still sees them a frame later:
+//
+//   bus.send(Explosion { at });                          // anywhere with the bus
+//   bus.subscribe(|e: &Explosion| play_sound(e.at));     // right when it is sent
+//   for e in bus.read(&mut reader) { .. }                // each event once per reader
+//
+// the game keeps an EventBus resource and calls update() at the start of every frame
+
+struct Instance<T> {
+    id: usize,
+    event: T,
+}
+
+// the double buffered queue of one event type
+pub struct Events<T> {
+    previous: Vec<Instance<T>>,
+    current: Vec<Instance<T>>,
+    // ever sent, the next id
+    count: usize,
+}
+impl<T> Default for Events<T> {
+    fn default() -> Self { Self { previous: Vec::new(), current: Vec::new(), count: 0 } }
+}
+impl<T> Events<T> {
+    pub fn new() -> Self { Self::default() }
+    pub fn send(&mut self, event: T) {
+        self.current.push(Instance { id: self.count, event });
+        self.count += 1;
+    }
+    // last frame's events are dropped, this frame's become last frame's
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+    // both frames, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(self.current.iter()).map(|i| &i.event)
+    }
+    // only what was sent since the last update
+    pub fn iter_current(&self) -> impl Iterator<Item = &T> {
+        self.current.iter().map(|i| &i.event)
+    }
+    pub fn len(&self) -> usize { self.previous.len() + self.current.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+}
+
+// remembers how far it has read, so every event is seen once no matter when it runs
+pub struct EventReader<T> {
+    next: usize,
+    _event: PhantomData<fn() -> T>,
+}
+impl<T> Default for EventReader<T> {
+    fn default() -> Self { Self { next: 0, _event: PhantomData } }
+}
+impl<T> EventReader<T> {
+    // starts with whatever is still buffered
+    pub fn new() -> Self { Self::default() }
+    // skips what is already there
+    pub fn from_now(events: &Events<T>) -> Self { Self { next: events.count, _event: PhantomData } }
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> + use<'a, T> {
+        let next = std::mem::replace(&mut self.next, events.count);
+        events.previous.iter().chain(events.current.iter())
+            .filter(move |i| i.id >= next)
+            .map(|i| &i.event)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(usize);
+
+type Subscribers<T> = Vec<(SubscriptionId, Box<dyn FnMut(&T)>)>;
+
+trait Queue {
+    fn update(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+impl<T: 'static> Queue for Events<T> {
+    fn update(&mut self) { Events::update(self) }
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    queues: HashMap<TypeId, Box<dyn Queue>>,
+    // Subscribers<T> per type
+    subscribers: HashMap<TypeId, Box<dyn Any>>,
+    next_subscription: usize,
+}
+impl EventBus {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn send<T: 'static>(&mut self, event: T) {
+        if let Some(subscribers) = self.subscribers.get_mut(&TypeId::of::<T>()) {
+            for (_, callback) in subscribers.downcast_mut::<Subscribers<T>>().unwrap().iter_mut() {
+                callback(&event);
+            }
+        }
+        self.events_mut::<T>().send(event);
+    }
+    pub fn send_batch<T: 'static>(&mut self, events: impl IntoIterator<Item = T>) {
+        for event in events { self.send(event) }
+    }
+    // called with every event of the type as it is sent, before anyone can read it
+    pub fn subscribe<T: 'static>(&mut self, callback: impl FnMut(&T) + 'static) -> SubscriptionId {
+        let id = SubscriptionId(self.next_subscription);
+        self.next_subscription += 1;
+        self.subscribers.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Subscribers::<T>::new()))
+            .downcast_mut::<Subscribers<T>>().unwrap()
+            .push((id, Box::new(callback)));
+        id
+    }
+    pub fn unsubscribe<T: 'static>(&mut self, id: SubscriptionId) {
+        if let Some(subscribers) = self.subscribers.get_mut(&TypeId::of::<T>()) {
+            subscribers.downcast_mut::<Subscribers<T>>().unwrap().retain(|(s, _)| *s != id);
+        }
+    }
+
+    pub fn events<T: 'static>(&self) -> Option<&Events<T>> {
+        self.queues.get(&TypeId::of::<T>()).map(|q| q.as_any().downcast_ref::<Events<T>>().unwrap())
+    }
+    pub fn events_mut<T: 'static>(&mut self) -> &mut Events<T> {
+        self.queues.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Events::<T>::new()))
+            .as_any_mut().downcast_mut::<Events<T>>().unwrap()
+    }
+    // the events the reader hasn't seen yet
+    pub fn read<'a, T: 'static>(&'a self, reader: &mut EventReader<T>) -> impl Iterator<Item = &'a T> + use<'a, T> {
+        self.events::<T>().map(|events| reader.read(events)).into_iter().flatten()
+    }
+    // everything buffered, this frame and the last
+    pub fn iter<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.events::<T>().into_iter().flat_map(|events| events.iter())
+    }
+
+    // once per frame, before anything is sent
+    pub fn update(&mut self) {
+        for queue in self.queues.values_mut() { queue.update() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::event::{EventBus, EventReader, Events};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Hit(u32);
+
+    #[test]
+    fn events_live_two_frames() {
+        let mut events = Events::new();
+        events.send(1);
+        events.update();
+        events.send(2);
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(events.iter_current().copied().collect::<Vec<_>>(), vec![2]);
+        events.update();
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn readers_see_each_event_once() {
+        let mut events = Events::new();
+        let mut early = EventReader::new();
+        events.send(1);
+        let mut late = EventReader::from_now(&events);
+        events.send(2);
+        assert_eq!(early.read(&events).copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(early.read(&events).count(), 0);
+        events.update();
+        events.send(3);
+        assert_eq!(late.read(&events).copied().collect::<Vec<_>>(), vec![2, 3]);
+        // missed a whole frame, the dropped events are gone
+        events.update();
+        events.update();
+        events.send(4);
+        assert_eq!(early.read(&events).copied().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn bus_keeps_types_apart() {
+        let mut bus = EventBus::new();
+        bus.send(Hit(3));
+        bus.send("door opened");
+        bus.send_batch([Hit(4), Hit(5)]);
+        let mut reader = EventReader::<Hit>::new();
+        assert_eq!(bus.read(&mut reader).cloned().collect::<Vec<_>>(), vec![Hit(3), Hit(4), Hit(5)]);
+        assert_eq!(bus.iter::<&str>().collect::<Vec<_>>(), vec![&"door opened"]);
+        assert_eq!(bus.iter::<u8>().count(), 0);
+        bus.update();
+        bus.update();
+        assert_eq!(bus.iter::<Hit>().count(), 0);
+    }
+
+    #[test]
+    fn immediate_subscribers() {
+        let mut bus = EventBus::new();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let id = {
+            let seen = seen.clone();
+            bus.subscribe(move |hit: &Hit| seen.borrow_mut().push(hit.0))
+        };
+        bus.send(Hit(1));
+        assert_eq!(*seen.borrow(), vec![1]);
+        bus.unsubscribe::<Hit>(id);
+        bus.send(Hit(2));
+        assert_eq!(*seen.borrow(), vec![1]);
+        assert_eq!(bus.iter::<Hit>().count(), 2);
+    }
+}
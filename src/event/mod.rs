@@ -0,0 +1,203 @@
+mod bus;
+
+pub use bus::{EventBus, EventReader, Events, SubscriptionId};
+
+use std::path::PathBuf;
+
+use glfw::{Action, Key, Modifiers, MouseButton, WindowEvent};
+
+use crate::{Error, ecs::Entity, math::Vector3};
+
+// what the game puts on the EventBus every frame: each window Event, each InputEvent that
+// reached input, an ActionEvent for every action that went down or up and a GamepadEvent
+// for every pad plugged in or pulled out.
+// the screenshot and record actions report how they went with a ScreenshotEvent and a RecordingEvent.
+// Collision and AssetLoaded are for whatever detects or loads them, anything else a game
+// sends is just another type on the same bus
+
+// an action from the input map changed this frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionEvent {
+    pub action: String,
+    pub pressed: bool,
+}
+
+// two entities touched, normal points from a to b
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collision {
+    pub a: Entity,
+    pub b: Entity,
+    pub point: Vector3,
+    pub normal: Vector3,
+}
+
+// the screenshot action saved the frame into config.screenshot_dir, or couldn't
+#[derive(Debug, Clone)]
+pub enum ScreenshotEvent {
+    Saved(PathBuf),
+    Failed(Error),
+}
+
+// the record action started a recording, or stopped one and saved it into config.replay_dir.
+// a recording still running on exit is saved too
+#[derive(Debug, Clone)]
+pub enum RecordingEvent {
+    Started,
+    Saved(PathBuf),
+    Failed(Error),
+}
+
+// a file under the asset root finished loading
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetLoaded {
+    pub path: PathBuf,
+}
+
+// everything the window reports, App::on_event gets each of these once.
+// sizes come in two units: the window's in screen coordinates and the framebuffer's in pixels.
+// on HiDPI screens they differ by the content scale, rendering only cares about the framebuffer
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    KeyPressed { key: Key, scancode: i32, modifiers: Modifiers, repeat: bool },
+    KeyReleased { key: Key, scancode: i32, modifiers: Modifiers },
+    MouseButtonPressed { button: MouseButton, modifiers: Modifiers },
+    MouseButtonReleased { button: MouseButton, modifiers: Modifiers },
+    // screen coordinates from the top left of the window's content area
+    CursorMoved { x: f64, y: f64 },
+    CursorEntered,
+    CursorLeft,
+    Scrolled { x: f64, y: f64 },
+    // typed text, after keyboard layouts and dead keys. for text fields, controls should use actions
+    Text(char),
+    // losing focus releases every held button and pauses time (config.pause_on_focus_loss)
+    FocusGained,
+    FocusLost,
+    Minimized,
+    Maximized,
+    // back from minimized or maximized
+    Restored,
+    WindowMoved { x: i32, y: i32 },
+    WindowResized { width: i32, height: i32 },
+    FramebufferResized { width: i32, height: i32 },
+    ContentScaleChanged { x: f32, y: f32 },
+    FilesDropped(Vec<PathBuf>),
+    // the close button or the like. the window closes after this frame unless
+    // the app calls ctx.window.set_should_close(false)
+    CloseRequested,
+}
+impl Event {
+    // refresh and the old char-with-modifiers callback have nothing to say
+    pub fn from_window_event(event: WindowEvent) -> Option<Self> {
+        Some(match event {
+            WindowEvent::Key(key, scancode, Action::Release, modifiers) => Event::KeyReleased { key, scancode, modifiers },
+            WindowEvent::Key(key, scancode, action, modifiers) =>
+                Event::KeyPressed { key, scancode, modifiers, repeat: action == Action::Repeat },
+            WindowEvent::MouseButton(button, Action::Release, modifiers) => Event::MouseButtonReleased { button, modifiers },
+            WindowEvent::MouseButton(button, _, modifiers) => Event::MouseButtonPressed { button, modifiers },
+            WindowEvent::CursorPos(x, y) => Event::CursorMoved { x, y },
+            WindowEvent::CursorEnter(true) => Event::CursorEntered,
+            WindowEvent::CursorEnter(false) => Event::CursorLeft,
+            WindowEvent::Scroll(x, y) => Event::Scrolled { x, y },
+            WindowEvent::Char(c) => Event::Text(c),
+            WindowEvent::Focus(true) => Event::FocusGained,
+            WindowEvent::Focus(false) => Event::FocusLost,
+            WindowEvent::Iconify(true) => Event::Minimized,
+            WindowEvent::Maximize(true) => Event::Maximized,
+            WindowEvent::Iconify(false) | WindowEvent::Maximize(false) => Event::Restored,
+            WindowEvent::Pos(x, y) => Event::WindowMoved { x, y },
+            WindowEvent::Size(width, height) => Event::WindowResized { width, height },
+            WindowEvent::FramebufferSize(width, height) => Event::FramebufferResized { width, height },
+            WindowEvent::ContentScale(x, y) => Event::ContentScaleChanged { x, y },
+            WindowEvent::FileDrop(paths) => Event::FilesDropped(paths),
+            WindowEvent::Close => Event::CloseRequested,
+            WindowEvent::Refresh | WindowEvent::CharModifiers(..) => return None,
+        })
+    }
+}
+
+// the window as of this frame's events, a resource
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowState {
+    pub focused: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    // screen coordinates
+    pub size: (i32, i32),
+    // pixels, 0x0 while minimized
+    pub framebuffer_size: (i32, i32),
+    pub content_scale: (f32, f32),
+}
+impl WindowState {
+    pub fn from_window(window: &glfw::Window) -> Self {
+        Self {
+            focused: window.is_focused(),
+            minimized: window.is_iconified(),
+            maximized: window.is_maximized(),
+            size: window.get_size(),
+            framebuffer_size: window.get_framebuffer_size(),
+            content_scale: window.get_content_scale(),
+        }
+    }
+    pub fn apply(&mut self, event: &Event) {
+        match *event {
+            Event::FocusGained => self.focused = true,
+            Event::FocusLost => self.focused = false,
+            Event::Minimized => self.minimized = true,
+            Event::Maximized => self.maximized = true,
+            Event::Restored => { self.minimized = false; self.maximized = false },
+            Event::WindowResized { width, height } => self.size = (width, height),
+            Event::FramebufferResized { width, height } => self.framebuffer_size = (width, height),
+            Event::ContentScaleChanged { x, y } => self.content_scale = (x, y),
+            _ => {},
+        }
+    }
+    // nothing to draw into
+    pub fn is_visible(&self) -> bool {
+        !self.minimized && self.framebuffer_size.0 > 0 && self.framebuffer_size.1 > 0
+    }
+    pub fn aspect_ratio(&self) -> f32 {
+        self.framebuffer_size.0 as f32/self.framebuffer_size.1.max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glfw::{Action, Key, Modifiers, MouseButton, WindowEvent};
+
+    use crate::event::{Event, WindowState};
+
+    #[test]
+    fn typed_events() {
+        let event = |e| Event::from_window_event(e);
+        assert_eq!(
+            event(WindowEvent::Key(Key::A, 30, Action::Repeat, Modifiers::Shift)),
+            Some(Event::KeyPressed { key: Key::A, scancode: 30, modifiers: Modifiers::Shift, repeat: true })
+        );
+        assert_eq!(
+            event(WindowEvent::MouseButton(MouseButton::Button2, Action::Release, Modifiers::empty())),
+            Some(Event::MouseButtonReleased { button: MouseButton::Button2, modifiers: Modifiers::empty() })
+        );
+        assert_eq!(event(WindowEvent::Char('ä')), Some(Event::Text('ä')));
+        assert_eq!(event(WindowEvent::Iconify(false)), Some(Event::Restored));
+        assert_eq!(event(WindowEvent::FileDrop(vec!["a.png".into()])), Some(Event::FilesDropped(vec!["a.png".into()])));
+        assert_eq!(event(WindowEvent::Close), Some(Event::CloseRequested));
+        assert_eq!(event(WindowEvent::Refresh), None);
+    }
+
+    #[test]
+    fn window_state() {
+        let mut state = WindowState {
+            focused: true, minimized: false, maximized: false,
+            size: (400, 300), framebuffer_size: (800, 600), content_scale: (2.0, 2.0),
+        };
+        assert_eq!(state.aspect_ratio(), 800.0/600.0);
+        for event in [Event::FocusLost, Event::Minimized, Event::FramebufferResized { width: 0, height: 0 }] {
+            state.apply(&event);
+        }
+        assert!(!state.focused && !state.is_visible());
+        state.apply(&Event::Restored);
+        state.apply(&Event::FramebufferResized { width: 1000, height: 500 });
+        assert!(state.is_visible());
+        assert_eq!(state.aspect_ratio(), 2.0);
+    }
+}
@@ -1,41 +1,32 @@
-use crate::math::Vector3;
+use crate::app::{App, Context};
+use crate::ecs::{Entity, Schedule, Stage, System, World};
+use crate::math::{Matrix4x4, Transform, Vector3};
 use crate::renderer::camera::Camera;
-use crate::renderer::objects::StaticMesh;
-use crate::vector;
+use crate::renderer::{dynamic::DynamicMesh, objects::StaticMesh};
+use crate::scene::Scene;
+use crate::config::Config;
+use crate::event::{ActionEvent, Event, EventBus, RecordingEvent, ScreenshotEvent, WindowState};
+use crate::input::{GamepadBackend, Input, InputEvent, Recorder, Recording, Replay, SimulatedGamepads};
+use crate::renderer::framebuffer::{Framebuffer, save_png};
+use crate::time::{Clock, ManualClock, Time};
+use crate::window::Window;
 use crate::{
-    math::{
-        Vector,
-        Quaternion,
-    },
-    renderer::{
-        Renderer,
-        objects::Cube,
-        textures::Texture,
-    },
+    math::Quaternion,
+    renderer::Renderer,
     Error,
 };
 
-use glfw::{Action, Context, GlfwReceiver, Key, WindowEvent, fail_on_errors};
+use glfw::{GlfwReceiver, WindowEvent, fail_on_errors};
 
-use crate::{GAME_NAME, WINDOW_SIZE_X, WINDOW_SIZE_Y};
+use std::cell::RefMut;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-//will change a lot
-#[derive(Default)]
-pub struct Input {
-    pub w: bool,
-    pub a: bool,
-    pub s: bool,
-    pub d: bool,
-    pub space: bool,
-    pub shift: bool,
-
-    pub cursor_diff: (f64, f64),
-}
+use image::RgbaImage;
 
 #[derive(Debug)]
 pub struct Player {
-    world_position: Vector3,
-    pub rotation: Quaternion,
+    pub transform: Transform,
     pub camera: Camera,
     pub speed: f32,
     pub camera_sense: f32
@@ -43,169 +34,458 @@ pub struct Player {
 impl Player {
     pub fn new(position: Vector3, rotation: Quaternion, speed: f32, camera_sense: f32) -> Self {
         Self{
-            world_position: position,
-            rotation,
-            camera: Camera::new(vector!(0.0, 0.0, -2.0), rotation),
+            transform: Transform::from_position_rotation(position, rotation),
+            camera: Camera::new(position, rotation),
             speed,
             camera_sense
         }
     }
 
     fn update(&mut self) {
-        self.camera.set_rotation(self.rotation);
-        self.camera.set_world_position(self.world_position);
+        self.camera.set_rotation(self.transform.rotation);
+        self.camera.set_world_position(self.transform.position);
     }
 
     pub fn translate(&mut self, offset: Vector3) {
-        self.world_position = self.world_position+offset;
+        self.transform.translate(offset);
         self.update();
     }
     pub fn rotate(&mut self, rot: Quaternion) {
-        self.rotation = self.rotation*rot;
+        self.transform.rotate(rot);
         self.update();
     }
 }
 
+// where Time gets the current time from
+enum FrameClock {
+    Real,
+    // headless runs step by a fixed amount every frame so they are reproducible
+    Fixed { clock: ManualClock, step: f64 },
+}
+
+// fields drop in order: everything holding gl resources (world, scene, renderer) comes before
+// the window, so they are deleted while the context is still there
 pub struct Game {
-    pub player: Player,
+    pub player: Entity,
+    pub world: World,
+    pub scene: Scene,
+    pub config: Config,
+    schedule: Schedule,
     renderer: Renderer,
     glfw: glfw::Glfw,
-    window: glfw::PWindow,
+    window: Window,
     events: GlfwReceiver<(f64, WindowEvent)>,
-    meshes: Vec<StaticMesh>,
-    pub delta_time: f64,
-    input: Input,
+    clock: FrameClock,
+    // None polls glfw's joysticks
+    gamepads: Option<Box<dyn GamepadBackend>>,
+    last_cursor: (f64, f64),
+    screenshot_requested: bool,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
+    scripted_input: Vec<InputEvent>,
+    // time was paused by losing focus, not by the app
+    focus_paused: bool,
 }
 impl Game {
     pub fn new(player: Player) -> Self {
+        Self::with_config(player, Config::default()).unwrap()
+    }
+    pub fn with_config(mut player: Player, config: Config) -> Result<Self, Error> {
+        config.validate()?;
+
+        // without a display server use glfws null platform with a software (osmesa) context
+        let no_display = std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none();
+        let offscreen_only = config.headless && no_display;
+        if offscreen_only {
+            glfw::init_hint(glfw::InitHint::Platform(glfw::Platform::Null));
+        }
+
         //create a window
-        let mut glfw = glfw::init(fail_on_errors!()).unwrap();
-        let (mut window, events) = glfw.create_window(
-            WINDOW_SIZE_X, WINDOW_SIZE_Y,
-            GAME_NAME,
-            glfw::WindowMode::Windowed,
-        ).expect("Failed to create GLFW Window.");
-        
-        let renderer = Renderer::init( &mut window);
-
-        let meshes = vec![
-            Cube::new(
-                (0.5, 0.5, 0.5),
-                vector!(0.0, 0.0, 1.0),
-                Quaternion::from_angle_vect(0.0, vector!(1.0, 0.0, 0.0)),
-                vector!(1.0, 1.0, 1.0, 1.0),
-                vec![
-                    Texture::from_file("src/textures/container.jpg").unwrap(),
-                    Texture::from_file("src/textures/awesomeface.png").unwrap()
-                ],
-                gl::DYNAMIC_DRAW
-            ).mesh,
-            Cube::new(
-                (0.5, 0.5, 0.5),
-                vector!(0.0, 1.0, -2.0),
-                Quaternion::from_angle_vect(0.0, vector!(1.0, 0.0, 0.0)),
-                vector!(1.0, 1.0, 1.0, 1.0),
-                vec![
-                    Texture::from_file("src/textures/container.jpg").unwrap(),
-                    Texture::from_file("src/textures/awesomeface.png").unwrap()
-                ],
-                gl::DYNAMIC_DRAW
-            ).mesh
-        ];
-
-        return Self{
-            player,
+        let mut glfw = glfw::init(fail_on_errors!())
+            .map_err(|e| Error::WindowError(format!("couldn't init glfw: {:?}", e)))?;
+        if config.headless {
+            glfw.window_hint(glfw::WindowHint::Visible(false));
+        }
+        if offscreen_only {
+            glfw.window_hint(glfw::WindowHint::ContextCreationApi(glfw::ContextCreationApi::OsMesa));
+        }
+        if config.msaa_samples > 0 {
+            glfw.window_hint(glfw::WindowHint::Samples(Some(config.msaa_samples)));
+        }
+        let (mut window, events) = Window::create(&mut glfw, &config)?;
+
+        let mut renderer = Renderer::init(window.raw(), &config)?;
+        let clock = if config.headless {
+            renderer.set_render_target(Some(Framebuffer::new(config.width as i32, config.height as i32)?));
+            FrameClock::Fixed { clock: ManualClock::default(), step: config.headless_frame_time }
+        } else {
+            FrameClock::Real
+        };
+        player.camera.set_aspect_ratio(config.width as f32/config.height as f32);
+        player.camera.set_fov(config.fov);
+        if let Some(sense) = config.mouse_sensitivity {
+            player.camera_sense = sense;
+        }
+
+        let mut world = World::new();
+        world.insert_resource(Input::new(config.input.clone()));
+        world.insert_resource(Time::default());
+        world.insert_resource(WindowState::from_window(window.raw()));
+        world.insert_resource(EventBus::new());
+        let player_entity = world.spawn();
+        world.insert(player_entity, player).unwrap();
+
+        // headless runs only see the pads they simulate
+        let gamepads = config.headless.then(|| Box::new(SimulatedGamepads::new()) as Box<dyn GamepadBackend>);
+
+        Ok(Self{
+            player: player_entity,
+            world,
+            config,
+            schedule: Schedule::new(),
             renderer,
             glfw,
             window,
             events,
-            meshes,
-            delta_time: 0.0,
-            input: Input::default(),
+            scene: Scene::new(),
+            clock,
+            gamepads,
+            last_cursor: (0.0, 0.0),
+            screenshot_requested: false,
+            recorder: None,
+            replay: None,
+            scripted_input: Vec::new(),
+            focus_paused: false,
+        })
+    }
+    pub fn add_system(&mut self, stage: Stage, system: impl System + 'static) -> &mut Self {
+        self.schedule.add_system(stage, system);
+        self
+    }
+    fn context(&mut self) -> Context<'_> {
+        Context {
+            scene: &mut self.scene,
+            world: &mut self.world,
+            schedule: &mut self.schedule,
+            renderer: &mut self.renderer,
+            window: &mut self.window,
+            config: &mut self.config,
+            player: self.player,
         }
     }
-    pub fn start(&mut self, update: fn(&Input, &mut Player, f32)) -> Result<(), Error> {
-        
-        self.window.make_current();
-        self.window.set_key_polling(true);
-        self.window.set_size_polling(true);
-        
-        self.window.set_cursor_mode(glfw::CursorMode::Disabled);
+    // scripted input for headless runs, takes effect on the next step.
+    // only send_input ends up in recordings
+    pub fn input_mut(&self) -> RefMut<'_, Input> {
+        self.world.resource_mut::<Input>().unwrap()
+    }
+    pub fn send_input(&mut self, event: InputEvent) {
+        self.scripted_input.push(event);
+    }
+    pub fn renderer(&self) -> &Renderer { &self.renderer }
+    // e.g. a SimulatedGamepads the caller keeps a clone of
+    pub fn set_gamepad_backend(&mut self, backend: impl GamepadBackend + 'static) {
+        self.gamepads = Some(Box::new(backend));
+    }
+    // writes the config back to the file it came from, with the current input bindings
+    pub fn save_config(&mut self) -> Result<(), Error> {
+        self.context().save_config()
+    }
 
-        let mut last_time: f64 = 0.0;
-        let mut last_cursor = self.window.get_cursor_pos(); //my code has been getting worse lately
+    pub fn start(&mut self, mut app: impl App) -> Result<(), Error> {
+        self.init(&mut app)?;
         while !self.window.should_close() {
-            let rot = Quaternion::from_angle_vect((self.glfw.get_time() as f32)*10.0, vector!(1.0, 0.0, 1.0));
+            self.step(&mut app)?;
+        }
+        self.exit(&mut app);
+        Ok(())
+    }
+
+    // start does init, step until the window closes, exit
+    // headless code can drive the same three by hand
+    pub fn init(&mut self, app: &mut impl App) -> Result<(), Error> {
+        self.window.make_current();
+        let window = self.window.raw();
+        window.set_key_polling(true);
+        window.set_char_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_cursor_enter_polling(true);
+        window.set_scroll_polling(true);
+        window.set_focus_polling(true);
+        window.set_iconify_polling(true);
+        window.set_maximize_polling(true);
+        window.set_pos_polling(true);
+        window.set_size_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_content_scale_polling(true);
+        window.set_drag_and_drop_polling(true);
+        window.set_close_polling(true);
+
+        if !self.config.headless {
+            self.window.grab_cursor();
+        }
+        self.last_cursor = self.window.cursor_pos(); //my code has been getting worse lately
+
+        app.init(&mut self.context())?;
+        if self.config.record { self.start_recording() }
+        if let Some(path) = self.config.replay.clone() {
+            self.start_replay(Recording::load(path)?);
+        }
+        Ok(())
+    }
+    pub fn step_frames(&mut self, app: &mut impl App, frames: u32) -> Result<(), Error> {
+        for _ in 0..frames { self.step(app)?; }
+        Ok(())
+    }
+    // one whole frame: events, simulation, rendering
+    pub fn step(&mut self, app: &mut impl App) -> Result<(), Error> {
+        self.world.resource_mut::<EventBus>().unwrap().update();
+        let (mut events, mut input_events) = self.process_events();
+        input_events.append(&mut self.scripted_input);
+        input_events.extend(self.poll_devices());
+        let now = match &mut self.clock {
+            FrameClock::Real => self.glfw.now(),
+            FrameClock::Fixed { clock, step } => { clock.advance(*step); clock.now },
+        };
+        // a replay stands in for the clock and every input device, the live ones are ignored
+        let now = match self.next_replay_frame() {
+            Some(frame) => {
+                events.retain(|event| InputEvent::from_window_event(event).is_none());
+                events.extend(frame.events.iter().filter_map(InputEvent::to_window_event));
+                input_events = frame.events;
+                frame.time
+            },
+            None => now,
+        };
+        let events: Vec<Event> = events.into_iter().filter_map(Event::from_window_event).collect();
+        for event in events.iter() {
+            self.window_event(event);
+            app.on_event(&mut self.context(), event);
+        }
+        let mut bus = self.world.resource_mut::<EventBus>().unwrap();
+        bus.send_batch(events);
+        bus.send_batch(input_events.iter().cloned());
+        drop(bus);
+        if let Some(recorder) = &mut self.recorder { recorder.record(now, &input_events) }
+        for event in input_events.iter() {
+            if let InputEvent::Focus(focused) = event { self.focus_changed(*focused) }
+        }
 
-            for mesh in self.meshes.iter_mut() { mesh.set_rotation(rot);}
+        let mut time = self.world.resource_mut::<Time>().unwrap();
+        time.advance_to(now);
+        let frame_time = time.unscaled_delta();
+        drop(time);
 
-            self.process_events();
-            self.renderer.render(&self.meshes, &mut self.player)?;
-            self.window.swap_buffers();
+        let mut input = self.world.resource_mut::<Input>().unwrap();
+        for event in input_events.iter() { input.apply(event) }
+        input.update(frame_time);
+        let actions: Vec<ActionEvent> = input.actions()
+            .filter(|(_, state)| state.just_pressed || state.just_released)
+            .map(|(name, state)| ActionEvent { action: name.to_string(), pressed: state.just_pressed })
+            .collect();
+        let gamepad_events = input.gamepad_events().to_vec();
+        drop(input);
+        let mut bus = self.world.resource_mut::<EventBus>().unwrap();
+        bus.send_batch(actions);
+        bus.send_batch(gamepad_events);
+        drop(bus);
+        self.engine_actions();
 
-            let current_time = self.glfw.get_time();
-            self.delta_time = last_time-current_time;
-            last_time = current_time;
+        self.schedule.run_stage(Stage::Input, &mut self.world);
+        while self.world.resource_mut::<Time>().unwrap().step_fixed() {
+            app.fixed_update(&mut self.context());
+            self.schedule.run_stage(Stage::Physics, &mut self.world);
+        }
+        self.schedule.run_stage(Stage::Update, &mut self.world);
+        app.update(&mut self.context());
+        self.schedule.run_stage(Stage::RenderExtract, &mut self.world);
+
+        self.scene.update_transforms();
+        // a minimized window has a 0x0 framebuffer
+        if self.config.headless || self.world.resource::<WindowState>().unwrap().is_visible() {
+            self.render()?;
+        }
+        app.render_ui(&mut self.context());
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            let event = match self.save_screenshot() {
+                Ok(path) => ScreenshotEvent::Saved(path),
+                Err(e) => ScreenshotEvent::Failed(e),
+            };
+            self.world.resource_mut::<EventBus>().unwrap().send(event);
+        }
+        self.window.swap_buffers();
+        Ok(())
+    }
+    // last rendered frame, valid between steps in headless mode
+    pub fn screenshot(&self) -> Result<RgbaImage, Error> {
+        self.renderer.screenshot()
+    }
+    // saves into config.screenshot_dir as screenshot_<unix seconds>_<millis>.png
+    pub fn save_screenshot(&self) -> Result<PathBuf, Error> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let path = PathBuf::from(&self.config.screenshot_dir)
+            .join(format!("screenshot_{}_{:03}.png", now.as_secs(), now.subsec_millis()));
+        save_png(&self.screenshot()?, &path)?;
+        Ok(path)
+    }
+    pub fn exit(&mut self, app: &mut impl App) {
+        // saved first, so on_exit can see how it went
+        if self.is_recording() { self.toggle_recording() }
+        app.on_exit(&mut self.context());
+    }
 
-            let current_cursor = self.window.get_cursor_pos();
-            self.input.cursor_diff = (current_cursor.0-last_cursor.0, current_cursor.1-last_cursor.1);
-            last_cursor = current_cursor;
+    // records every frame from the next one on, with the current bindings and fixed step
+    pub fn start_recording(&mut self) {
+        let fixed_step = self.world.resource::<Time>().unwrap().fixed_step();
+        let map = self.world.resource::<Input>().unwrap().map().clone();
+        self.recorder = Some(Recorder::new(fixed_step, map));
+    }
+    pub fn is_recording(&self) -> bool { self.recorder.is_some() }
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recorder.take().map(Recorder::finish)
+    }
+    // saves into config.replay_dir as replay_<unix seconds>_<millis>.json
+    pub fn save_recording(&self, recording: &Recording) -> Result<PathBuf, Error> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let path = PathBuf::from(&self.config.replay_dir)
+            .join(format!("replay_{}_{:03}.json", now.as_secs(), now.subsec_millis()));
+        recording.save(&path)?;
+        Ok(path)
+    }
+    fn toggle_recording(&mut self) {
+        let event = match self.stop_recording() {
+            None => { self.start_recording(); RecordingEvent::Started },
+            Some(recording) => match self.save_recording(&recording) {
+                Ok(path) => RecordingEvent::Saved(path),
+                Err(e) => RecordingEvent::Failed(e),
+            },
+        };
+        self.world.resource_mut::<EventBus>().unwrap().send(event);
+    }
 
-            update(&self.input, &mut self.player, self.delta_time as f32);
+    // plays the recording instead of live input from the next step on.
+    // bindings and fixed step go back to what they were while recording,
+    // the rest of the game state is up to the caller, a replay only matches if it starts where the recording did
+    pub fn start_replay(&mut self, recording: Recording) {
+        self.world.insert_resource(Input::new(recording.input.clone()));
+        self.world.resource_mut::<Time>().unwrap().set_fixed_step(recording.fixed_step);
+        self.replay = Some(Replay::new(recording));
+    }
+    pub fn is_replaying(&self) -> bool { self.replay.is_some() }
+    // steps through the whole recording, then live input takes over again
+    pub fn replay(&mut self, app: &mut impl App, recording: Recording) -> Result<(), Error> {
+        self.start_replay(recording);
+        while self.replay.as_ref().is_some_and(|replay| !replay.is_finished()) {
+            self.step(app)?;
+        }
+        self.end_replay();
+        Ok(())
+    }
+    fn next_replay_frame(&mut self) -> Option<crate::input::replay::Frame> {
+        let frame = self.replay.as_mut()?.next_frame();
+        if frame.is_none() { self.end_replay() }
+        frame
+    }
+    fn end_replay(&mut self) {
+        let Some(replay) = self.replay.take() else { return };
+        // a fixed clock carries on from where the recording stopped instead of going back in time
+        if let (FrameClock::Fixed { clock, .. }, Some(last)) = (&mut self.clock, replay.recording().frames.last()) {
+            clock.now = last.time;
         }
-        
-        return Ok(())
     }
 
-    fn process_events(&mut self) {
+    // scene meshes plus every entity with a StaticMesh or DynamicMesh, seen from the scenes camera or the players
+    fn render(&mut self) -> Result<(), Error> {
+        let player = self.world.get::<Player>(self.player);
+        let camera = match (self.scene.active_camera(), &player) {
+            (Some(camera), _) => camera,
+            (None, Some(player)) => &player.camera,
+            (None, None) => return Err(Error::EntityError("no camera to render with")),
+        };
+        self.renderer.render(&self.scene, camera)?;
+        self.world.query::<(&StaticMesh, Option<&Transform>)>(|_, (mesh, transform)| {
+            self.renderer.draw_mesh(mesh, transform.map_or(Matrix4x4::IDENTITY, |t| t.to_matrix()));
+        });
+        self.world.query::<(&DynamicMesh, Option<&Transform>)>(|_, (mesh, transform)| {
+            self.renderer.draw_dynamic(mesh, transform.map_or(Matrix4x4::IDENTITY, |t| t.to_matrix()));
+        });
+        // a mesh that doesn't fit is skipped from then on, this frame says why
+        self.renderer.take_layout_error().map_or(Ok(()), Err)
+    }
+
+    // quit, wireframe, release_cursor, fullscreen, screenshot and record are ordinary actions,
+    // so they can be rebound too
+    fn engine_actions(&mut self) {
+        let input = self.world.resource::<Input>().unwrap();
+        if input.just_pressed("quit") { self.window.set_should_close(true) }
+        if input.just_pressed("wireframe") { self.renderer.switch_wireframe() }
+        // releases the cursor, or grabs it again
+        if input.just_pressed("release_cursor") && !self.config.headless { self.window.toggle_cursor_grab() }
+        if input.just_pressed("fullscreen") && !self.config.headless { self.window.toggle_fullscreen() }
+        if input.just_pressed("screenshot") { self.screenshot_requested = true }
+        // a replayed press of the hotkey doesn't start another recording
+        let record = input.just_pressed("record") && !self.is_replaying();
+        drop(input);
+        if record { self.toggle_recording() }
+    }
+
+    // every event so the app can see them too, and the ones meant for input
+    fn process_events(&mut self) -> (Vec<WindowEvent>, Vec<InputEvent>) {
         self.glfw.poll_events();
+        let mut events = Vec::new();
+        let mut input_events = Vec::new();
         for (_, event) in glfw::flush_messages(&self.events) {
-            match event {
-                glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                    self.window.set_should_close(true)
-                },
-                glfw::WindowEvent::Key(Key::F1, _, Action::Press, _) => {
-                    self.renderer.switch_wireframe()
-                },
-
-                glfw::WindowEvent::Key(Key::F2, _, Action::Press, _) => {
-                    self.window.set_cursor_mode(glfw::CursorMode::Normal);
-                },
-                glfw::WindowEvent::Key(Key::F1, _, Action::Release, _) => {
-                    self.window.set_cursor_mode(glfw::CursorMode::Disabled);
-                },
-
-                glfw::WindowEvent::Size(x, y) => {
-                    self.player.camera.set_aspect_ratio(x as f32/y as f32);
-                    self.renderer.resize(x, y)
-                },
-
-                glfw::WindowEvent::Key(key, _, action, _) => {
-                    match (key, action) {
-                        (Key::W, Action::Press) => self.input.w = true,
-                        (Key::W, Action::Release) => self.input.w = false,
-
-                        (Key::S, Action::Press) => self.input.s = true,
-                        (Key::S, Action::Release) => self.input.s = false,
-
-                        (Key::A, Action::Press) => self.input.a = true,
-                        (Key::A, Action::Release) => self.input.a = false,
-
-                        (Key::D, Action::Press) => self.input.d = true,
-                        (Key::D, Action::Release) => self.input.d = false,
-
-                        (Key::Space, Action::Press) => self.input.space = true,
-                        (Key::Space, Action::Release) => self.input.space = false,
-
-                        (Key::LeftShift, Action::Press) => self.input.shift = true,
-                        (Key::LeftShift, Action::Release) => self.input.shift = false,
-
-                        _ => {},
-                    }
-                }
-
-                _ => {},
+            input_events.extend(InputEvent::from_window_event(&event));
+            events.push(event);
+        }
+        (events, input_events)
+    }
+    fn window_event(&mut self, event: &Event) {
+        let mut state = self.world.resource_mut::<WindowState>().unwrap();
+        state.apply(event);
+        let aspect_ratio = state.aspect_ratio();
+        drop(state);
+        if *event == Event::FocusGained { self.window.apply_cursor() }
+        // rendering goes by pixels, on HiDPI screens the window size would be too small
+        if let Event::FramebufferResized { width, height } = *event {
+            // minimizing shrinks it to 0x0, keep the old size until it comes back
+            if width == 0 || height == 0 { return }
+            if let Some(mut player) = self.world.get_mut::<Player>(self.player) {
+                player.camera.set_aspect_ratio(aspect_ratio);
+            }
+            if let Some(camera) = self.scene.active_camera_mut() { camera.set_aspect_ratio(aspect_ratio); }
+            self.renderer.resize(width, height)
+        }
+    }
+    // held buttons were already released by input, time only resumes if focus paused it
+    fn focus_changed(&mut self, focused: bool) {
+        let mut time = self.world.resource_mut::<Time>().unwrap();
+        if !focused && self.config.pause_on_focus_loss && !time.is_paused() {
+            time.pause();
+            self.focus_paused = true;
+        } else if focused && self.focus_paused {
+            time.resume();
+            self.focus_paused = false;
+        }
+    }
+    // cursor movement since the last frame and the state of every gamepad
+    fn poll_devices(&mut self) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        // a hidden window has no real cursor, headless cursor movement comes from the script.
+        // a released cursor is on its way to other windows, not looking around
+        if !self.config.headless {
+            let current_cursor = self.window.cursor_pos();
+            if self.window.cursor_grabbed() {
+                events.push(InputEvent::MouseMotion(current_cursor.0-self.last_cursor.0, current_cursor.1-self.last_cursor.1));
             }
+            self.last_cursor = current_cursor;
         }
+        let pads = match &mut self.gamepads {
+            Some(backend) => backend.poll(),
+            None => self.glfw.poll(),
+        };
+        events.push(InputEvent::Gamepads(pads));
+        events
     }
 }
\ No newline at end of file
@@ -0,0 +1,285 @@
+use std::{path::{Path, PathBuf}, rc::Rc};
+
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    Error,
+    app::{App, Context},
+    config::Config,
+    game::{Game, Player},
+    math::{Color, Quaternion, Transform, Vector3},
+    renderer::{framebuffer::save_png, objects::{Cube, Rectangle, StaticMesh, Triangle}, textures::Texture},
+};
+
+// golden image tests: render a described scene headless, compare it with a stored png
+// references live in tests/golden (GOLDEN_DIR overrides), UPDATE_GOLDEN=1 rewrites them,
+// failures write <name>.actual.png and <name>.diff.png into target/golden
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Triangle((f32, f32)),
+    Rectangle((f32, f32)),
+    Cube((f32, f32, f32)),
+}
+
+#[derive(Debug, Clone)]
+pub struct MeshDescription {
+    pub shape: Shape,
+    pub transform: Transform,
+    pub color: Color,
+    // relative to the asset root
+    pub textures: Vec<&'static str>,
+}
+impl MeshDescription {
+    fn build(&self, config: &Config) -> Result<StaticMesh, Error> {
+        let textures = self.textures.iter()
+            .map(|path| Texture::from_file(config.asset(path)).map(Rc::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (transform, color) = (Transform::IDENTITY, self.color);
+        Ok(match self.shape {
+            Shape::Triangle(size) => Triangle::new(size, transform, color, textures, gl::STATIC_DRAW).mesh,
+            Shape::Rectangle(size) => Rectangle::new(size, transform, color, textures, gl::STATIC_DRAW).mesh,
+            Shape::Cube(size) => Cube::new(size, transform, color, textures, gl::STATIC_DRAW).mesh,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneDescription {
+    pub width: u32,
+    pub height: u32,
+    pub frames: u32,
+    pub camera_position: Vector3,
+    pub camera_rotation: Quaternion,
+    pub fov: f32,
+    pub meshes: Vec<MeshDescription>,
+}
+impl SceneDescription {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            frames: 1,
+            camera_position: Vector3::new([0.0, 0.0, 0.0]),
+            camera_rotation: Quaternion::IDENTITY,
+            fov: 45.0,
+            meshes: Vec::new(),
+        }
+    }
+    pub fn with_camera(mut self, position: Vector3, rotation: Quaternion, fov: f32) -> Self {
+        self.camera_position = position;
+        self.camera_rotation = rotation;
+        self.fov = fov;
+        self
+    }
+    pub fn with_mesh(mut self, mesh: MeshDescription) -> Self {
+        self.meshes.push(mesh);
+        self
+    }
+    pub fn with_frames(mut self, frames: u32) -> Self {
+        self.frames = frames;
+        self
+    }
+}
+
+struct GoldenScene<'a>(&'a SceneDescription);
+impl App for GoldenScene<'_> {
+    fn init(&mut self, ctx: &mut Context) -> Result<(), Error> {
+        for (i, mesh) in self.0.meshes.iter().enumerate() {
+            ctx.scene.add_mesh(&format!("mesh{}", i), mesh.transform, mesh.build(ctx.config)?);
+        }
+        Ok(())
+    }
+}
+
+// glfw wants the main thread, run golden tests one at a time (--test-threads=1)
+pub fn render(desc: &SceneDescription) -> Result<RgbaImage, Error> {
+    let player = Player::new(desc.camera_position, desc.camera_rotation, 0.0, 0.0);
+    let config = Config { fov: desc.fov, ..Config::headless(desc.width, desc.height) };
+    let mut game = Game::with_config(player, config)?;
+    let mut app = GoldenScene(desc);
+    game.init(&mut app)?;
+    game.step_frames(&mut app, desc.frames.max(1))?;
+    let img = game.screenshot();
+    game.exit(&mut app);
+    img
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance {
+    // a pixel only counts as different if a channel is off by more than this
+    pub channel: u8,
+    // ...and the perceived color difference is above this, 0..1
+    pub perceptual: f32,
+    // share of pixels that may differ before the comparison fails
+    pub max_mismatch_ratio: f32,
+}
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self { channel: 2, perceptual: 0.01, max_mismatch_ratio: 0.001 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub mismatched: usize,
+    pub total: usize,
+    pub max_channel_diff: u8,
+    pub max_perceptual_diff: f32,
+    // red where pixels differ, faded reference everywhere else
+    pub diff: RgbaImage,
+}
+impl Comparison {
+    pub fn mismatch_ratio(&self) -> f32 { self.mismatched as f32 / self.total.max(1) as f32 }
+    pub fn passes(&self, tolerance: &Tolerance) -> bool {
+        self.mismatch_ratio() <= tolerance.max_mismatch_ratio
+    }
+}
+
+// yiq based color distance (same weights as pixelmatch), alpha blended onto white, 0..1
+pub fn perceptual_diff(a: Rgba<u8>, b: Rgba<u8>) -> f32 {
+    fn yiq(p: Rgba<u8>) -> (f32, f32, f32) {
+        let alpha = p.0[3] as f32 / 255.0;
+        let [r, g, b] = [0, 1, 2].map(|i| 255.0 + (p.0[i] as f32 - 255.0)*alpha);
+        (
+            r*0.298_895_3 + g*0.586_622_5 + b*0.114_482_23,
+            r*0.595_978 - g*0.274_176_1 - b*0.321_801_9,
+            r*0.211_470_2 - g*0.522_617_1 + b*0.311_146_94,
+        )
+    }
+    let (a, b) = (yiq(a), yiq(b));
+    let (y, i, q) = (a.0-b.0, a.1-b.1, a.2-b.2);
+    // largest possible value over all color pairs
+    const MAX: f32 = 35215.0;
+    (0.5053*y*y + 0.299*i*i + 0.1957*q*q) / MAX
+}
+
+pub fn compare(actual: &RgbaImage, expected: &RgbaImage, tolerance: &Tolerance) -> Result<Comparison, Error> {
+    if actual.dimensions() != expected.dimensions() {
+        return Err(Error::GoldenError(format!(
+            "size differs, got {:?} expected {:?}", actual.dimensions(), expected.dimensions()
+        )))
+    }
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let (mut mismatched, mut max_channel_diff, mut max_perceptual_diff) = (0, 0, 0.0_f32);
+    for ((a, e), d) in actual.pixels().zip(expected.pixels()).zip(diff.pixels_mut()) {
+        let channel = (0..4).map(|i| a.0[i].abs_diff(e.0[i])).max().unwrap();
+        let perceptual = perceptual_diff(*a, *e);
+        max_channel_diff = max_channel_diff.max(channel);
+        max_perceptual_diff = max_perceptual_diff.max(perceptual);
+
+        if channel > tolerance.channel && perceptual > tolerance.perceptual {
+            mismatched += 1;
+            *d = Rgba([255, 0, 0, 255]);
+        } else {
+            let gray = (e.0[0] as u32 + e.0[1] as u32 + e.0[2] as u32) / 3;
+            let faded = (255 - (255 - gray)/4) as u8;
+            *d = Rgba([faded, faded, faded, 255]);
+        }
+    }
+    Ok(Comparison {
+        mismatched,
+        total: (actual.width()*actual.height()) as usize,
+        max_channel_diff,
+        max_perceptual_diff,
+        diff,
+    })
+}
+
+fn reference_dir() -> PathBuf {
+    std::env::var_os("GOLDEN_DIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("tests/golden"))
+}
+fn output_dir() -> PathBuf { PathBuf::from("target/golden") }
+
+pub fn assert_golden(name: &str, actual: &RgbaImage, tolerance: &Tolerance) -> Result<(), Error> {
+    let reference = reference_dir().join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        return save_png(actual, &reference)
+    }
+    let expected = load_png(&reference)?;
+
+    let comparison = compare(actual, &expected, tolerance);
+    let failure = match &comparison {
+        Ok(c) if c.passes(tolerance) => return Ok(()),
+        Ok(c) => format!(
+            "{}: {} of {} pixels differ ({:.3}%), max channel diff {}, max perceptual diff {:.4}",
+            name, c.mismatched, c.total, c.mismatch_ratio()*100.0, c.max_channel_diff, c.max_perceptual_diff
+        ),
+        Err(e) => format!("{}: {:?}", name, e),
+    };
+
+    let out = output_dir();
+    save_png(actual, out.join(format!("{}.actual.png", name)))?;
+    if let Ok(c) = &comparison {
+        save_png(&c.diff, out.join(format!("{}.diff.png", name)))?;
+    }
+    Err(Error::GoldenError(format!("{}, see {}", failure, out.display())))
+}
+
+fn load_png(path: &Path) -> Result<RgbaImage, Error> {
+    match image::open(path) {
+        Ok(img) => Ok(img.to_rgba8()),
+        Err(e) => Err(Error::GoldenError(format!(
+            "couldn't load reference {}: {} (run with UPDATE_GOLDEN=1 to create it)", path.display(), e
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::{Rgba, RgbaImage};
+
+    use crate::golden::{Tolerance, compare, perceptual_diff};
+
+    #[test]
+    fn perceptual_range() {
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        assert_eq!(perceptual_diff(black, black), 0.0);
+        let extreme = perceptual_diff(black, white);
+        assert!(extreme > 0.9 && extreme <= 1.0);
+        // transparent is white once blended
+        assert!(perceptual_diff(Rgba([0, 0, 0, 0]), white) < 1e-6);
+        // a small hue shift reads as a small difference
+        assert!(perceptual_diff(Rgba([200, 10, 10, 255]), Rgba([205, 10, 10, 255])) < 0.01);
+    }
+
+    #[test]
+    fn identical_images_pass() {
+        let img = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        let c = compare(&img, &img, &Tolerance::default()).unwrap();
+        assert_eq!(c.mismatched, 0);
+        assert_eq!(c.max_channel_diff, 0);
+        assert!(c.passes(&Tolerance::default()));
+    }
+
+    #[test]
+    fn small_noise_is_tolerated() {
+        let a = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let b = RgbaImage::from_pixel(4, 4, Rgba([101, 99, 102, 255]));
+        let c = compare(&a, &b, &Tolerance::default()).unwrap();
+        assert_eq!(c.mismatched, 0);
+        assert_eq!(c.max_channel_diff, 2);
+    }
+
+    #[test]
+    fn changed_pixels_fail_and_show_in_diff() {
+        let expected = RgbaImage::from_pixel(10, 10, Rgba([0, 0, 0, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(3, 4, Rgba([255, 255, 255, 255]));
+        let c = compare(&actual, &expected, &Tolerance::default()).unwrap();
+        assert_eq!(c.mismatched, 1);
+        assert_eq!(c.total, 100);
+        assert!(!c.passes(&Tolerance::default()));
+        assert!(c.passes(&Tolerance { max_mismatch_ratio: 0.01, ..Tolerance::default() }));
+        assert_eq!(c.diff.get_pixel(3, 4), &Rgba([255, 0, 0, 255]));
+        assert_ne!(c.diff.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn size_mismatch_is_an_error() {
+        let a = RgbaImage::new(2, 2);
+        let b = RgbaImage::new(2, 3);
+        assert!(compare(&a, &b, &Tolerance::default()).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use glfw::{Action, GamepadAxis, GamepadButton, JoystickId};
+use serde::{Deserialize, Serialize};
+
+pub const BUTTON_COUNT: usize = 15;
+pub const AXIS_COUNT: usize = 6;
+
+pub const BUTTONS: [GamepadButton; BUTTON_COUNT] = [
+    GamepadButton::ButtonA, GamepadButton::ButtonB, GamepadButton::ButtonX, GamepadButton::ButtonY,
+    GamepadButton::ButtonLeftBumper, GamepadButton::ButtonRightBumper,
+    GamepadButton::ButtonBack, GamepadButton::ButtonStart, GamepadButton::ButtonGuide,
+    GamepadButton::ButtonLeftThumb, GamepadButton::ButtonRightThumb,
+    GamepadButton::ButtonDpadUp, GamepadButton::ButtonDpadRight, GamepadButton::ButtonDpadDown, GamepadButton::ButtonDpadLeft,
+];
+pub const AXES: [GamepadAxis; AXIS_COUNT] = [
+    GamepadAxis::AxisLeftX, GamepadAxis::AxisLeftY,
+    GamepadAxis::AxisRightX, GamepadAxis::AxisRightY,
+    GamepadAxis::AxisLeftTrigger, GamepadAxis::AxisRightTrigger,
+];
+
+// slot number, glfw has 16 (Joystick1 is 0)
+pub type GamepadId = usize;
+
+// one gamepad in glfw's standard layout. sticks are -1..1 with y pointing down,
+// triggers -1 (released) to 1 as glfw reports them, until GamepadSettings::apply maps them to 0..1
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PadState {
+    pub buttons: [bool; BUTTON_COUNT],
+    pub axes: [f32; AXIS_COUNT],
+}
+impl PadState {
+    // a pad at rest, triggers released
+    pub fn neutral() -> Self {
+        let mut state = Self::default();
+        state.axes[GamepadAxis::AxisLeftTrigger as usize] = -1.0;
+        state.axes[GamepadAxis::AxisRightTrigger as usize] = -1.0;
+        state
+    }
+    pub fn button(&self, button: GamepadButton) -> bool { self.buttons[button as usize] }
+    pub fn axis(&self, axis: GamepadAxis) -> f32 { self.axes[axis as usize] }
+}
+
+// where gamepads come from, glfw in the game and SimulatedGamepads in tests
+pub trait GamepadBackend {
+    // every slot with a gamepad in it, with its name and current state
+    fn poll(&mut self) -> Vec<(GamepadId, String, PadState)>;
+}
+impl GamepadBackend for glfw::Glfw {
+    fn poll(&mut self) -> Vec<(GamepadId, String, PadState)> {
+        (0..16).filter_map(|i| {
+            let joystick = self.get_joystick(JoystickId::from_i32(i)?);
+            // joysticks without a mapping in glfw's gamepad database are left out
+            if !joystick.is_present() || !joystick.is_gamepad() { return None }
+            let state = joystick.get_gamepad_state()?;
+            let mut pad = PadState::default();
+            for (i, button) in BUTTONS.iter().enumerate() {
+                pad.buttons[i] = state.get_button_state(*button) == Action::Press;
+            }
+            for (i, axis) in AXES.iter().enumerate() {
+                pad.axes[i] = state.get_axis(*axis);
+            }
+            let name = joystick.get_gamepad_name().unwrap_or_default();
+            Some((i as GamepadId, name, pad))
+        }).collect()
+    }
+}
+
+// gamepads without hardware. clones share the same pads, so a test can keep one
+// and hand the other to the game
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedGamepads {
+    pads: Rc<RefCell<BTreeMap<GamepadId, (String, PadState)>>>,
+}
+impl SimulatedGamepads {
+    pub fn new() -> Self { Self::default() }
+    pub fn connect(&self, id: GamepadId, name: &str) {
+        self.pads.borrow_mut().insert(id, (name.to_string(), PadState::neutral()));
+    }
+    pub fn disconnect(&self, id: GamepadId) {
+        self.pads.borrow_mut().remove(&id);
+    }
+    pub fn set_button(&self, id: GamepadId, button: GamepadButton, down: bool) {
+        if let Some((_, pad)) = self.pads.borrow_mut().get_mut(&id) { pad.buttons[button as usize] = down; }
+    }
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        if let Some((_, pad)) = self.pads.borrow_mut().get_mut(&id) { pad.axes[axis as usize] = value.clamp(-1.0, 1.0); }
+    }
+}
+impl GamepadBackend for SimulatedGamepads {
+    fn poll(&mut self) -> Vec<(GamepadId, String, PadState)> {
+        self.pads.borrow().iter().map(|(id, (name, pad))| (*id, name.clone(), *pad)).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId, String),
+    Disconnected(GamepadId),
+}
+
+// how raw stick and trigger values turn into what actions and axes see, part of config.input
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GamepadSettings {
+    // radial, sticks closer to the center than this read as centered
+    pub stick_dead_zone: f32,
+    // output = input^curve after the dead zone, 1 is linear, 2 gives finer control near the center
+    pub stick_curve: f32,
+    pub trigger_dead_zone: f32,
+    pub trigger_curve: f32,
+    // triggers bound as buttons count as pressed past this
+    pub trigger_threshold: f32,
+}
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            stick_dead_zone: 0.15,
+            stick_curve: 1.0,
+            trigger_dead_zone: 0.05,
+            trigger_curve: 1.0,
+            trigger_threshold: 0.5,
+        }
+    }
+}
+impl GamepadSettings {
+    // rescales past the dead zone so values still start at 0 and reach 1
+    fn shape(value: f32, dead_zone: f32, curve: f32) -> f32 {
+        if value <= dead_zone { return 0.0 }
+        ((value - dead_zone) / (1.0 - dead_zone)).min(1.0).powf(curve)
+    }
+    fn stick(&self, x: f32, y: f32) -> (f32, f32) {
+        let length = (x*x + y*y).sqrt();
+        if length == 0.0 { return (0.0, 0.0) }
+        let scale = Self::shape(length, self.stick_dead_zone, self.stick_curve) / length;
+        (x*scale, y*scale)
+    }
+    pub fn apply(&self, raw: &PadState) -> PadState {
+        let mut pad = *raw;
+        for (x, y) in [
+            (GamepadAxis::AxisLeftX as usize, GamepadAxis::AxisLeftY as usize),
+            (GamepadAxis::AxisRightX as usize, GamepadAxis::AxisRightY as usize),
+        ] {
+            (pad.axes[x], pad.axes[y]) = self.stick(raw.axes[x], raw.axes[y]);
+        }
+        for trigger in [GamepadAxis::AxisLeftTrigger as usize, GamepadAxis::AxisRightTrigger as usize] {
+            let value = (raw.axes[trigger] + 1.0) / 2.0;
+            pad.axes[trigger] = Self::shape(value, self.trigger_dead_zone, self.trigger_curve);
+        }
+        pad
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, dead_zone) in [("stick_dead_zone", self.stick_dead_zone), ("trigger_dead_zone", self.trigger_dead_zone)] {
+            if !(0.0..1.0).contains(&dead_zone) { return Err(format!("{} must be in 0..1, got {}", name, dead_zone)) }
+        }
+        for (name, curve) in [("stick_curve", self.stick_curve), ("trigger_curve", self.trigger_curve)] {
+            if !(curve > 0.0 && curve.is_finite()) { return Err(format!("{} must be positive, got {}", name, curve)) }
+        }
+        if !(0.0..=1.0).contains(&self.trigger_threshold) {
+            return Err(format!("trigger_threshold must be in 0..1, got {}", self.trigger_threshold))
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gamepad {
+    pub name: String,
+    pub raw: PadState,
+    // after dead zones and curves
+    pub state: PadState,
+}
+
+// which pads are connected, reports plugging and unplugging between polls
+#[derive(Debug, Clone, Default)]
+pub struct Gamepads {
+    pads: BTreeMap<GamepadId, Gamepad>,
+}
+impl Gamepads {
+    pub fn poll(&mut self, backend: &mut dyn GamepadBackend, settings: &GamepadSettings) -> Vec<GamepadEvent> {
+        self.update(backend.poll(), settings)
+    }
+    // what a backend returned, polled elsewhere or read back from a recording
+    pub fn update(&mut self, polled: Vec<(GamepadId, String, PadState)>, settings: &GamepadSettings) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        self.pads.retain(|id, _| {
+            let present = polled.iter().any(|(polled_id, _, _)| polled_id == id);
+            if !present { events.push(GamepadEvent::Disconnected(*id)) }
+            present
+        });
+        for (id, name, raw) in polled {
+            let state = settings.apply(&raw);
+            if !self.pads.contains_key(&id) { events.push(GamepadEvent::Connected(id, name.clone())) }
+            self.pads.insert(id, Gamepad { name, raw, state });
+        }
+        events
+    }
+    pub fn get(&self, id: GamepadId) -> Option<&Gamepad> { self.pads.get(&id) }
+    pub fn iter(&self) -> impl Iterator<Item = (GamepadId, &Gamepad)> { self.pads.iter().map(|(id, pad)| (*id, pad)) }
+    pub fn len(&self) -> usize { self.pads.len() }
+    pub fn is_empty(&self) -> bool { self.pads.is_empty() }
+
+    // bindings don't say which pad, any of them will do
+    pub fn any_button(&self, button: GamepadButton) -> bool {
+        self.pads.values().any(|pad| pad.state.button(button))
+    }
+    // the pad pushed furthest wins
+    pub fn any_axis(&self, axis: GamepadAxis) -> f32 {
+        self.pads.values()
+            .map(|pad| pad.state.axis(axis))
+            .fold(0.0, |best, v| if v.abs() > best.abs() { v } else { best })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use glfw::{GamepadAxis, GamepadButton};
+
+    use crate::input::gamepad::{GamepadEvent, GamepadSettings, Gamepads, PadState, SimulatedGamepads};
+
+    fn approx_eq(a: f32, b: f32) -> bool { (a-b).abs() < 1e-5 }
+
+    #[test]
+    fn hot_plug() {
+        let sim = SimulatedGamepads::new();
+        let mut backend = sim.clone();
+        let mut pads = Gamepads::default();
+        let settings = GamepadSettings::default();
+        assert!(pads.poll(&mut backend, &settings).is_empty());
+
+        sim.connect(0, "pad");
+        assert_eq!(pads.poll(&mut backend, &settings), vec![GamepadEvent::Connected(0, "pad".to_string())]);
+        assert!(pads.poll(&mut backend, &settings).is_empty());
+        assert_eq!(pads.len(), 1);
+
+        sim.disconnect(0);
+        sim.connect(3, "other");
+        assert_eq!(pads.poll(&mut backend, &settings), vec![
+            GamepadEvent::Disconnected(0),
+            GamepadEvent::Connected(3, "other".to_string()),
+        ]);
+        assert_eq!(pads.get(3).unwrap().name, "other");
+    }
+
+    #[test]
+    fn stick_dead_zone_is_radial() {
+        let settings = GamepadSettings { stick_dead_zone: 0.2, ..GamepadSettings::default() };
+        let mut raw = PadState::neutral();
+        raw.axes[GamepadAxis::AxisLeftX as usize] = 0.1;
+        raw.axes[GamepadAxis::AxisLeftY as usize] = 0.1;
+        let pad = settings.apply(&raw);
+        assert_eq!(pad.axis(GamepadAxis::AxisLeftX), 0.0);
+
+        // rescaled so the edge of the dead zone is 0 and full tilt is still 1
+        raw.axes[GamepadAxis::AxisLeftX as usize] = 0.6;
+        raw.axes[GamepadAxis::AxisLeftY as usize] = 0.0;
+        assert!(approx_eq(settings.apply(&raw).axis(GamepadAxis::AxisLeftX), 0.5));
+        raw.axes[GamepadAxis::AxisLeftX as usize] = -1.0;
+        assert!(approx_eq(settings.apply(&raw).axis(GamepadAxis::AxisLeftX), -1.0));
+    }
+
+    #[test]
+    fn curves() {
+        let settings = GamepadSettings { stick_dead_zone: 0.0, stick_curve: 2.0, ..GamepadSettings::default() };
+        let mut raw = PadState::neutral();
+        raw.axes[GamepadAxis::AxisRightY as usize] = -0.5;
+        assert!(approx_eq(settings.apply(&raw).axis(GamepadAxis::AxisRightY), -0.25));
+    }
+
+    #[test]
+    fn triggers() {
+        let settings = GamepadSettings { trigger_dead_zone: 0.0, ..GamepadSettings::default() };
+        let mut raw = PadState::neutral();
+        assert_eq!(settings.apply(&raw).axis(GamepadAxis::AxisLeftTrigger), 0.0);
+        raw.axes[GamepadAxis::AxisLeftTrigger as usize] = 0.0;
+        assert!(approx_eq(settings.apply(&raw).axis(GamepadAxis::AxisLeftTrigger), 0.5));
+        raw.axes[GamepadAxis::AxisLeftTrigger as usize] = 1.0;
+        assert!(approx_eq(settings.apply(&raw).axis(GamepadAxis::AxisLeftTrigger), 1.0));
+    }
+
+    #[test]
+    fn any_pad() {
+        let sim = SimulatedGamepads::new();
+        sim.connect(0, "a");
+        sim.connect(1, "b");
+        sim.set_button(1, GamepadButton::ButtonA, true);
+        sim.set_axis(0, GamepadAxis::AxisLeftX, 0.5);
+        sim.set_axis(1, GamepadAxis::AxisLeftX, -0.9);
+        let mut pads = Gamepads::default();
+        pads.poll(&mut sim.clone(), &GamepadSettings { stick_dead_zone: 0.0, ..GamepadSettings::default() });
+        assert!(pads.any_button(GamepadButton::ButtonA));
+        assert!(!pads.any_button(GamepadButton::ButtonB));
+        assert!(approx_eq(pads.any_axis(GamepadAxis::AxisLeftX), -0.9));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        assert!(GamepadSettings::default().validate().is_ok());
+        assert!(GamepadSettings { stick_dead_zone: 1.0, ..GamepadSettings::default() }.validate().is_err());
+        assert!(GamepadSettings { trigger_curve: 0.0, ..GamepadSettings::default() }.validate().is_err());
+    }
+}
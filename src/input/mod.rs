@@ -0,0 +1,768 @@
+pub mod gamepad;
+pub mod replay;
+
+pub use gamepad::{GamepadBackend, GamepadEvent, GamepadId, GamepadSettings, Gamepads, PadState, SimulatedGamepads};
+pub use replay::{InputEvent, Recorder, Recording, Replay};
+
+use std::collections::{BTreeMap, HashMap};
+
+use glfw::{GamepadAxis, GamepadButton, Key, Modifiers, MouseButton, WindowEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+// games ask for named actions and axes instead of keys:
+//   input.just_pressed("jump"), input.axis("zoom"), input.axis_2d("move")
+// the names are bound in config.input, written as text so the config file stays readable:
+//   actions: "W", "Ctrl+S", "MouseLeft", "Mouse4", "PadA", "PadDpadUp", "PadRightTrigger"
+//   axes:    "MouseX", "MouseY", "ScrollX", "ScrollY", "PadLeftX", "-PadLeftY" (inverted),
+//            or "negative/positive" like "A/D"
+// gamepad bindings react to any connected pad
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Button {
+    Key(Key),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+    // AxisLeftTrigger or AxisRightTrigger past GamepadSettings::trigger_threshold
+    Trigger(GamepadAxis),
+}
+// "ButtonA" -> "PadA", "AxisLeftX" -> "PadLeftX"
+fn pad_name(glfw_name: String) -> String {
+    let name = glfw_name.strip_prefix("Button").or(glfw_name.strip_prefix("Axis")).unwrap_or(&glfw_name);
+    format!("Pad{}", name)
+}
+fn parse_pad_axis(name: &str) -> Option<GamepadAxis> {
+    gamepad::AXES.into_iter().find(|axis| pad_name(format!("{:?}", axis)) == name)
+}
+impl Button {
+    fn parse(name: &str) -> Result<Self, Error> {
+        if let Some(button) = gamepad::BUTTONS.into_iter().find(|b| pad_name(format!("{:?}", b)) == name) {
+            return Ok(Button::Gamepad(button))
+        }
+        if let Some(axis @ (GamepadAxis::AxisLeftTrigger | GamepadAxis::AxisRightTrigger)) = parse_pad_axis(name) {
+            return Ok(Button::Trigger(axis))
+        }
+        let mouse = match name {
+            "MouseLeft" => Some(MouseButton::Left),
+            "MouseRight" => Some(MouseButton::Right),
+            "MouseMiddle" => Some(MouseButton::Middle),
+            _ => name.strip_prefix("Mouse")
+                .and_then(|n| n.parse::<i32>().ok())
+                .and_then(|n| MouseButton::from_i32(n-1)),
+        };
+        if let Some(button) = mouse { return Ok(Button::Mouse(button)) }
+
+        // glfw's own (serde) names: "W", "Space", "LeftShift", "F12", "Num1", ...
+        let de = serde::de::value::StrDeserializer::<serde::de::value::Error>::new(name);
+        Key::deserialize(de)
+            .map(Button::Key)
+            .map_err(|_| Error::ConfigError(format!("unknown key or button: {}", name)))
+    }
+    fn name(&self) -> String {
+        match self {
+            Button::Key(key) => format!("{:?}", key),
+            Button::Mouse(button) => match *button {
+                MouseButton::Button1 => "MouseLeft".to_string(),
+                MouseButton::Button2 => "MouseRight".to_string(),
+                MouseButton::Button3 => "MouseMiddle".to_string(),
+                b => format!("Mouse{}", b as i32 + 1),
+            },
+            Button::Gamepad(button) => pad_name(format!("{:?}", button)),
+            Button::Trigger(axis) => pad_name(format!("{:?}", axis)),
+        }
+    }
+    fn modifier(&self) -> Option<Modifiers> {
+        match self {
+            Button::Key(Key::LeftShift | Key::RightShift) => Some(Modifiers::Shift),
+            Button::Key(Key::LeftControl | Key::RightControl) => Some(Modifiers::Control),
+            Button::Key(Key::LeftAlt | Key::RightAlt) => Some(Modifiers::Alt),
+            Button::Key(Key::LeftSuper | Key::RightSuper) => Some(Modifiers::Super),
+            _ => None,
+        }
+    }
+}
+
+// a button plus the modifiers that have to be held with it.
+// extra modifiers don't matter, "W" still fires while shift is held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Binding {
+    pub button: Button,
+    pub modifiers: Modifiers,
+}
+impl Binding {
+    pub fn new(button: Button) -> Self { Self { button, modifiers: Modifiers::empty() } }
+    pub fn key(key: Key) -> Self { Self::new(Button::Key(key)) }
+    pub fn mouse(button: MouseButton) -> Self { Self::new(Button::Mouse(button)) }
+    pub fn with(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers |= modifiers;
+        self
+    }
+}
+const MODIFIER_NAMES: [(&str, Modifiers); 4] = [
+    ("Ctrl", Modifiers::Control),
+    ("Shift", Modifiers::Shift),
+    ("Alt", Modifiers::Alt),
+    ("Super", Modifiers::Super),
+];
+impl std::str::FromStr for Binding {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let button = Button::parse(parts.pop().unwrap())?;
+        let mut modifiers = Modifiers::empty();
+        for part in parts {
+            modifiers |= MODIFIER_NAMES.iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(part))
+                .ok_or(Error::ConfigError(format!("unknown modifier {} in {}", part, s)))?.1;
+        }
+        Ok(Self { button, modifiers })
+    }
+}
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, modifier) in MODIFIER_NAMES {
+            if self.modifiers.contains(modifier) { write!(f, "{}+", name)?; }
+        }
+        write!(f, "{}", self.button.name())
+    }
+}
+// serde wants an error it can print
+fn message(e: Error) -> String {
+    match e {
+        Error::ConfigError(msg) => msg,
+        e => format!("{:?}", e),
+    }
+}
+impl TryFrom<String> for Binding {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> { s.parse().map_err(message) }
+}
+impl From<Binding> for String {
+    fn from(b: Binding) -> String { b.to_string() }
+}
+impl TryFrom<String> for Button {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> { Button::parse(&s).map_err(message) }
+}
+impl From<Button> for String {
+    fn from(b: Button) -> String { b.name() }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AxisSource {
+    MouseX,
+    MouseY,
+    ScrollX,
+    ScrollY,
+    // after the dead zones and curves in GamepadSettings
+    Gamepad { axis: GamepadAxis, inverted: bool },
+    // -1 while negative is held, +1 for positive, 0 for both or neither
+    Buttons { negative: Binding, positive: Binding },
+}
+impl std::str::FromStr for AxisSource {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(match s {
+            "MouseX" => AxisSource::MouseX,
+            "MouseY" => AxisSource::MouseY,
+            "ScrollX" => AxisSource::ScrollX,
+            "ScrollY" => AxisSource::ScrollY,
+            _ if let Some(axis) = parse_pad_axis(s.trim_start_matches('-')) => {
+                AxisSource::Gamepad { axis, inverted: s.starts_with('-') }
+            },
+            _ => {
+                let (negative, positive) = s.split_once('/')
+                    .ok_or(Error::ConfigError(format!("axis binding {} should be MouseX, MouseY, ScrollX, ScrollY or negative/positive", s)))?;
+                AxisSource::Buttons { negative: negative.parse()?, positive: positive.parse()? }
+            },
+        })
+    }
+}
+impl std::fmt::Display for AxisSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AxisSource::MouseX => write!(f, "MouseX"),
+            AxisSource::MouseY => write!(f, "MouseY"),
+            AxisSource::ScrollX => write!(f, "ScrollX"),
+            AxisSource::ScrollY => write!(f, "ScrollY"),
+            AxisSource::Gamepad { axis, inverted } => {
+                write!(f, "{}{}", if *inverted { "-" } else { "" }, pad_name(format!("{:?}", axis)))
+            },
+            AxisSource::Buttons { negative, positive } => write!(f, "{}/{}", negative, positive),
+        }
+    }
+}
+impl TryFrom<String> for AxisSource {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> { s.parse().map_err(message) }
+}
+impl From<AxisSource> for String {
+    fn from(a: AxisSource) -> String { a.to_string() }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Axis {
+    // all sources are added up
+    pub bindings: Vec<AxisSource>,
+    // smaller values read as 0
+    pub dead_zone: f32,
+    pub scale: f32,
+}
+impl Default for Axis {
+    fn default() -> Self { Self { bindings: Vec::new(), dead_zone: 0.0, scale: 1.0 } }
+}
+impl Axis {
+    pub fn new(bindings: impl IntoIterator<Item = AxisSource>) -> Self {
+        Self { bindings: bindings.into_iter().collect(), ..Self::default() }
+    }
+}
+
+// two 1d axes read together, the dead zone is radial
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Axis2d {
+    pub x: String,
+    pub y: String,
+    #[serde(default)]
+    pub dead_zone: f32,
+}
+
+// what config.input holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputMap {
+    pub actions: BTreeMap<String, Vec<Binding>>,
+    pub axes: BTreeMap<String, Axis>,
+    pub axes_2d: BTreeMap<String, Axis2d>,
+    pub gamepad: GamepadSettings,
+}
+impl Default for InputMap {
+    fn default() -> Self {
+        let key = |k| Binding::key(k);
+        let keys = |negative, positive| AxisSource::Buttons { negative: key(negative), positive: key(positive) };
+        let pad = |negative, positive| AxisSource::Buttons {
+            negative: Binding::new(Button::Gamepad(negative)),
+            positive: Binding::new(Button::Gamepad(positive)),
+        };
+        let stick = |axis, inverted| AxisSource::Gamepad { axis, inverted };
+        let axis_2d = |x: &str, y: &str| Axis2d { x: x.to_string(), y: y.to_string(), dead_zone: 0.0 };
+        Self {
+            actions: [
+                ("quit", vec![key(Key::Escape)]),
+                ("wireframe", vec![key(Key::F1)]),
+                ("release_cursor", vec![key(Key::F2)]),
+                ("screenshot", vec![key(Key::F12)]),
+                ("record", vec![key(Key::F9)]),
+                ("fullscreen", vec![key(Key::F11)]),
+            ].into_iter().map(|(name, b)| (name.to_string(), b)).collect(),
+            axes: [
+                ("move_x", Axis::new([keys(Key::D, Key::A), stick(GamepadAxis::AxisLeftX, true)])),
+                ("move_y", Axis::new([
+                    keys(Key::LeftShift, Key::Space),
+                    pad(GamepadButton::ButtonLeftBumper, GamepadButton::ButtonRightBumper),
+                ])),
+                ("move_z", Axis::new([keys(Key::S, Key::W), stick(GamepadAxis::AxisLeftY, true)])),
+                ("look_x", Axis::new([AxisSource::MouseX])),
+                ("look_y", Axis::new([AxisSource::MouseY])),
+            ].into_iter().map(|(name, a)| (name.to_string(), a)).collect(),
+            axes_2d: [
+                ("move", axis_2d("move_x", "move_z")),
+                ("look", axis_2d("look_x", "look_y")),
+            ].into_iter().map(|(name, a)| (name.to_string(), a)).collect(),
+            gamepad: GamepadSettings::default(),
+        }
+    }
+}
+impl InputMap {
+    pub fn validate(&self) -> Result<(), Error> {
+        self.gamepad.validate().map_err(|e| Error::ConfigError(format!("gamepad {}", e)))?;
+        let mut seen: HashMap<Binding, &str> = HashMap::new();
+        for (action, bindings) in self.actions.iter() {
+            for binding in bindings {
+                if let Some(other) = seen.insert(*binding, action)
+                    && other != action
+                {
+                    return Err(Error::ConfigError(format!("{} and {} are both bound to {}", other, action, binding)))
+                }
+            }
+        }
+        for (name, axis) in self.axes.iter() {
+            if !(axis.dead_zone >= 0.0 && axis.dead_zone.is_finite()) {
+                return Err(Error::ConfigError(format!("dead zone of axis {} can't be negative, got {}", name, axis.dead_zone)))
+            }
+        }
+        for (name, axis) in self.axes_2d.iter() {
+            if let Some(missing) = [&axis.x, &axis.y].into_iter().find(|a| !self.axes.contains_key(*a)) {
+                return Err(Error::ConfigError(format!("2d axis {} uses unknown axis {}", name, missing)))
+            }
+            if !(axis.dead_zone >= 0.0 && axis.dead_zone.is_finite()) {
+                return Err(Error::ConfigError(format!("dead zone of axis {} can't be negative, got {}", name, axis.dead_zone)))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ButtonState {
+    down: bool,
+    // pressed at some point since the last update, so taps shorter than a frame still count
+    pressed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActionState {
+    pub pressed: bool,
+    pub just_pressed: bool,
+    pub just_released: bool,
+    // seconds, 0 while not pressed
+    pub held: f32,
+}
+
+// the Input resource. events and scripted presses go in, update() once per frame turns them into
+// action and axis values that stay the same until the next update
+pub struct Input {
+    map: InputMap,
+    buttons: HashMap<Button, ButtonState>,
+    pending_motion: (f64, f64),
+    pending_scroll: (f64, f64),
+
+    mouse_motion: (f64, f64),
+    scroll: (f64, f64),
+    actions: HashMap<String, ActionState>,
+    axes: HashMap<String, f32>,
+
+    gamepads: Gamepads,
+    gamepad_events: Vec<GamepadEvent>,
+
+    capturing: bool,
+    captured: Option<Binding>,
+}
+impl Default for Input {
+    fn default() -> Self { Self::new(InputMap::default()) }
+}
+impl Input {
+    pub fn new(map: InputMap) -> Self {
+        Self {
+            map,
+            buttons: HashMap::new(),
+            pending_motion: (0.0, 0.0),
+            pending_scroll: (0.0, 0.0),
+            mouse_motion: (0.0, 0.0),
+            scroll: (0.0, 0.0),
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+            gamepads: Gamepads::default(),
+            gamepad_events: Vec::new(),
+            capturing: false,
+            captured: None,
+        }
+    }
+    pub fn map(&self) -> &InputMap { &self.map }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        if let Some(event) = InputEvent::from_window_event(event) { self.apply(&event) }
+    }
+    // everything that reaches input goes through here, so recording these is enough to replay a session
+    pub fn apply(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Press(button) => self.press(*button),
+            InputEvent::Release(button) => self.release(*button),
+            InputEvent::MouseMotion(dx, dy) => self.add_mouse_motion(*dx, *dy),
+            InputEvent::Scroll(dx, dy) => self.add_scroll(*dx, *dy),
+            InputEvent::Gamepads(pads) => self.set_gamepads(pads.clone()),
+            InputEvent::Focus(false) => self.release_all(),
+            InputEvent::Focus(true) => {},
+        }
+    }
+    pub fn press(&mut self, button: Button) {
+        if self.capturing && button.modifier().is_none() {
+            self.capturing = false;
+            self.captured = Some(Binding { button, modifiers: self.modifiers() });
+        }
+        let state = self.buttons.entry(button).or_default();
+        state.down = true;
+        state.pressed = true;
+    }
+    pub fn release(&mut self, button: Button) {
+        self.buttons.entry(button).or_default().down = false;
+    }
+    pub fn add_mouse_motion(&mut self, dx: f64, dy: f64) {
+        self.pending_motion.0 += dx;
+        self.pending_motion.1 += dy;
+    }
+    pub fn add_scroll(&mut self, dx: f64, dy: f64) {
+        self.pending_scroll.0 += dx;
+        self.pending_scroll.1 += dy;
+    }
+    // once per frame before update, pad buttons and triggers then act like keys
+    pub fn poll_gamepads(&mut self, backend: &mut dyn GamepadBackend) {
+        self.set_gamepads(backend.poll());
+    }
+    pub fn set_gamepads(&mut self, polled: Vec<(GamepadId, String, PadState)>) {
+        self.gamepad_events = self.gamepads.update(polled, &self.map.gamepad);
+        let buttons = gamepad::BUTTONS.map(|b| (Button::Gamepad(b), self.gamepads.any_button(b)));
+        let triggers = [GamepadAxis::AxisLeftTrigger, GamepadAxis::AxisRightTrigger]
+            .map(|t| (Button::Trigger(t), self.gamepads.any_axis(t) > self.map.gamepad.trigger_threshold));
+        for (button, down) in buttons.into_iter().chain(triggers) {
+            match (self.is_down(button), down) {
+                (false, true) => self.press(button),
+                (true, false) => self.release(button),
+                _ => {},
+            }
+        }
+    }
+    // lets go of everything, e.g. when the window loses focus
+    pub fn release_all(&mut self) {
+        for state in self.buttons.values_mut() { state.down = false; }
+    }
+
+    // delta is the real frame time, held durations don't care about time scale
+    pub fn update(&mut self, delta: f32) {
+        self.mouse_motion = std::mem::take(&mut self.pending_motion);
+        self.scroll = std::mem::take(&mut self.pending_scroll);
+
+        for (name, bindings) in self.map.actions.iter() {
+            let active = bindings.iter().any(|b| self.binding_active(b));
+            let state = self.actions.entry(name.clone()).or_default();
+            let was = state.pressed;
+            *state = ActionState {
+                pressed: active,
+                just_pressed: active && !was,
+                just_released: !active && was,
+                held: if active && was { state.held + delta } else { 0.0 },
+            };
+        }
+        self.actions.retain(|name, _| self.map.actions.contains_key(name));
+
+        self.axes.clear();
+        for (name, axis) in self.map.axes.iter() {
+            let raw: f32 = axis.bindings.iter().map(|source| self.source_value(source)).sum::<f32>() * axis.scale;
+            let value = if raw.abs() < axis.dead_zone { 0.0 } else { raw };
+            self.axes.insert(name.clone(), value);
+        }
+
+        // anything pressed since the last update counts once, then only what is still down
+        for state in self.buttons.values_mut() { state.pressed = false; }
+    }
+
+    fn binding_active(&self, binding: &Binding) -> bool {
+        let state = self.buttons.get(&binding.button).copied().unwrap_or_default();
+        (state.down || state.pressed) && self.modifiers().contains(binding.modifiers)
+    }
+    fn source_value(&self, source: &AxisSource) -> f32 {
+        match source {
+            AxisSource::MouseX => self.mouse_motion.0 as f32,
+            AxisSource::MouseY => self.mouse_motion.1 as f32,
+            AxisSource::ScrollX => self.scroll.0 as f32,
+            AxisSource::ScrollY => self.scroll.1 as f32,
+            AxisSource::Gamepad { axis, inverted } => {
+                self.gamepads.any_axis(*axis) * if *inverted { -1.0 } else { 1.0 }
+            },
+            AxisSource::Buttons { negative, positive } => {
+                self.binding_active(positive) as i32 as f32 - self.binding_active(negative) as i32 as f32
+            },
+        }
+    }
+    pub fn modifiers(&self) -> Modifiers {
+        self.buttons.iter()
+            .filter(|(_, state)| state.down || state.pressed)
+            .filter_map(|(button, _)| button.modifier())
+            .fold(Modifiers::empty(), |all, m| all | m)
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = (&str, ActionState)> {
+        self.actions.iter().map(|(name, state)| (name.as_str(), *state))
+    }
+    pub fn action(&self, name: &str) -> ActionState { self.actions.get(name).copied().unwrap_or_default() }
+    pub fn pressed(&self, name: &str) -> bool { self.action(name).pressed }
+    pub fn just_pressed(&self, name: &str) -> bool { self.action(name).just_pressed }
+    pub fn just_released(&self, name: &str) -> bool { self.action(name).just_released }
+    pub fn held_duration(&self, name: &str) -> f32 { self.action(name).held }
+    pub fn axis(&self, name: &str) -> f32 { self.axes.get(name).copied().unwrap_or(0.0) }
+    pub fn axis_2d(&self, name: &str) -> (f32, f32) {
+        let Some(axis) = self.map.axes_2d.get(name) else { return (0.0, 0.0) };
+        let (x, y) = (self.axis(&axis.x), self.axis(&axis.y));
+        if (x*x + y*y).sqrt() < axis.dead_zone { (0.0, 0.0) } else { (x, y) }
+    }
+    // raw state, not going through the bindings
+    pub fn is_down(&self, button: Button) -> bool { self.buttons.get(&button).is_some_and(|s| s.down) }
+    pub fn mouse_motion(&self) -> (f64, f64) { self.mouse_motion }
+    pub fn scroll(&self) -> (f64, f64) { self.scroll }
+    pub fn gamepads(&self) -> &Gamepads { &self.gamepads }
+    // pads connected or disconnected by the last poll_gamepads
+    pub fn gamepad_events(&self) -> &[GamepadEvent] { &self.gamepad_events }
+
+    // rebinding, Game::save_config writes the result back to the config file
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.map.actions.get(action).map_or(&[], |b| b.as_slice())
+    }
+    // adds a binding, fails if another action already uses it
+    pub fn bind(&mut self, action: &str, binding: Binding) -> Result<(), Error> {
+        if let Some((other, _)) = self.map.actions.iter().find(|(name, b)| *name != action && b.contains(&binding)) {
+            return Err(Error::ConfigError(format!("{} is already bound to {}", binding, other)))
+        }
+        let bindings = self.map.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) { bindings.push(binding); }
+        Ok(())
+    }
+    // replaces all bindings of the action with this one
+    pub fn rebind(&mut self, action: &str, binding: Binding) -> Result<(), Error> {
+        let old = self.map.actions.insert(action.to_string(), Vec::new());
+        self.bind(action, binding).inspect_err(|_| match old {
+            Some(old) => { self.map.actions.insert(action.to_string(), old); },
+            // a new action doesn't stay behind empty
+            None => { self.map.actions.remove(action); },
+        })
+    }
+    pub fn unbind(&mut self, action: &str, binding: &Binding) {
+        if let Some(bindings) = self.map.actions.get_mut(action) { bindings.retain(|b| b != binding); }
+    }
+    pub fn set_axis(&mut self, name: &str, axis: Axis) {
+        self.map.axes.insert(name.to_string(), axis);
+    }
+    pub fn set_axis_2d(&mut self, name: &str, axis: Axis2d) -> Result<(), Error> {
+        let mut map = self.map.clone();
+        map.axes_2d.insert(name.to_string(), axis);
+        map.validate()?;
+        self.map = map;
+        Ok(())
+    }
+    // for "press a key" menus: the next non modifier press (with the modifiers held) is kept for take_captured
+    pub fn capture_next(&mut self) {
+        self.capturing = true;
+        self.captured = None;
+    }
+    pub fn is_capturing(&self) -> bool { self.capturing }
+    pub fn take_captured(&mut self) -> Option<Binding> { self.captured.take() }
+}
+
+#[cfg(test)]
+mod test {
+    use glfw::{Key, Modifiers, MouseButton};
+
+    use glfw::{GamepadAxis, GamepadButton};
+
+    use crate::input::{Axis, Axis2d, AxisSource, Binding, Button, GamepadEvent, Input, InputEvent, InputMap, SimulatedGamepads};
+
+    fn input() -> Input {
+        let mut map = InputMap::default();
+        map.actions.insert("jump".to_string(), vec![Binding::key(Key::Space), Binding::mouse(MouseButton::Right)]);
+        map.actions.insert("save".to_string(), vec![Binding::key(Key::S).with(Modifiers::Control)]);
+        Input::new(map)
+    }
+
+    #[test]
+    fn binding_names() {
+        for name in ["W", "Ctrl+S", "Ctrl+Shift+F5", "MouseLeft", "MouseMiddle", "Mouse5", "LeftShift"] {
+            assert_eq!(name.parse::<Binding>().unwrap().to_string(), name);
+        }
+        assert!("Shift+w".parse::<Binding>().is_err());
+        assert_eq!("Shift+W".parse::<Binding>().unwrap(), Binding::key(Key::W).with(Modifiers::Shift));
+        assert!("Hyper+W".parse::<Binding>().is_err());
+        assert!("Mouse9".parse::<Binding>().is_err());
+        for name in ["PadA", "PadDpadLeft", "PadStart", "PadRightTrigger", "Shift+PadB"] {
+            assert_eq!(name.parse::<Binding>().unwrap().to_string(), name);
+        }
+        assert!("PadLeftX".parse::<Binding>().is_err());
+        for name in ["MouseX", "ScrollY", "A/D", "Ctrl+Down/Ctrl+Up", "PadRightY", "-PadLeftTrigger", "PadX/PadB"] {
+            assert_eq!(name.parse::<AxisSource>().unwrap().to_string(), name);
+        }
+        assert!("A".parse::<AxisSource>().is_err());
+    }
+
+    #[test]
+    fn map_round_trips_through_toml() {
+        let map = input().map().clone();
+        let text = toml::to_string(&map).unwrap();
+        assert_eq!(toml::from_str::<InputMap>(&text).unwrap(), map);
+    }
+
+    #[test]
+    fn press_and_release() {
+        let mut input = input();
+        input.press(Button::Key(Key::Space));
+        input.update(0.1);
+        assert!(input.pressed("jump") && input.just_pressed("jump"));
+        assert_eq!(input.held_duration("jump"), 0.0);
+
+        input.update(0.1);
+        assert!(input.pressed("jump") && !input.just_pressed("jump"));
+        assert!((input.held_duration("jump") - 0.1).abs() < 1e-6);
+
+        input.release(Button::Key(Key::Space));
+        input.update(0.1);
+        assert!(!input.pressed("jump") && input.just_released("jump"));
+        input.update(0.1);
+        assert!(!input.just_released("jump"));
+    }
+
+    #[test]
+    fn focus_loss_releases_everything() {
+        let mut input = input();
+        input.press(Button::Key(Key::Space));
+        input.press(Button::Key(Key::W));
+        input.update(0.1);
+        input.apply(&InputEvent::Focus(false));
+        input.update(0.1);
+        assert!(input.just_released("jump"));
+        assert_eq!(input.axis("move_z"), 0.0);
+    }
+
+    #[test]
+    fn tap_within_one_frame() {
+        let mut input = input();
+        input.press(Button::Mouse(MouseButton::Right));
+        input.release(Button::Mouse(MouseButton::Right));
+        input.update(0.1);
+        assert!(input.just_pressed("jump"));
+        input.update(0.1);
+        assert!(input.just_released("jump"));
+    }
+
+    #[test]
+    fn modifiers() {
+        let mut input = input();
+        input.press(Button::Key(Key::S));
+        input.update(0.1);
+        assert!(!input.pressed("save"));
+        // S also moves backwards
+        assert_eq!(input.axis("move_z"), -1.0);
+
+        input.press(Button::Key(Key::RightControl));
+        input.update(0.1);
+        assert!(input.just_pressed("save"));
+    }
+
+    #[test]
+    fn axes() {
+        let mut input = input();
+        input.press(Button::Key(Key::W));
+        input.press(Button::Key(Key::A));
+        input.add_mouse_motion(3.0, -2.0);
+        input.add_mouse_motion(1.0, 0.0);
+        input.update(0.1);
+        assert_eq!(input.axis_2d("move"), (1.0, 1.0));
+        assert_eq!(input.axis_2d("look"), (4.0, -2.0));
+        assert_eq!(input.mouse_motion(), (4.0, -2.0));
+
+        // both directions cancel, motion is per frame
+        input.press(Button::Key(Key::D));
+        input.update(0.1);
+        assert_eq!(input.axis("move_x"), 0.0);
+        assert_eq!(input.axis_2d("look"), (0.0, 0.0));
+        assert_eq!(input.axis("unknown"), 0.0);
+    }
+
+    #[test]
+    fn dead_zones() {
+        let mut input = input();
+        input.set_axis("zoom", Axis { bindings: vec![AxisSource::ScrollY], dead_zone: 0.5, scale: 0.25 });
+        input.set_axis_2d("look", Axis2d { x: "look_x".to_string(), y: "look_y".to_string(), dead_zone: 5.0 }).unwrap();
+        input.add_scroll(0.0, 1.0);
+        input.add_mouse_motion(3.0, 3.0);
+        input.update(0.1);
+        assert_eq!(input.axis("zoom"), 0.0);
+        assert_eq!(input.axis_2d("look"), (0.0, 0.0));
+
+        input.add_scroll(0.0, 4.0);
+        input.add_mouse_motion(3.0, 4.0);
+        input.update(0.1);
+        assert_eq!(input.axis("zoom"), 1.0);
+        assert_eq!(input.axis_2d("look"), (3.0, 4.0));
+
+        assert!(input.set_axis_2d("bad", Axis2d { x: "nope".to_string(), y: "look_y".to_string(), dead_zone: 0.0 }).is_err());
+    }
+
+    #[test]
+    fn rebinding() {
+        let mut input = input();
+        assert!(input.bind("jump", Binding::key(Key::Escape)).is_err());
+        input.rebind("jump", Binding::key(Key::J)).unwrap();
+        assert_eq!(input.bindings("jump"), &[Binding::key(Key::J)]);
+        // a failed rebind keeps the old bindings
+        assert!(input.rebind("jump", Binding::key(Key::F1)).is_err());
+        assert_eq!(input.bindings("jump"), &[Binding::key(Key::J)]);
+        // and a failed rebind of a new action doesn't add it
+        assert!(input.rebind("dash", Binding::key(Key::J)).is_err());
+        assert!(!input.map().actions.contains_key("dash"));
+
+        input.press(Button::Key(Key::Space));
+        input.update(0.1);
+        assert!(!input.pressed("jump"));
+
+        input.unbind("jump", &Binding::key(Key::J));
+        assert!(input.bindings("jump").is_empty());
+        assert!(input.map().validate().is_ok());
+    }
+
+    #[test]
+    fn capture() {
+        let mut input = input();
+        input.capture_next();
+        input.press(Button::Key(Key::LeftControl));
+        assert!(input.is_capturing());
+        input.press(Button::Key(Key::K));
+        assert!(!input.is_capturing());
+        assert_eq!(input.take_captured(), Some(Binding::key(Key::K).with(Modifiers::Control)));
+        assert_eq!(input.take_captured(), None);
+    }
+
+    #[test]
+    fn gamepad_actions_and_axes() {
+        let sim = SimulatedGamepads::new();
+        let mut backend = sim.clone();
+        let mut input = input();
+        input.bind("jump", "PadA".parse().unwrap()).unwrap();
+        input.bind("shoot", "PadRightTrigger".parse().unwrap()).unwrap();
+
+        sim.connect(2, "test pad");
+        input.poll_gamepads(&mut backend);
+        input.update(0.1);
+        assert_eq!(input.gamepad_events(), &[GamepadEvent::Connected(2, "test pad".to_string())]);
+        assert!(!input.pressed("jump") && !input.pressed("shoot"));
+
+        sim.set_button(2, GamepadButton::ButtonA, true);
+        sim.set_axis(2, GamepadAxis::AxisRightTrigger, 0.5);
+        sim.set_axis(2, GamepadAxis::AxisLeftY, -1.0);
+        input.poll_gamepads(&mut backend);
+        input.update(0.1);
+        assert!(input.gamepad_events().is_empty());
+        assert!(input.just_pressed("jump"));
+        // 0.75 of the way down is past the default threshold
+        assert!(input.just_pressed("shoot"));
+        // stick up is forward
+        assert_eq!(input.axis("move_z"), 1.0);
+
+        // unplugging lets go of everything it held
+        sim.disconnect(2);
+        input.poll_gamepads(&mut backend);
+        input.update(0.1);
+        assert_eq!(input.gamepad_events(), &[GamepadEvent::Disconnected(2)]);
+        assert!(input.just_released("jump") && input.just_released("shoot"));
+        assert_eq!(input.axis("move_z"), 0.0);
+    }
+
+    #[test]
+    fn capture_gamepad_button() {
+        let sim = SimulatedGamepads::new();
+        sim.connect(0, "pad");
+        let mut input = input();
+        input.capture_next();
+        sim.set_button(0, GamepadButton::ButtonY, true);
+        input.poll_gamepads(&mut sim.clone());
+        assert_eq!(input.take_captured(), Some("PadY".parse().unwrap()));
+    }
+
+    #[test]
+    fn duplicate_bindings_are_invalid() {
+        let mut map = InputMap::default();
+        map.actions.insert("pause".to_string(), vec![Binding::key(Key::Escape)]);
+        assert!(map.validate().is_err());
+    }
+}
@@ -0,0 +1,328 @@
+use std::path::Path;
+
+use glfw::{Action, Modifiers, WindowEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+use super::{Button, GamepadId, InputMap, PadState};
+
+// a session's input frame by frame, saved as json:
+//   {"format":"enigne-input","version":1,"fixed_step":0.016666666666666666,"input":{...},
+//    "frames":[{"time":0.0},{"time":0.016,"events":[{"Press":"W"},{"MouseMotion":[3.0,-1.0]}]}, ...]}
+// replaying feeds the same events at the same clock times, so the same app started
+// from the same state does the same thing again, with or without a window.
+// the bindings are saved with it, rebinding after recording doesn't change what a replay does
+
+pub const FORMAT: &str = "enigne-input";
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Press(Button),
+    Release(Button),
+    MouseMotion(f64, f64),
+    Scroll(f64, f64),
+    // every connected pad. recordings only keep it when something changed
+    Gamepads(Vec<(GamepadId, String, PadState)>),
+    // losing focus releases everything, the window never sees those buttons come up
+    Focus(bool),
+}
+impl InputEvent {
+    // key repeats and non-input events are None
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match *event {
+            WindowEvent::Key(key, _, Action::Press, _) => Some(Self::Press(Button::Key(key))),
+            WindowEvent::Key(key, _, Action::Release, _) => Some(Self::Release(Button::Key(key))),
+            WindowEvent::MouseButton(button, Action::Press, _) => Some(Self::Press(Button::Mouse(button))),
+            WindowEvent::MouseButton(button, Action::Release, _) => Some(Self::Release(Button::Mouse(button))),
+            WindowEvent::Scroll(x, y) => Some(Self::Scroll(x, y)),
+            WindowEvent::Focus(focused) => Some(Self::Focus(focused)),
+            _ => None,
+        }
+    }
+    // the way back, so apps watching raw events see replayed input too.
+    // scancodes and modifiers aren't recorded, they come back as 0 and empty
+    pub fn to_window_event(&self) -> Option<WindowEvent> {
+        match *self {
+            Self::Press(Button::Key(key)) => Some(WindowEvent::Key(key, 0, Action::Press, Modifiers::empty())),
+            Self::Release(Button::Key(key)) => Some(WindowEvent::Key(key, 0, Action::Release, Modifiers::empty())),
+            Self::Press(Button::Mouse(button)) => Some(WindowEvent::MouseButton(button, Action::Press, Modifiers::empty())),
+            Self::Release(Button::Mouse(button)) => Some(WindowEvent::MouseButton(button, Action::Release, Modifiers::empty())),
+            Self::Scroll(x, y) => Some(WindowEvent::Scroll(x, y)),
+            Self::Focus(focused) => Some(WindowEvent::Focus(focused)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    // what the clock said, not the delta, so a replay gets bit for bit the same deltas
+    pub time: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<InputEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub format: String,
+    pub version: u32,
+    // Time's fixed step while recording
+    pub fixed_step: f64,
+    pub input: InputMap,
+    pub frames: Vec<Frame>,
+}
+impl Recording {
+    pub fn new(fixed_step: f64, input: InputMap) -> Self {
+        Self { format: FORMAT.to_string(), version: VERSION, fixed_step, input, frames: Vec::new() }
+    }
+    pub fn len(&self) -> usize { self.frames.len() }
+    pub fn is_empty(&self) -> bool { self.frames.is_empty() }
+    // clock time between the first and the last frame
+    pub fn duration(&self) -> f64 {
+        match (self.frames.first(), self.frames.last()) {
+            (Some(first), Some(last)) => last.time-first.time,
+            _ => 0.0,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(|e| Error::ReplayError(e.to_string()))
+    }
+    // the header is checked first, so an old or foreign file says so instead of failing on some field
+    pub fn from_json(source: &str) -> Result<Self, Error> {
+        let fail = |msg: String| Error::ReplayError(msg);
+        let value: serde_json::Value = serde_json::from_str(source).map_err(|e| fail(e.to_string()))?;
+        if value.get("format").and_then(|f| f.as_str()) != Some(FORMAT) {
+            return Err(fail("not an input recording".to_string()))
+        }
+        match value.get("version").and_then(|v| v.as_u64()) {
+            Some(version) if version == VERSION as u64 => {},
+            Some(version) => return Err(fail(format!("recording version {} is not supported, expected {}", version, VERSION))),
+            None => return Err(fail("recording has no version".to_string())),
+        }
+        let recording: Self = serde_json::from_value(value).map_err(|e| fail(e.to_string()))?;
+        // replaying sets Time's fixed step to it, and 0 would never get through a frame
+        if !(recording.fixed_step.is_finite() && recording.fixed_step > 0.0) {
+            return Err(fail(format!("fixed_step must be positive, got {}", recording.fixed_step)))
+        }
+        recording.input.validate().map_err(|e| match e {
+            Error::ConfigError(msg) => fail(format!("input: {}", msg)),
+            e => e,
+        })?;
+        Ok(recording)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| Error::ReplayError(format!("couldn't create {}: {}", dir.display(), e)))?;
+        }
+        std::fs::write(path, self.to_json()?)
+            .map_err(|e| Error::ReplayError(format!("couldn't write {}: {}", path.display(), e)))
+    }
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| Error::ReplayError(format!("couldn't read {}: {}", path.display(), e)))?;
+        Self::from_json(&source).map_err(|e| match e {
+            Error::ReplayError(msg) => Error::ReplayError(format!("{}: {}", path.display(), msg)),
+            e => e,
+        })
+    }
+}
+
+// builds a recording one frame at a time
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    recording: Recording,
+    pads: Vec<(GamepadId, String, PadState)>,
+}
+impl Recorder {
+    pub fn new(fixed_step: f64, input: InputMap) -> Self {
+        Self { recording: Recording::new(fixed_step, input), pads: Vec::new() }
+    }
+    // the frame's clock time and everything input saw during it
+    pub fn record(&mut self, time: f64, events: &[InputEvent]) {
+        let events = events.iter().filter(|event| match event {
+            InputEvent::Gamepads(pads) if *pads == self.pads => false,
+            InputEvent::Gamepads(pads) => { self.pads = pads.clone(); true },
+            _ => true,
+        }).cloned().collect();
+        self.recording.frames.push(Frame { time, events });
+    }
+    pub fn recording(&self) -> &Recording { &self.recording }
+    pub fn finish(self) -> Recording { self.recording }
+}
+
+// plays a recording back one frame at a time
+#[derive(Debug, Clone)]
+pub struct Replay {
+    recording: Recording,
+    next: usize,
+    // the last pads the recording had, the recorder leaves them out while they don't change
+    pads: Option<Vec<(GamepadId, String, PadState)>>,
+}
+impl Replay {
+    pub fn new(recording: Recording) -> Self { Self { recording, next: 0, pads: None } }
+    // frames come back the way input saw them live, with the pads polled every frame
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        let mut frame = self.recording.frames.get(self.next)?.clone();
+        self.next += 1;
+        let recorded = frame.events.iter().rev().find_map(|event| match event {
+            InputEvent::Gamepads(pads) => Some(pads.clone()),
+            _ => None,
+        });
+        match recorded {
+            Some(pads) => self.pads = Some(pads),
+            None => if let Some(pads) = &self.pads { frame.events.push(InputEvent::Gamepads(pads.clone())) },
+        }
+        Some(frame)
+    }
+    // frames played so far
+    pub fn position(&self) -> usize { self.next }
+    pub fn is_finished(&self) -> bool { self.next >= self.recording.len() }
+    pub fn recording(&self) -> &Recording { &self.recording }
+}
+
+#[cfg(test)]
+mod test {
+    use glfw::{GamepadButton, Key, MouseButton, WindowEvent};
+
+    use crate::input::{Binding, Button, GamepadEvent, Input, InputMap, PadState};
+    use super::{InputEvent, Recorder, Recording, Replay};
+
+    fn pads(a: bool) -> InputEvent {
+        let mut state = PadState::neutral();
+        state.buttons[GamepadButton::ButtonA as usize] = a;
+        InputEvent::Gamepads(vec![(0, "pad".to_string(), state)])
+    }
+
+    fn session() -> Recording {
+        let mut recorder = Recorder::new(1.0/60.0, InputMap::default());
+        recorder.record(0.0, &[pads(false)]);
+        recorder.record(0.1, &[InputEvent::Press(Button::Key(Key::W)), InputEvent::MouseMotion(3.0, -1.5), pads(false)]);
+        recorder.record(0.2, &[pads(true)]);
+        recorder.record(0.3, &[InputEvent::Release(Button::Key(Key::W)), InputEvent::Scroll(0.0, 1.0), pads(true)]);
+        recorder.finish()
+    }
+
+    #[test]
+    fn unchanged_pads_are_left_out() {
+        let recording = session();
+        assert_eq!(recording.len(), 4);
+        assert_eq!(recording.frames[0].events, vec![pads(false)]);
+        assert_eq!(recording.frames[1].events.len(), 2);
+        assert_eq!(recording.frames[2].events, vec![pads(true)]);
+        assert_eq!(recording.frames[3].events.len(), 2);
+        assert!((recording.duration() - 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let recording = session();
+        let json = recording.to_json().unwrap();
+        assert!(json.contains("\"Press\":\"W\""));
+        assert_eq!(Recording::from_json(&json).unwrap(), recording);
+    }
+
+    #[test]
+    fn header_is_checked() {
+        let json = session().to_json().unwrap();
+        assert!(Recording::from_json(&json.replace("\"version\":1", "\"version\":2")).is_err());
+        assert!(Recording::from_json(&json.replace("enigne-input", "something-else")).is_err());
+        assert!(Recording::from_json("{\"frames\":[]}").is_err());
+        assert!(Recording::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn contents_are_checked() {
+        let json = session().to_json().unwrap();
+        assert!(Recording::from_json(&json.replace("\"fixed_step\":0.016666666666666666", "\"fixed_step\":0.0")).is_err());
+        assert!(Recording::from_json(&json.replace("\"fixed_step\":0.016666666666666666", "\"fixed_step\":-1.0")).is_err());
+
+        let mut recording = session();
+        recording.input.actions.insert("other".to_string(), vec![Binding::key(Key::F12)]);
+        assert!(Recording::from_json(&recording.to_json().unwrap()).is_err());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("engine_replay_{}.json", std::process::id()));
+        let recording = session();
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), recording);
+    }
+
+    // feeding the frames into a fresh Input gives the same states every time
+    #[test]
+    fn replay_reproduces_input() {
+        let run = |recording: Recording| {
+            let mut input = Input::new(recording.input.clone());
+            let mut replay = Replay::new(recording);
+            let mut states = Vec::new();
+            while let Some(frame) = replay.next_frame() {
+                for event in frame.events.iter() { input.apply(event) }
+                input.update(0.1);
+                states.push((input.axis("move_z"), input.mouse_motion(), input.scroll(), input.is_down(Button::Gamepad(GamepadButton::ButtonA))));
+            }
+            assert!(replay.is_finished());
+            states
+        };
+        let states = run(session());
+        assert_eq!(states, run(Recording::from_json(&session().to_json().unwrap()).unwrap()));
+        assert_eq!(states[1], (1.0, (3.0, -1.5), (0.0, 0.0), false));
+        assert!(states[2].3);
+        assert_eq!(states[3], (0.0, (0.0, 0.0), (0.0, 1.0), true));
+    }
+
+    // the recorder leaves out pads that didn't change, a replay still has to look like the live frames
+    #[test]
+    fn held_pad_button_replays_like_live() {
+        let mut map = InputMap::default();
+        map.actions.insert("jump".to_string(), vec![Binding::new(Button::Gamepad(GamepadButton::ButtonA))]);
+        let live_frames = [vec![pads(true)], vec![pads(true)], vec![pads(true)], vec![pads(false)]];
+        let state = |input: &Input| (
+            input.is_down(Button::Gamepad(GamepadButton::ButtonA)),
+            input.just_pressed("jump"),
+            input.gamepad_events().to_vec(),
+        );
+
+        let mut input = Input::new(map.clone());
+        let mut recorder = Recorder::new(1.0/60.0, map.clone());
+        let mut live = Vec::new();
+        for (i, events) in live_frames.iter().enumerate() {
+            recorder.record(i as f64*0.1, events);
+            for event in events { input.apply(event) }
+            input.update(0.1);
+            live.push(state(&input));
+        }
+        let recording = recorder.finish();
+        assert!(recording.frames[1].events.is_empty() && recording.frames[2].events.is_empty());
+
+        let mut input = Input::new(map);
+        let mut replay = Replay::new(recording);
+        let mut replayed = Vec::new();
+        while let Some(frame) = replay.next_frame() {
+            for event in frame.events.iter() { input.apply(event) }
+            input.update(0.1);
+            replayed.push(state(&input));
+        }
+        assert_eq!(replayed, live);
+        assert_eq!(live[0].2, vec![GamepadEvent::Connected(0, "pad".to_string())]);
+        assert!(live[1].0 && !live[1].1 && live[1].2.is_empty());
+    }
+
+    #[test]
+    fn window_events() {
+        let press = WindowEvent::MouseButton(MouseButton::Button1, glfw::Action::Press, glfw::Modifiers::Shift);
+        let event = InputEvent::from_window_event(&press).unwrap();
+        assert_eq!(event, InputEvent::Press(Button::Mouse(MouseButton::Button1)));
+        assert!(matches!(event.to_window_event(), Some(WindowEvent::MouseButton(MouseButton::Button1, glfw::Action::Press, _))));
+        assert!(InputEvent::from_window_event(&WindowEvent::Key(Key::W, 0, glfw::Action::Repeat, glfw::Modifiers::empty())).is_none());
+        assert!(InputEvent::from_window_event(&WindowEvent::Size(10, 10)).is_none());
+        assert!(InputEvent::MouseMotion(1.0, 1.0).to_window_event().is_none());
+    }
+}
@@ -1,8 +1,15 @@
+pub mod app;
+pub mod config;
+pub mod ecs;
+pub mod event;
 pub mod game;
+pub mod golden;
+pub mod input;
 pub mod math;
 pub mod renderer;
-
-use math::{Color};
+pub mod scene;
+pub mod time;
+pub mod window;
 
 #[derive(Debug, Clone)]
 pub enum Error{
@@ -10,11 +17,17 @@ pub enum Error{
     VBOGenError(&'static str),
     EBOGenError(&'static str),
     ShaderError(String),
-    UniformError(&'static str),
+    UniformError(String),
     TextureError(String),
+    SceneError(&'static str),
+    EntityError(&'static str),
+    FramebufferError(String),
+    WindowError(String),
+    ScreenshotError(String),
+    GoldenError(String),
+    ConfigError(String),
+    ReplayError(String),
+    LayoutError(String),
+    BufferError(String),
+    MaterialError(String),
 }
-
-pub static GAME_NAME: &str = "enigne";
-pub const WINDOW_SIZE_X: u32 = 300;
-pub const WINDOW_SIZE_Y: u32 = 300;
-pub static BACKGROUND_COLOR: Color = Color::new([0.5,0.3, 0.3, 1.0]);
\ No newline at end of file
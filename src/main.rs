@@ -1,35 +1,88 @@
+use std::rc::Rc;
+
 use engine::{
-    game::{Game, Input, Player},
-    math::{Vector, Quaternion},
+    app::{App, Context},
+    config::Config,
+    ecs::{Stage, World},
+    event::{EventBus, RecordingEvent, ScreenshotEvent},
+    game::{Game, Player},
+    input::Input,
+    math::{Vector, Quaternion, Transform},
+    renderer::{objects::Cube, textures::Texture},
+    scene::NodeHandle,
+    time::Time,
     vector
 };
 
-fn update(input: &Input, player: &mut Player, delta_time: f32) {
+#[derive(Default)]
+struct Demo {
+    cubes: Vec<NodeHandle>,
+}
+impl App for Demo {
+    fn init(&mut self, ctx: &mut Context) -> Result<(), engine::Error> {
+        // loaded once, every cube shares them
+        let container = Rc::new(Texture::from_file(ctx.config.asset("textures/container.jpg"))?);
+        let face = Rc::new(Texture::from_file(ctx.config.asset("textures/awesomeface.png"))?);
+        for (name, position) in [("cube1", vector!(0.0, 0.0, 1.0)), ("cube2", vector!(0.0, 1.0, -2.0))] {
+            let cube = Cube::new(
+                (0.5, 0.5, 0.5),
+                Transform::IDENTITY,
+                vector!(1.0, 1.0, 1.0, 1.0),
+                vec![container.clone(), face.clone()],
+                gl::DYNAMIC_DRAW
+            );
+            self.cubes.push(ctx.scene.add_mesh(name, Transform::from_position(position), cube.mesh));
+        }
+        ctx.schedule.add_system(Stage::Update, player_movement);
+        // the engine only reports screenshots and recordings, saying so is up to the app
+        let mut bus = ctx.world.resource_mut::<EventBus>().unwrap();
+        bus.subscribe(|event: &ScreenshotEvent| match event {
+            ScreenshotEvent::Saved(path) => println!("screenshot: {}", path.display()),
+            ScreenshotEvent::Failed(e) => eprintln!("screenshot failed: {:?}", e),
+        });
+        bus.subscribe(|event: &RecordingEvent| match event {
+            RecordingEvent::Started => println!("recording input"),
+            RecordingEvent::Saved(path) => println!("recording: {}", path.display()),
+            RecordingEvent::Failed(e) => eprintln!("recording failed: {:?}", e),
+        });
+        Ok(())
+    }
+    fn update(&mut self, ctx: &mut Context) {
+        let rot = Quaternion::from_angle_vect((ctx.time().elapsed() as f32)*10.0, vector!(1.0, 0.0, 1.0));
+        for cube in self.cubes.iter() {
+            if let Some(node) = ctx.scene.get_mut(*cube) { node.transform_mut().rotation = rot; }
+        }
+    }
+}
+
+fn player_movement(world: &mut World) {
+    let input = world.resource::<Input>().unwrap();
+    let delta_time = world.resource::<Time>().unwrap().delta();
+    world.query::<&mut Player>(|_, player| move_player(&input, player, delta_time));
+}
+
+fn move_player(input: &Input, player: &mut Player, delta_time: f32) {
     let speed = player.speed*delta_time;
+    let look = player.camera_sense*delta_time;
 
+    let (look_x, look_y) = input.axis_2d("look");
     player.rotate(
-        Quaternion::from_radian_vect(input.cursor_diff.0 as f32 * delta_time, vector!(0.0, 1.0, 0.0))
-        * Quaternion::from_radian_vect(-input.cursor_diff.1 as f32 * delta_time, vector!(1.0, 0.0, 0.0))
+        Quaternion::from_radian_vect(look_x * look, vector!(0.0, 1.0, 0.0))
+        * Quaternion::from_radian_vect(-look_y * look, vector!(1.0, 0.0, 0.0))
     );
-    if input.w {
-        player.translate(vector!(0.0, 0.0, 1.0*speed).rotate(player.rotation));
-    }
-    if input.s {
-        player.translate(vector!(0.0, 0.0, -1.0*speed).rotate(player.rotation));}
-    if input.a { player.translate(vector!(1.0*speed, 0.0, 0.0).rotate(player.rotation)); }
-    if input.d { player.translate(vector!(-1.0*speed, 0.0, 0.0).rotate(player.rotation)); }
-    if input.space { player.translate(vector!(0.0, 1.0*speed, 0.0).rotate(player.rotation)); }
-    if input.shift { player.translate(vector!(0.0, -1.0*speed, 0.0).rotate(player.rotation)); }
+    let movement = vector!(input.axis("move_x"), input.axis("move_y"), input.axis("move_z"));
+    player.translate((movement*speed).rotate(player.transform.rotation));
 }
 
 fn main() -> Result<(), engine::Error> {
-    let mut player = Player::new(
+    // fov comes from the config, mouse_sensitivity there overrides the 0.5
+    let player = Player::new(
         vector!(0.0, 0.0, 0.0),
         Quaternion::from_angle_vect(95.0, vector!(0.0, 1.0, 0.0)),
         2.0,
         0.5,
     );
-    player.camera.set_fov(90.0);
-    let mut game = Game::new(player);
-    game.start(update)
+    let config = Config::load_with_overrides("config.toml")?;
+    let mut game = Game::with_config(player, config)?;
+    game.start(Demo::default())
 }
\ No newline at end of file
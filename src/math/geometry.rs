@@ -0,0 +1,800 @@
+use crate::math::{Matrix3x3, Matrix4x4, Quaternion, Vector, Vector3};
+use crate::vector;
+
+// shapes for culling, picking and physics, all in whatever space their points are in.
+// transform(&Matrix4x4) expects an affine matrix (no projection), like Transform::to_matrix().
+// ray queries return the distance along the ray to the first hit, None if it misses or the
+// hit is behind the origin. a ray starting inside a volume hits it at 0
+
+const EPSILON: f32 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+impl Aabb {
+    // any two opposite corners
+    pub fn new(a: Vector3, b: Vector3) -> Self {
+        Self { min: a.min(&b), max: a.max(&b) }
+    }
+    pub fn from_center_half_extents(center: Vector3, half_extents: Vector3) -> Self {
+        let half_extents = half_extents.abs();
+        Self { min: center-half_extents, max: center+half_extents }
+    }
+    // None without any points
+    pub fn from_points(points: impl IntoIterator<Item = Vector3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self { min: first, max: first }, |bounds, point| bounds.expand(point)))
+    }
+
+    pub fn center(&self) -> Vector3 { (self.min+self.max)*0.5 }
+    pub fn half_extents(&self) -> Vector3 { (self.max-self.min)*0.5 }
+    pub fn size(&self) -> Vector3 { self.max-self.min }
+    // bit 0 picks x, bit 1 y and bit 2 z, min when unset
+    pub fn corners(&self) -> [Vector3; 8] {
+        std::array::from_fn(|i| Vector::new(std::array::from_fn(|axis|
+            if i & (1 << axis) == 0 { self.min[axis] } else { self.max[axis] }
+        )))
+    }
+
+    // the boundary counts as inside
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+    pub fn contains(&self, other: &Self) -> bool {
+        (0..3).all(|i| self.min[i] <= other.min[i] && other.max[i] <= self.max[i])
+    }
+    // touching counts
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool { sphere.intersects_aabb(self) }
+
+    // the box around both
+    pub fn merge(&self, other: &Self) -> Self {
+        Self { min: self.min.min(&other.min), max: self.max.max(&other.max) }
+    }
+    // grown just enough to hold the point
+    pub fn expand(&self, point: Vector3) -> Self {
+        Self { min: self.min.min(&point), max: self.max.max(&point) }
+    }
+    // the point itself when it is inside
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        point.clamp(&self.min, &self.max)
+    }
+    pub fn distance_squared(&self, point: Vector3) -> f32 {
+        self.closest_point(point).distance_squared(&point)
+    }
+
+    // the box around the transformed box, a rotation makes it bigger than the shape inside
+    pub fn transform(&self, m: &Matrix4x4) -> Self {
+        let (center, half_extents) = (self.center(), self.half_extents());
+        let rows = m.as_arrays();
+        let half_extents = Vector::new(std::array::from_fn(|i|
+            (0..3).map(|j| rows[i][j].abs()*half_extents[j]).sum()
+        ));
+        Self::from_center_half_extents(m.transform_point(center), half_extents)
+    }
+    pub fn bounding_sphere(&self) -> Sphere {
+        Sphere::new(self.center(), self.half_extents().length())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Vector3,
+    pub radius: f32,
+}
+impl Sphere {
+    pub const fn new(center: Vector3, radius: f32) -> Self { Self { center, radius } }
+    // around the center of the points' bounds, not the smallest possible one
+    pub fn from_points(points: impl IntoIterator<Item = Vector3> + Clone) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points.into_iter().map(|p| p.distance_squared(&center)).fold(0.0, f32::max).sqrt();
+        Some(Self { center, radius })
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        self.center.distance_squared(&point) <= self.radius*self.radius
+    }
+    pub fn contains(&self, other: &Self) -> bool {
+        self.center.distance(&other.center) + other.radius <= self.radius
+    }
+    pub fn intersects(&self, other: &Self) -> bool {
+        let radii = self.radius + other.radius;
+        self.center.distance_squared(&other.center) <= radii*radii
+    }
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        aabb.distance_squared(self.center) <= self.radius*self.radius
+    }
+
+    // the smallest sphere around both
+    pub fn merge(&self, other: &Self) -> Self {
+        let distance = self.center.distance(&other.center);
+        if distance + other.radius <= self.radius { return *self }
+        if distance + self.radius <= other.radius { return *other }
+        let radius = (distance + self.radius + other.radius)*0.5;
+        let center = self.center + (other.center-self.center)*((radius-self.radius)/distance);
+        Self { center, radius }
+    }
+    // the point itself when it is inside
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        let offset = point-self.center;
+        if offset.length_squared() <= self.radius*self.radius { return point }
+        self.center + offset.normalize()*self.radius
+    }
+
+    // non uniform scale grows the radius by the largest axis
+    pub fn transform(&self, m: &Matrix4x4) -> Self {
+        let scale = (0..3)
+            .map(|axis| m.transform_vector(Vector::new(std::array::from_fn(|i| (i == axis) as u8 as f32))).length())
+            .fold(0.0, f32::max);
+        Self { center: m.transform_point(self.center), radius: self.radius*scale }
+    }
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_center_half_extents(self.center, vector!(self.radius, self.radius, self.radius))
+    }
+}
+
+// every point p where normal.dot(p) + d == 0, with a unit normal.
+// the normal side is the positive one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3,
+    pub d: f32,
+}
+impl Plane {
+    // normalizes, the normal doesn't need to be unit
+    pub fn new(normal: Vector3, d: f32) -> Self {
+        let length = normal.length();
+        Self { normal: normal/length, d: d/length }
+    }
+    pub fn from_point_normal(point: Vector3, normal: Vector3) -> Self {
+        let normal = normal.normalize();
+        Self { normal, d: -normal.dot(&point) }
+    }
+    // counter clockwise seen from the positive side
+    pub fn from_points(a: Vector3, b: Vector3, c: Vector3) -> Self {
+        Self::from_point_normal(a, (b-a).cross(c-a))
+    }
+
+    // positive in front, negative behind
+    pub fn signed_distance(&self, point: Vector3) -> f32 {
+        self.normal.dot(&point) + self.d
+    }
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        point - self.normal*self.signed_distance(point)
+    }
+    pub fn flip(&self) -> Self { Self { normal: -self.normal, d: -self.d } }
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.signed_distance(sphere.center).abs() <= sphere.radius
+    }
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.signed_distance(aabb.center()).abs() <= project_extents(self.normal, aabb.half_extents())
+    }
+
+    // panics like Matrix::inverse when m can't be inverted
+    pub fn transform(&self, m: &Matrix4x4) -> Self {
+        let v = m.inverse().transpose()*self.normal.extend(self.d);
+        Self::new(v.xyz(), v.w())
+    }
+}
+
+// how far a box with these half extents reaches along normal
+fn project_extents(normal: Vector3, half_extents: Vector3) -> f32 {
+    normal.abs().dot(&half_extents)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vector3,
+    // unit length
+    pub direction: Vector3,
+}
+impl Ray {
+    // normalizes the direction
+    pub fn new(origin: Vector3, direction: Vector3) -> Self {
+        Self { origin, direction: direction.normalize() }
+    }
+    pub fn at(&self, t: f32) -> Vector3 { self.origin + self.direction*t }
+    // never behind the origin
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        self.at((point-self.origin).dot(&self.direction).max(0.0))
+    }
+
+    // from either side, a ray in the plane misses it
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let facing = plane.normal.dot(&self.direction);
+        if facing.abs() < EPSILON { return None }
+        let t = -plane.signed_distance(self.origin)/facing;
+        (t >= 0.0).then_some(t)
+    }
+    // slabs
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let (mut near, mut far) = (0.0f32, f32::INFINITY);
+        for i in 0..3 {
+            let inverse = 1.0/self.direction[i];
+            let (mut t0, mut t1) = ((aabb.min[i]-self.origin[i])*inverse, (aabb.max[i]-self.origin[i])*inverse);
+            if inverse < 0.0 { std::mem::swap(&mut t0, &mut t1) }
+            // NaN (parallel and exactly on a face) leaves the range alone
+            near = near.max(t0);
+            far = far.min(t1);
+            if far < near { return None }
+        }
+        Some(near)
+    }
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let offset = self.origin-sphere.center;
+        let b = offset.dot(&self.direction);
+        let c = offset.length_squared() - sphere.radius*sphere.radius;
+        // outside and pointing away
+        if c > 0.0 && b > 0.0 { return None }
+        let discriminant = b*b - c;
+        if discriminant < 0.0 { return None }
+        Some((-b - discriminant.sqrt()).max(0.0))
+    }
+    // möller-trumbore, hits both sides
+    pub fn intersect_triangle(&self, triangle: &Triangle) -> Option<f32> {
+        let (ab, ac) = (triangle.b-triangle.a, triangle.c-triangle.a);
+        let p = self.direction.cross(ac);
+        let det = ab.dot(&p);
+        if det.abs() < EPSILON { return None }
+        let inverse = 1.0/det;
+        let s = self.origin-triangle.a;
+        let u = s.dot(&p)*inverse;
+        if !(0.0..=1.0).contains(&u) { return None }
+        let q = s.cross(ab);
+        let v = self.direction.dot(&q)*inverse;
+        if v < 0.0 || u+v > 1.0 { return None }
+        let t = ac.dot(&q)*inverse;
+        (t >= 0.0).then_some(t)
+    }
+    pub fn intersect_obb(&self, obb: &Obb) -> Option<f32> {
+        let inverse = obb.rotation.conjugate();
+        let local = Ray {
+            origin: (self.origin-obb.center).rotate(inverse),
+            direction: self.direction.rotate(inverse),
+        };
+        local.intersect_aabb(&Aabb::from_center_half_extents(vector!(0.0, 0.0, 0.0), obb.half_extents))
+    }
+
+    // scale changes what distances along the ray mean, they are in the new space afterwards
+    pub fn transform(&self, m: &Matrix4x4) -> Self {
+        Self::new(m.transform_point(self.origin), m.transform_vector(self.direction))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle {
+    pub a: Vector3,
+    pub b: Vector3,
+    pub c: Vector3,
+}
+impl Triangle {
+    pub const fn new(a: Vector3, b: Vector3, c: Vector3) -> Self { Self { a, b, c } }
+    // facing the side a, b and c are counter clockwise from
+    pub fn normal(&self) -> Vector3 { (self.b-self.a).cross(self.c-self.a).normalize() }
+    pub fn area(&self) -> f32 { (self.b-self.a).cross(self.c-self.a).length()*0.5 }
+    pub fn centroid(&self) -> Vector3 { (self.a+self.b+self.c)/3.0 }
+    pub fn plane(&self) -> Plane { Plane::from_points(self.a, self.b, self.c) }
+    pub fn bounds(&self) -> Aabb { Aabb::new(self.a, self.b).expand(self.c) }
+    // weights of a, b and c for the point projected onto the triangle's plane
+    pub fn barycentric(&self, point: Vector3) -> Vector3 {
+        let (ab, ac, ap) = (self.b-self.a, self.c-self.a, point-self.a);
+        let (d00, d01, d11) = (ab.dot(&ab), ab.dot(&ac), ac.dot(&ac));
+        let (d20, d21) = (ap.dot(&ab), ap.dot(&ac));
+        let denominator = d00*d11 - d01*d01;
+        let v = (d11*d20 - d01*d21)/denominator;
+        let w = (d00*d21 - d01*d20)/denominator;
+        vector!(1.0-v-w, v, w)
+    }
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        let weights = self.barycentric(point);
+        self.plane().signed_distance(point).abs() <= EPSILON && (0..3).all(|i| weights[i] >= -EPSILON)
+    }
+    // on the triangle, edges and corners included (ericson, real-time collision detection 5.1.5)
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        let (a, b, c) = (self.a, self.b, self.c);
+        let (ab, ac, ap) = (b-a, c-a, point-a);
+        let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+        if d1 <= 0.0 && d2 <= 0.0 { return a }
+        let bp = point-b;
+        let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+        if d3 >= 0.0 && d4 <= d3 { return b }
+        let vc = d1*d4 - d3*d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 { return a + ab*(d1/(d1-d3)) }
+        let cp = point-c;
+        let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+        if d6 >= 0.0 && d5 <= d6 { return c }
+        let vb = d5*d2 - d1*d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 { return a + ac*(d2/(d2-d6)) }
+        let va = d3*d6 - d5*d4;
+        if va <= 0.0 && d4-d3 >= 0.0 && d5-d6 >= 0.0 {
+            return b + (c-b)*((d4-d3)/((d4-d3) + (d5-d6)))
+        }
+        let denominator = 1.0/(va+vb+vc);
+        a + ab*(vb*denominator) + ac*(vc*denominator)
+    }
+    pub fn transform(&self, m: &Matrix4x4) -> Self {
+        Self::new(m.transform_point(self.a), m.transform_point(self.b), m.transform_point(self.c))
+    }
+}
+
+// a box turned by rotation around its center
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Obb {
+    pub center: Vector3,
+    pub half_extents: Vector3,
+    pub rotation: Quaternion,
+}
+impl Obb {
+    pub const fn new(center: Vector3, half_extents: Vector3, rotation: Quaternion) -> Self {
+        Self { center, half_extents, rotation }
+    }
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents(), Quaternion::IDENTITY)
+    }
+    // the box's own x, y and z, unit length
+    pub fn axes(&self) -> [Vector3; 3] {
+        [vector!(1.0, 0.0, 0.0), vector!(0.0, 1.0, 0.0), vector!(0.0, 0.0, 1.0)].map(|axis| axis.rotate(self.rotation))
+    }
+    pub fn corners(&self) -> [Vector3; 8] {
+        Aabb::from_center_half_extents(vector!(0.0, 0.0, 0.0), self.half_extents).corners()
+            .map(|corner| corner.rotate(self.rotation) + self.center)
+    }
+    pub fn bounds(&self) -> Aabb {
+        let axes = self.axes();
+        let half_extents = (0..3).fold(vector!(0.0, 0.0, 0.0), |sum, i| sum + axes[i].abs()*self.half_extents[i]);
+        Aabb::from_center_half_extents(self.center, half_extents)
+    }
+
+    fn local_point(&self, point: Vector3) -> Vector3 {
+        (point-self.center).rotate(self.rotation.conjugate())
+    }
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        let local = self.local_point(point);
+        (0..3).all(|i| local[i].abs() <= self.half_extents[i])
+    }
+    // the point itself when it is inside
+    pub fn closest_point(&self, point: Vector3) -> Vector3 {
+        let local = self.local_point(point).clamp(&-self.half_extents, &self.half_extents);
+        local.rotate(self.rotation) + self.center
+    }
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.closest_point(sphere.center).distance_squared(&sphere.center) <= sphere.radius*sphere.radius
+    }
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool { self.intersects(&Self::from_aabb(aabb)) }
+    // separating axes, the 3 + 3 face normals and their 9 cross products
+    // (ericson, real-time collision detection 4.4.1)
+    pub fn intersects(&self, other: &Self) -> bool {
+        let (a, b) = (self.axes(), other.axes());
+        let (ea, eb) = (self.half_extents, other.half_extents);
+        let r: [[f32; 3]; 3] = std::array::from_fn(|i| std::array::from_fn(|j| a[i].dot(&b[j])));
+        // the epsilon keeps near parallel edges from producing a bogus cross product axis
+        let abs_r: [[f32; 3]; 3] = std::array::from_fn(|i| std::array::from_fn(|j| r[i][j].abs() + EPSILON));
+        let d = other.center-self.center;
+        let t = [d.dot(&a[0]), d.dot(&a[1]), d.dot(&a[2])];
+
+        for i in 0..3 {
+            let rb = eb[0]*abs_r[i][0] + eb[1]*abs_r[i][1] + eb[2]*abs_r[i][2];
+            if t[i].abs() > ea[i] + rb { return false }
+        }
+        for j in 0..3 {
+            let ra = ea[0]*abs_r[0][j] + ea[1]*abs_r[1][j] + ea[2]*abs_r[2][j];
+            let distance = t[0]*r[0][j] + t[1]*r[1][j] + t[2]*r[2][j];
+            if distance.abs() > ra + eb[j] { return false }
+        }
+        for i in 0..3 {
+            let (i1, i2) = ((i+1)%3, (i+2)%3);
+            for j in 0..3 {
+                let (j1, j2) = ((j+1)%3, (j+2)%3);
+                let ra = ea[i1]*abs_r[i2][j] + ea[i2]*abs_r[i1][j];
+                let rb = eb[j1]*abs_r[i][j2] + eb[j2]*abs_r[i][j1];
+                let distance = t[i2]*r[i1][j] - t[i1]*r[i2][j];
+                if distance.abs() > ra + rb { return false }
+            }
+        }
+        true
+    }
+
+    // rotation and per axis scale of the box, a shearing matrix gives an approximation
+    pub fn transform(&self, m: &Matrix4x4) -> Self {
+        let mut axes = self.axes();
+        let mut half_extents = self.half_extents;
+        for i in 0..3 {
+            let axis = m.transform_vector(axes[i]*self.half_extents[i]);
+            half_extents[i] = axis.length();
+            axes[i] = if half_extents[i] > 0.0 { axis/half_extents[i] } else { axes[i] };
+        }
+        // a mirroring matrix would turn the axes left handed, the box is symmetric so flip one back
+        if axes[0].cross(axes[1]).dot(&axes[2]) < 0.0 { axes[2] = -axes[2] }
+        let rotation = Quaternion::from_matrix(&Matrix3x3::from_arrays(std::array::from_fn(|row|
+            std::array::from_fn(|column| axes[column][row])
+        )));
+        Self::new(m.transform_point(self.center), half_extents, rotation)
+    }
+}
+
+// the volume a camera sees, as six planes facing inwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    // left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+}
+impl Frustum {
+    // from projection * view with gl's -w..w clip space, the planes come out in world space.
+    // a projection alone gives them in view space (gribb & hartmann)
+    pub fn from_matrix(m: &Matrix4x4) -> Self {
+        let rows = m.as_arrays();
+        let plane = |row: usize, sign: f32| Plane::new(
+            Vector::new(std::array::from_fn(|i| rows[3][i] + sign*rows[row][i])),
+            rows[3][3] + sign*rows[row][3],
+        );
+        Self { planes: [plane(0, 1.0), plane(0, -1.0), plane(1, 1.0), plane(1, -1.0), plane(2, 1.0), plane(2, -1.0)] }
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+    // these are conservative: something just outside a corner can still count as intersecting,
+    // nothing inside is ever rejected
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let (center, half_extents) = (aabb.center(), aabb.half_extents());
+        self.planes.iter().all(|plane| plane.signed_distance(center) >= -project_extents(plane.normal, half_extents))
+    }
+    pub fn intersects_obb(&self, obb: &Obb) -> bool {
+        let axes = obb.axes();
+        self.planes.iter().all(|plane| {
+            let reach = (0..3).map(|i| plane.normal.dot(&axes[i]).abs()*obb.half_extents[i]).sum::<f32>();
+            plane.signed_distance(obb.center) >= -reach
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{self, Aabb, Frustum, Matrix4x4, Obb, Plane, Quaternion, Ray, Sphere, Transform, Triangle, Vector, Vector3};
+    use crate::vector;
+
+    fn approx_eq(a: f32, b: f32) -> bool { (a-b).abs() < 1e-4 }
+    fn vec_eq(a: Vector3, b: Vector3) -> bool { (0..3).all(|i| approx_eq(a[i], b[i])) }
+    fn unit_box() -> Aabb { Aabb::new(vector!(-1.0, -1.0, -1.0), vector!(1.0, 1.0, 1.0)) }
+    fn turned(degrees: f32) -> Matrix4x4 {
+        Transform::from_position_rotation(vector!(10.0, 0.0, 0.0), Quaternion::from_angle_vect(degrees, vector!(0.0, 1.0, 0.0))).to_matrix()
+    }
+
+    #[test]
+    fn aabb_basics() {
+        let b = Aabb::new(vector!(2.0, 0.0, 4.0), vector!(0.0, 2.0, 0.0));
+        assert_eq!(b.min, vector!(0.0, 0.0, 0.0));
+        assert_eq!(b.center(), vector!(1.0, 1.0, 2.0));
+        assert_eq!(b.half_extents(), vector!(1.0, 1.0, 2.0));
+        assert_eq!(b.size(), vector!(2.0, 2.0, 4.0));
+        assert_eq!(Aabb::from_center_half_extents(b.center(), b.half_extents()), b);
+        let corners = b.corners();
+        assert_eq!(corners[0], b.min);
+        assert_eq!(corners[7], b.max);
+        assert_eq!(Aabb::from_points(corners), Some(b));
+        assert_eq!(Aabb::from_points([]), None);
+    }
+
+    #[test]
+    fn aabb_containment_and_merging() {
+        let b = unit_box();
+        assert!(b.contains_point(vector!(1.0, 0.0, -1.0)));
+        assert!(!b.contains_point(vector!(1.1, 0.0, 0.0)));
+        let small = Aabb::new(vector!(0.0, 0.0, 0.0), vector!(0.5, 0.5, 0.5));
+        assert!(b.contains(&small));
+        assert!(!small.contains(&b));
+        let far = Aabb::new(vector!(3.0, 3.0, 3.0), vector!(4.0, 4.0, 4.0));
+        let merged = b.merge(&far);
+        assert_eq!(merged, Aabb::new(vector!(-1.0, -1.0, -1.0), vector!(4.0, 4.0, 4.0)));
+        assert!(merged.contains(&b) && merged.contains(&far));
+        assert_eq!(b.expand(vector!(0.0, 5.0, 0.0)).max, vector!(1.0, 5.0, 1.0));
+    }
+
+    #[test]
+    fn aabb_intersections() {
+        let b = unit_box();
+        assert!(b.intersects(&Aabb::new(vector!(0.5, 0.5, 0.5), vector!(3.0, 3.0, 3.0))));
+        // touching faces
+        assert!(b.intersects(&Aabb::new(vector!(1.0, -1.0, -1.0), vector!(2.0, 1.0, 1.0))));
+        // overlapping on two axes only
+        assert!(!b.intersects(&Aabb::new(vector!(0.0, 0.0, 2.0), vector!(1.0, 1.0, 3.0))));
+        assert!(b.intersects_sphere(&Sphere::new(vector!(2.0, 0.0, 0.0), 1.0)));
+        // the sphere reaches past the corner's x, y and z but not the corner itself
+        assert!(!b.intersects_sphere(&Sphere::new(vector!(1.9, 1.9, 1.9), 1.5)));
+    }
+
+    #[test]
+    fn aabb_closest_point() {
+        let b = unit_box();
+        assert_eq!(b.closest_point(vector!(0.5, 0.0, 0.0)), vector!(0.5, 0.0, 0.0));
+        assert_eq!(b.closest_point(vector!(3.0, 0.5, -4.0)), vector!(1.0, 0.5, -1.0));
+        assert_eq!(b.distance_squared(vector!(3.0, 0.0, 0.0)), 4.0);
+    }
+
+    #[test]
+    fn aabb_transform() {
+        let b = Aabb::new(vector!(0.0, 0.0, 0.0), vector!(2.0, 1.0, 1.0));
+        assert_eq!(b.transform(&Matrix4x4::IDENTITY), b);
+        let moved = b.transform(&Transform::new(vector!(1.0, 2.0, 3.0), Quaternion::IDENTITY, vector!(2.0, 2.0, 2.0)).to_matrix());
+        assert!(vec_eq(moved.min, vector!(1.0, 2.0, 3.0)));
+        assert!(vec_eq(moved.max, vector!(5.0, 4.0, 5.0)));
+        // turned 90 degrees x and z swap
+        let quarter = b.transform(&turned(90.0));
+        assert!(vec_eq(quarter.size(), vector!(1.0, 1.0, 2.0)));
+        // at 45 degrees it has to grow, and still holds every corner
+        let m = turned(45.0);
+        let grown = b.transform(&m);
+        for corner in b.corners().map(|c| m.transform_point(c)) {
+            assert!(vec_eq(grown.closest_point(corner), corner));
+        }
+        assert!(grown.size()[0] > 2.0);
+    }
+
+    #[test]
+    fn sphere_basics() {
+        let s = Sphere::new(vector!(0.0, 0.0, 0.0), 2.0);
+        assert!(s.contains_point(vector!(0.0, 2.0, 0.0)));
+        assert!(!s.contains_point(vector!(1.5, 1.5, 0.0)));
+        assert!(s.contains(&Sphere::new(vector!(1.0, 0.0, 0.0), 1.0)));
+        assert!(!s.contains(&Sphere::new(vector!(1.5, 0.0, 0.0), 1.0)));
+        assert!(s.intersects(&Sphere::new(vector!(3.0, 0.0, 0.0), 1.0)));
+        assert!(!s.intersects(&Sphere::new(vector!(3.1, 0.0, 0.0), 1.0)));
+        assert_eq!(s.closest_point(vector!(0.0, 0.0, 5.0)), vector!(0.0, 0.0, 2.0));
+        assert_eq!(s.closest_point(vector!(0.0, 1.0, 0.0)), vector!(0.0, 1.0, 0.0));
+        assert_eq!(s.bounds(), Aabb::new(vector!(-2.0, -2.0, -2.0), vector!(2.0, 2.0, 2.0)));
+        let around = Sphere::from_points([vector!(-1.0, 0.0, 0.0), vector!(3.0, 0.0, 0.0), vector!(1.0, 1.0, 0.0)]).unwrap();
+        assert_eq!(around.center, vector!(1.0, 0.5, 0.0));
+        assert!(approx_eq(around.radius, 4.25f32.sqrt()));
+    }
+
+    #[test]
+    fn sphere_merge() {
+        let a = Sphere::new(vector!(0.0, 0.0, 0.0), 1.0);
+        let b = Sphere::new(vector!(4.0, 0.0, 0.0), 1.0);
+        let merged = a.merge(&b);
+        assert!(vec_eq(merged.center, vector!(2.0, 0.0, 0.0)));
+        assert!(approx_eq(merged.radius, 3.0));
+        // one inside the other
+        let big = Sphere::new(vector!(0.0, 0.0, 0.0), 10.0);
+        assert_eq!(big.merge(&a), big);
+        assert_eq!(a.merge(&big), big);
+    }
+
+    #[test]
+    fn sphere_transform() {
+        let s = Sphere::new(vector!(1.0, 0.0, 0.0), 1.0);
+        let m = Transform::new(vector!(0.0, 5.0, 0.0), Quaternion::from_angle_vect(90.0, vector!(0.0, 0.0, 1.0)), vector!(1.0, 3.0, 2.0)).to_matrix();
+        let t = s.transform(&m);
+        assert!(vec_eq(t.center, vector!(0.0, 6.0, 0.0)));
+        assert!(approx_eq(t.radius, 3.0));
+    }
+
+    #[test]
+    fn plane_basics() {
+        let p = Plane::from_point_normal(vector!(0.0, 2.0, 0.0), vector!(0.0, 5.0, 0.0));
+        assert_eq!(p, Plane::new(vector!(0.0, 1.0, 0.0), -2.0));
+        assert_eq!(Plane::new(vector!(0.0, 2.0, 0.0), -4.0), p);
+        assert_eq!(p.signed_distance(vector!(3.0, 5.0, 1.0)), 3.0);
+        assert_eq!(p.signed_distance(vector!(0.0, 0.0, 0.0)), -2.0);
+        assert_eq!(p.closest_point(vector!(3.0, 5.0, 1.0)), vector!(3.0, 2.0, 1.0));
+        assert_eq!(p.flip().signed_distance(vector!(0.0, 0.0, 0.0)), 2.0);
+        // counter clockwise seen from above faces up
+        let p = Plane::from_points(vector!(0.0, 1.0, 0.0), vector!(0.0, 1.0, 1.0), vector!(1.0, 1.0, 0.0));
+        assert!(vec_eq(p.normal, vector!(0.0, 1.0, 0.0)));
+        assert!(approx_eq(p.d, -1.0));
+        assert!(p.intersects_sphere(&Sphere::new(vector!(5.0, 2.0, 5.0), 1.0)));
+        assert!(!p.intersects_sphere(&Sphere::new(vector!(5.0, 2.5, 5.0), 1.0)));
+        assert!(p.intersects_aabb(&unit_box()));
+        assert!(!p.intersects_aabb(&Aabb::new(vector!(0.0, 1.5, 0.0), vector!(1.0, 2.0, 1.0))));
+    }
+
+    #[test]
+    fn plane_transform() {
+        let p = Plane::from_point_normal(vector!(0.0, 1.0, 0.0), vector!(0.0, 1.0, 0.0));
+        let m = Transform::new(vector!(0.0, 0.0, 3.0), Quaternion::from_angle_vect(90.0, vector!(1.0, 0.0, 0.0)), vector!(1.0, 2.0, 1.0)).to_matrix();
+        let t = p.transform(&m);
+        // points on the plane stay on it
+        for point in [vector!(0.0, 1.0, 0.0), vector!(4.0, 1.0, -2.0)] {
+            assert!(approx_eq(t.signed_distance(m.transform_point(point)), 0.0));
+        }
+        assert!(vec_eq(t.normal, vector!(0.0, 0.0, 1.0)));
+        assert!(approx_eq(t.signed_distance(m.transform_point(vector!(0.0, 3.0, 0.0))), 4.0));
+    }
+
+    #[test]
+    fn ray_basics() {
+        let ray = Ray::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 0.0, 3.0));
+        assert_eq!(ray.direction, vector!(0.0, 0.0, 1.0));
+        assert_eq!(ray.at(2.0), vector!(0.0, 0.0, 2.0));
+        assert_eq!(ray.closest_point(vector!(1.0, 0.0, 5.0)), vector!(0.0, 0.0, 5.0));
+        assert_eq!(ray.closest_point(vector!(1.0, 0.0, -5.0)), ray.origin);
+        let moved = ray.transform(&Matrix4x4::translation_mat(vector!(1.0, 0.0, 0.0)));
+        assert_eq!(moved, Ray::new(vector!(1.0, 0.0, 0.0), vector!(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn ray_plane() {
+        let floor = Plane::from_point_normal(vector!(0.0, 0.0, 0.0), vector!(0.0, 1.0, 0.0));
+        let down = Ray::new(vector!(1.0, 4.0, 0.0), vector!(0.0, -1.0, 1.0));
+        let t = down.intersect_plane(&floor).unwrap();
+        assert!(vec_eq(down.at(t), vector!(1.0, 0.0, 4.0)));
+        // from below works too
+        assert_eq!(Ray::new(vector!(0.0, -2.0, 0.0), vector!(0.0, 1.0, 0.0)).intersect_plane(&floor), Some(2.0));
+        assert_eq!(Ray::new(vector!(0.0, 2.0, 0.0), vector!(0.0, 1.0, 0.0)).intersect_plane(&floor), None);
+        assert_eq!(Ray::new(vector!(0.0, 2.0, 0.0), vector!(1.0, 0.0, 0.0)).intersect_plane(&floor), None);
+    }
+
+    #[test]
+    fn ray_aabb() {
+        let b = unit_box();
+        assert_eq!(Ray::new(vector!(-5.0, 0.0, 0.0), vector!(1.0, 0.0, 0.0)).intersect_aabb(&b), Some(4.0));
+        assert_eq!(Ray::new(vector!(5.0, 0.0, 0.0), vector!(1.0, 0.0, 0.0)).intersect_aabb(&b), None);
+        assert_eq!(Ray::new(vector!(0.0, 0.0, 0.0), vector!(1.0, 0.0, 0.0)).intersect_aabb(&b), Some(0.0));
+        assert_eq!(Ray::new(vector!(-5.0, 2.0, 0.0), vector!(1.0, 0.0, 0.0)).intersect_aabb(&b), None);
+        let diagonal = Ray::new(vector!(-3.0, -3.0, -3.0), vector!(1.0, 1.0, 1.0));
+        assert!(approx_eq(diagonal.intersect_aabb(&b).unwrap(), 2.0*3f32.sqrt()));
+        // passes just over the top
+        assert_eq!(Ray::new(vector!(-3.0, 0.0, 1.1), vector!(1.0, 1.0, 0.0)).intersect_aabb(&b), None);
+    }
+
+    #[test]
+    fn ray_sphere() {
+        let s = Sphere::new(vector!(0.0, 0.0, 10.0), 2.0);
+        assert_eq!(Ray::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 0.0, 1.0)).intersect_sphere(&s), Some(8.0));
+        assert_eq!(Ray::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 0.0, -1.0)).intersect_sphere(&s), None);
+        assert_eq!(Ray::new(vector!(0.0, 3.0, 0.0), vector!(0.0, 0.0, 1.0)).intersect_sphere(&s), None);
+        assert_eq!(Ray::new(vector!(0.0, 0.0, 9.0), vector!(0.0, 1.0, 0.0)).intersect_sphere(&s), Some(0.0));
+        let grazing = Ray::new(vector!(0.0, 2.0, 0.0), vector!(0.0, 0.0, 1.0)).intersect_sphere(&s).unwrap();
+        assert!(approx_eq(grazing, 10.0));
+    }
+
+    #[test]
+    fn ray_triangle() {
+        let t = Triangle::new(vector!(0.0, 0.0, 5.0), vector!(2.0, 0.0, 5.0), vector!(0.0, 2.0, 5.0));
+        let hit = Ray::new(vector!(0.5, 0.5, 0.0), vector!(0.0, 0.0, 1.0)).intersect_triangle(&t);
+        assert!(approx_eq(hit.unwrap(), 5.0));
+        // back side
+        assert!(Ray::new(vector!(0.5, 0.5, 10.0), vector!(0.0, 0.0, -1.0)).intersect_triangle(&t).is_some());
+        assert_eq!(Ray::new(vector!(1.5, 1.5, 0.0), vector!(0.0, 0.0, 1.0)).intersect_triangle(&t), None);
+        assert_eq!(Ray::new(vector!(0.5, 0.5, 6.0), vector!(0.0, 0.0, 1.0)).intersect_triangle(&t), None);
+        // in the triangle's plane
+        assert_eq!(Ray::new(vector!(-1.0, 0.5, 5.0), vector!(1.0, 0.0, 0.0)).intersect_triangle(&t), None);
+    }
+
+    #[test]
+    fn ray_obb() {
+        let obb = Obb::new(vector!(0.0, 0.0, 10.0), vector!(4.0, 1.0, 1.0), Quaternion::from_angle_vect(90.0, vector!(0.0, 1.0, 0.0)));
+        // the long side now runs along z
+        let t = Ray::new(vector!(0.0, 0.0, 0.0), vector!(0.0, 0.0, 1.0)).intersect_obb(&obb).unwrap();
+        assert!(approx_eq(t, 6.0));
+        assert_eq!(Ray::new(vector!(2.0, 0.0, 0.0), vector!(0.0, 0.0, 1.0)).intersect_obb(&obb), None);
+    }
+
+    #[test]
+    fn triangle_queries() {
+        let t = Triangle::new(vector!(0.0, 0.0, 0.0), vector!(1.0, 0.0, 0.0), vector!(0.0, 1.0, 0.0));
+        assert_eq!(t.normal(), vector!(0.0, 0.0, 1.0));
+        assert_eq!(t.area(), 0.5);
+        assert!(vec_eq(t.centroid(), vector!(1.0/3.0, 1.0/3.0, 0.0)));
+        assert_eq!(t.bounds(), Aabb::new(vector!(0.0, 0.0, 0.0), vector!(1.0, 1.0, 0.0)));
+        assert!(vec_eq(t.barycentric(vector!(0.25, 0.5, 0.0)), vector!(0.25, 0.25, 0.5)));
+        assert!(t.contains_point(vector!(0.25, 0.25, 0.0)));
+        assert!(!t.contains_point(vector!(0.25, 0.25, 0.1)));
+        assert!(!t.contains_point(vector!(0.75, 0.75, 0.0)));
+        // each voronoi region
+        assert_eq!(t.closest_point(vector!(-1.0, -1.0, 3.0)), t.a);
+        assert_eq!(t.closest_point(vector!(2.0, -0.5, 0.0)), t.b);
+        assert_eq!(t.closest_point(vector!(-0.5, 2.0, 0.0)), t.c);
+        assert!(vec_eq(t.closest_point(vector!(0.5, -1.0, 0.0)), vector!(0.5, 0.0, 0.0)));
+        assert!(vec_eq(t.closest_point(vector!(-1.0, 0.5, 0.0)), vector!(0.0, 0.5, 0.0)));
+        assert!(vec_eq(t.closest_point(vector!(1.0, 1.0, 0.0)), vector!(0.5, 0.5, 0.0)));
+        assert!(vec_eq(t.closest_point(vector!(0.2, 0.3, -4.0)), vector!(0.2, 0.3, 0.0)));
+        let moved = t.transform(&Matrix4x4::translation_mat(vector!(0.0, 0.0, 2.0)));
+        assert_eq!(moved.plane().signed_distance(vector!(0.0, 0.0, 0.0)), -2.0);
+    }
+
+    #[test]
+    fn obb_queries() {
+        let obb = Obb::new(vector!(0.0, 0.0, 0.0), vector!(2.0, 1.0, 1.0), Quaternion::from_angle_vect(90.0, vector!(0.0, 0.0, 1.0)));
+        // the long side now runs along y
+        assert!(obb.contains_point(vector!(0.0, 1.9, 0.0)));
+        assert!(!obb.contains_point(vector!(1.9, 0.0, 0.0)));
+        assert!(vec_eq(obb.closest_point(vector!(0.0, 5.0, 0.0)), vector!(0.0, 2.0, 0.0)));
+        assert!(vec_eq(obb.bounds().size(), vector!(2.0, 4.0, 2.0)));
+        let bounds = obb.bounds();
+        assert!(obb.corners().iter().all(|c| vec_eq(bounds.closest_point(*c), *c)));
+        assert!(obb.intersects_sphere(&Sphere::new(vector!(0.0, 2.5, 0.0), 1.0)));
+        assert!(!obb.intersects_sphere(&Sphere::new(vector!(2.5, 0.0, 0.0), 1.0)));
+        assert_eq!(Obb::from_aabb(&unit_box()).bounds(), unit_box());
+    }
+
+    #[test]
+    fn obb_intersections() {
+        let a = Obb::from_aabb(&unit_box());
+        let turned = |center: Vector3| Obb::new(center, vector!(1.0, 1.0, 1.0), Quaternion::from_angle_vect(45.0, vector!(0.0, 0.0, 1.0)));
+        // a diamond's tip reaches 1.414 out
+        assert!(a.intersects(&turned(vector!(2.3, 0.0, 0.0))));
+        assert!(!a.intersects(&turned(vector!(2.5, 0.0, 0.0))));
+        // their bounds overlap, the boxes don't
+        let b = Obb::new(vector!(2.1, 2.1, 0.0), vector!(1.0, 1.0, 1.0), Quaternion::from_angle_vect(45.0, vector!(0.0, 0.0, 1.0)));
+        assert!(a.bounds().intersects(&b.bounds()));
+        assert!(!a.intersects(&b));
+        assert!(a.intersects_aabb(&Aabb::new(vector!(0.5, 0.5, 0.5), vector!(3.0, 3.0, 3.0))));
+        assert!(!a.intersects_aabb(&Aabb::new(vector!(1.5, 0.0, 0.0), vector!(3.0, 3.0, 3.0))));
+        let tilted = |x: f32| Obb::new(vector!(x, 0.0, 0.0), vector!(0.1, 3.0, 0.1), Quaternion::from_euler(0.3, 0.7, 0.2));
+        assert!(a.intersects(&tilted(0.5)));
+        assert!(!a.intersects(&tilted(4.0)));
+    }
+
+    #[test]
+    fn obb_transform() {
+        let obb = Obb::from_aabb(&Aabb::new(vector!(0.0, 0.0, 0.0), vector!(2.0, 2.0, 2.0)));
+        let m = Transform::new(vector!(0.0, 0.0, 5.0), Quaternion::from_angle_vect(30.0, vector!(0.0, 1.0, 0.0)), vector!(2.0, 1.0, 1.0)).to_matrix();
+        let t = obb.transform(&m);
+        assert!(vec_eq(t.center, m.transform_point(vector!(1.0, 1.0, 1.0))));
+        assert!(vec_eq(t.half_extents, vector!(2.0, 1.0, 1.0)));
+        for corner in obb.corners() {
+            let corner = m.transform_point(corner);
+            assert!(vec_eq(t.closest_point(corner), corner));
+        }
+        // mirrored still holds the same corners
+        let mirror = Transform::new(vector!(0.0, 0.0, 0.0), Quaternion::IDENTITY, vector!(-1.0, 1.0, 1.0)).to_matrix();
+        let t = obb.transform(&mirror);
+        assert!(vec_eq(t.center, vector!(-1.0, 1.0, 1.0)));
+        assert!(obb.corners().iter().all(|c| vec_eq(t.closest_point(mirror.transform_point(*c)), mirror.transform_point(*c))));
+    }
+
+    // camera at the origin looking down -z, 90 degrees so the sides are x = +-z
+    fn frustum() -> Frustum { Frustum::from_matrix(&math::perspective(90.0, 1.0, 1.0, 100.0)) }
+
+    #[test]
+    fn frustum_planes() {
+        let f = frustum();
+        assert!(f.contains_point(vector!(0.0, 0.0, -10.0)));
+        assert!(f.contains_point(vector!(9.0, -9.0, -10.0)));
+        assert!(!f.contains_point(vector!(11.0, 0.0, -10.0)));
+        assert!(!f.contains_point(vector!(0.0, 11.0, -10.0)));
+        assert!(!f.contains_point(vector!(0.0, 0.0, 10.0)));
+        assert!(!f.contains_point(vector!(0.0, 0.0, -0.5)));
+        assert!(!f.contains_point(vector!(0.0, 0.0, -101.0)));
+        assert!(approx_eq(f.planes[4].signed_distance(vector!(0.0, 0.0, -3.0)), 2.0));
+        // the far plane loses precision to the projection's near/far ratio
+        assert!((f.planes[5].signed_distance(vector!(0.0, 0.0, -90.0)) - 10.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn frustum_in_world_space() {
+        // camera at z = 10 turned around to look down +z
+        let camera = Transform::from_position_rotation(vector!(0.0, 0.0, 10.0), Quaternion::from_angle_vect(180.0, vector!(0.0, 1.0, 0.0)));
+        let f = Frustum::from_matrix(&(math::perspective(90.0, 1.0, 1.0, 100.0)*camera.inverse_matrix()));
+        assert!(f.contains_point(vector!(0.0, 0.0, 20.0)));
+        assert!(!f.contains_point(vector!(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn frustum_shapes() {
+        let f = frustum();
+        assert!(f.intersects_sphere(&Sphere::new(vector!(0.0, 0.0, -10.0), 1.0)));
+        // center outside, still reaching in
+        assert!(f.intersects_sphere(&Sphere::new(vector!(11.0, 0.0, -10.0), 2.0)));
+        assert!(f.intersects_sphere(&Sphere::new(vector!(0.0, 0.0, -0.5), 1.0)));
+        assert!(!f.intersects_sphere(&Sphere::new(vector!(0.0, 0.0, 5.0), 1.0)));
+        assert!(!f.intersects_sphere(&Sphere::new(vector!(20.0, 0.0, -10.0), 1.0)));
+        assert!(f.intersects_aabb(&Aabb::new(vector!(-1.0, -1.0, -11.0), vector!(1.0, 1.0, -9.0))));
+        assert!(f.intersects_aabb(&Aabb::new(vector!(10.0, -1.0, -11.0), vector!(12.0, 1.0, -9.0))));
+        assert!(!f.intersects_aabb(&Aabb::new(vector!(12.0, -1.0, -11.0), vector!(14.0, 1.0, -9.0))));
+        assert!(!f.intersects_aabb(&Aabb::new(vector!(-1.0, -1.0, -200.0), vector!(1.0, 1.0, -150.0))));
+        let long = |x: f32, degrees: f32| Obb::new(vector!(x, 0.0, -10.0), vector!(0.5, 0.5, 5.0), Quaternion::from_angle_vect(degrees, vector!(0.0, 1.0, 0.0)));
+        assert!(!f.intersects_obb(&long(17.0, 0.0)));
+        // turned so one end reaches back into view
+        assert!(f.intersects_obb(&long(17.0, 45.0)));
+    }
+}
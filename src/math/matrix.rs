@@ -13,14 +13,14 @@ pub type Matrix4x4 = Matrix<4, 4>;
 pub struct Matrix<const X: usize, const Y: usize>([[f32; X]; Y]);
 impl<const X: usize, const Y: usize> Matrix<X, Y> {
     pub const fn from_arrays(arr: [[f32; X]; Y]) -> Self { Self(arr) }
+    pub const fn as_arrays(&self) -> [[f32; X]; Y] { self.0 }
     pub fn column_major(&self) -> [[f32; Y]; X] {
-        let mut new = [[0.0; Y]; X];
-        for y in 0..Y {
-            for x in 0..X {
-                new[x][y] = self.0[y][x];
-            }
-        };
-        new
+        self.transpose().0
+    }
+    pub fn transpose(&self) -> Matrix<Y, X> {
+        Matrix::from_arrays(std::array::from_fn(|x|
+            std::array::from_fn(|y| self.0[y][x])
+        ))
     }
 }
 impl<const X: usize, const Y: usize> std::ops::Add for Matrix<X, Y> {
@@ -53,19 +53,20 @@ impl<const X: usize, const Y: usize> std::ops::Mul<f32> for Matrix<X, Y> {
         )
     }
 }
-impl<const X: usize, const Y: usize> std::ops::Mul<Matrix<Y, X>> for Matrix<X, Y> {
-    type Output = Self;
-    fn mul(self, other: Matrix<Y, X>) -> Self {
+// row by column product, (X cols, Y rows) * (C cols, X rows) => (C cols, Y rows)
+impl<const X: usize, const Y: usize, const C: usize> std::ops::Mul<Matrix<C, X>> for Matrix<X, Y> {
+    type Output = Matrix<C, Y>;
+    fn mul(self, other: Matrix<C, X>) -> Matrix<C, Y> {
         Matrix::from_arrays(std::array::from_fn(|y|
-            std::array::from_fn(|x|
-                self.0[y][x] * other.0[x][y]
+            std::array::from_fn(|c|
+                (0..X).map(|x| self.0[y][x] * other.0[x][c]).sum()
             )
         ))
     }
 }
-impl<const X: usize> std::ops::Mul<Vector<X>> for Matrix<X, X> {
-    type Output = Vector<X>;
-    fn mul(self, other: Vector<X>) -> Vector<X> {
+impl<const X: usize, const Y: usize> std::ops::Mul<Vector<X>> for Matrix<X, Y> {
+    type Output = Vector<Y>;
+    fn mul(self, other: Vector<X>) -> Vector<Y> {
         Vector::new(
             std::array::from_fn(|y| {
                 (0..X)
@@ -75,6 +76,75 @@ impl<const X: usize> std::ops::Mul<Vector<X>> for Matrix<X, X> {
         )
     }
 }
+impl<const X: usize, const Y: usize> std::ops::Neg for Matrix<X, Y> {
+    type Output = Self;
+    fn neg(self) -> Self { self * -1.0 }
+}
+
+impl<const N: usize> Matrix<N, N> {
+    pub fn identity() -> Self {
+        Self::from_arrays(std::array::from_fn(|y|
+            std::array::from_fn(|x| if x == y {1.0} else {0.0})
+        ))
+    }
+    // sum of the main diagonal
+    pub fn trace(&self) -> f32 {
+        (0..N).map(|i| self.0[i][i]).sum()
+    }
+    // gaussian elimination with partial pivoting, det = product of pivots (sign flips on row swaps)
+    pub fn determinant(&self) -> f32 {
+        let mut m = self.0;
+        let mut det = 1.0;
+        for col in 0..N {
+            let pivot = (col..N)
+                .max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))
+                .unwrap();
+            if m[pivot][col] == 0.0 { return 0.0 }
+            if pivot != col {
+                m.swap(pivot, col);
+                det = -det;
+            }
+            det *= m[col][col];
+            let pivot_row = m[col];
+            for row in m.iter_mut().skip(col+1) {
+                let factor = row[col] / pivot_row[col];
+                for (v, p) in row.iter_mut().zip(pivot_row).skip(col) { *v -= factor * p; }
+            }
+        }
+        det
+    }
+    // gauss-jordan on [self | identity], None if the matrix is singular
+    pub fn try_inverse(&self) -> Option<Self> {
+        let mut m = self.0;
+        let mut inv = Self::identity().0;
+        for col in 0..N {
+            let pivot = (col..N)
+                .max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))
+                .unwrap();
+            if m[pivot][col].abs() < f32::EPSILON { return None }
+            m.swap(pivot, col);
+            inv.swap(pivot, col);
+
+            let p = m[col][col];
+            for x in 0..N {
+                m[col][x] /= p;
+                inv[col][x] /= p;
+            }
+            for row in 0..N {
+                if row == col { continue }
+                let factor = m[row][col];
+                for x in 0..N {
+                    m[row][x] -= factor * m[col][x];
+                    inv[row][x] -= factor * inv[col][x];
+                }
+            }
+        }
+        Some(Self::from_arrays(inv))
+    }
+    pub fn inverse(&self) -> Self {
+        self.try_inverse().expect("tried to invert a singular matrix")
+    }
+}
 
 impl Matrix4x4 {
     pub const IDENTITY: Self = Self::from_arrays([
@@ -85,13 +155,21 @@ impl Matrix4x4 {
     ]);
     pub fn as_gl_slice(&self) -> [f32; 16] {
         let m = self.column_major();
-        return [
+        [
             m[0][0], m[0][1], m[0][2], m[0][3],
             m[1][0], m[1][1], m[1][2], m[1][3],
             m[2][0], m[2][1], m[2][2], m[2][3],
-            m[3][0], m[3][1], m[3][2], m[3][2],
+            m[3][0], m[3][1], m[3][2], m[3][3],
         ]
     }
+    // w = 1, translation applies
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        (*self*point.extend(1.0)).xyz()
+    }
+    // w = 0, translation doesn't
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        (*self*vector.extend(0.0)).xyz()
+    }
     //turn into macro later
     pub fn translation_mat(v: Vector3) -> Self{
         Self::from_arrays([
@@ -118,9 +196,35 @@ impl Matrix3x3 {
     // }
 }
 
+// drops the last row and column (translation/projection part)
+impl From<Matrix4x4> for Matrix3x3 {
+    fn from(m: Matrix4x4) -> Self {
+        Self::from_arrays(std::array::from_fn(|y|
+            std::array::from_fn(|x| m.0[y][x])
+        ))
+    }
+}
+// embeds into the upper left corner of an identity matrix
+impl From<Matrix3x3> for Matrix4x4 {
+    fn from(m: Matrix3x3) -> Self {
+        let mut new = Self::IDENTITY;
+        for y in 0..3 {
+            for x in 0..3 { new.0[y][x] = m.0[y][x]; }
+        }
+        new
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::math::{Matrix, Vector3, Vector4, matrix::Matrix4x4};
+    use crate::math::{Matrix, Vector, Vector3, Vector4, matrix::{Matrix3x3, Matrix4x4}};
+    use crate::vector;
+
+    fn approx_eq<const X: usize, const Y: usize>(a: Matrix<X, Y>, b: Matrix<X, Y>) -> bool {
+        a.as_arrays().iter().flatten()
+            .zip(b.as_arrays().iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-5)
+    }
 
     #[test]
     fn matrix_ops() {
@@ -171,12 +275,138 @@ mod test {
         ]);
 
         let r = Matrix::from_arrays([
-            [0.0, 5.0, 4.0],
-            [21.0, 16.0, 5.0],
-            [36.0, 21.0, 0.0]
+            [9.0, 6.0, 3.0],
+            [54.0, 42.0, 30.0],
+            [99.0, 78.0, 57.0]
         ]);
 
         assert_eq!(a*b, r);
+        assert_eq!(a*Matrix3x3::IDENTITY, a);
+        assert_eq!(Matrix3x3::IDENTITY*a, a);
+    }
+    #[test]
+    fn matrix_mul_non_square() {
+        // 2 rows 3 cols * 3 rows 2 cols => 2x2
+        let a = Matrix::from_arrays([
+            [1.0, 2.0, 3.0],
+            [4.0, 5.0, 6.0],
+        ]);
+        let b = Matrix::from_arrays([
+            [7.0, 8.0],
+            [9.0, 10.0],
+            [11.0, 12.0],
+        ]);
+        assert_eq!(a*b, Matrix::from_arrays([
+            [58.0, 64.0],
+            [139.0, 154.0],
+        ]));
+        assert_eq!(a*Vector3::new([1.0, 0.0, -1.0]), vector!(-2.0, -2.0));
+    }
+    #[test]
+    fn matrix_mul_composes_transforms() {
+        let t = Matrix4x4::translation_mat(vector!(1.0, 2.0, 3.0));
+        let s = Matrix4x4::from_arrays([
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 2.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let p = vector!(1.0, 1.0, 1.0, 1.0);
+        // scale first, then translate
+        assert_eq!((t*s)*p, vector!(3.0, 4.0, 5.0, 1.0));
+        assert_eq!(t*(s*p), (t*s)*p);
+        assert_eq!((s*t)*p, vector!(4.0, 6.0, 8.0, 1.0));
+    }
+    #[test]
+    fn transpose() {
+        let a = Matrix::from_arrays([
+            [1.0, 2.0, 3.0],
+            [4.0, 5.0, 6.0],
+        ]);
+        assert_eq!(a.transpose(), Matrix::from_arrays([
+            [1.0, 4.0],
+            [2.0, 5.0],
+            [3.0, 6.0],
+        ]));
+        assert_eq!(a.transpose().transpose(), a);
+        assert_eq!(a.column_major(), a.transpose().as_arrays());
+    }
+    #[test]
+    fn trace_and_determinant() {
+        let a = Matrix::from_arrays([
+            [0.0, 1.0, 2.0],
+            [3.0, 4.0, 5.0],
+            [6.0, 7.0, 8.0],
+        ]);
+        assert_eq!(a.trace(), 12.0);
+        assert_eq!(a.determinant(), 0.0);
+        assert_eq!(Matrix4x4::IDENTITY.determinant(), 1.0);
+
+        let b = Matrix::from_arrays([
+            [2.0, -3.0, 1.0],
+            [2.0, 0.0, -1.0],
+            [1.0, 4.0, 5.0],
+        ]);
+        assert!((b.determinant() - 49.0).abs() < 1e-4);
+        // needs a row swap
+        let c = Matrix::from_arrays([
+            [0.0, 1.0],
+            [1.0, 0.0],
+        ]);
+        assert_eq!(c.determinant(), -1.0);
+    }
+    #[test]
+    fn inverse() {
+        let a = Matrix::from_arrays([
+            [4.0, 7.0],
+            [2.0, 6.0],
+        ]);
+        assert!(approx_eq(a.inverse(), Matrix::from_arrays([
+            [0.6, -0.7],
+            [-0.2, 0.4],
+        ])));
+
+        let t = Matrix4x4::translation_mat(vector!(1.0, -2.0, 3.0));
+        assert!(approx_eq(t.inverse(), Matrix4x4::translation_mat(vector!(-1.0, 2.0, -3.0))));
+
+        let m = Matrix4x4::from_arrays([
+            [0.0, 2.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0, 3.0, 3.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert!(approx_eq(m*m.inverse(), Matrix4x4::IDENTITY));
+        assert!(approx_eq(m.inverse()*m, Matrix4x4::IDENTITY));
+    }
+    #[test]
+    fn singular_inverse() {
+        let a = Matrix::from_arrays([
+            [1.0, 2.0],
+            [2.0, 4.0],
+        ]);
+        assert_eq!(a.try_inverse(), None);
+    }
+    #[test]
+    fn matrix_conversions() {
+        let m = Matrix4x4::from_arrays([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 10.0, 11.0, 12.0],
+            [13.0, 14.0, 15.0, 16.0],
+        ]);
+        let m3 = Matrix3x3::from(m);
+        assert_eq!(m3, Matrix::from_arrays([
+            [1.0, 2.0, 3.0],
+            [5.0, 6.0, 7.0],
+            [9.0, 10.0, 11.0],
+        ]));
+        assert_eq!(Matrix4x4::from(m3), Matrix4x4::from_arrays([
+            [1.0, 2.0, 3.0, 0.0],
+            [5.0, 6.0, 7.0, 0.0],
+            [9.0, 10.0, 11.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]));
+        assert_eq!(Matrix4x4::from(Matrix3x3::IDENTITY), Matrix4x4::IDENTITY);
     }
     #[test]
     fn mat_vec_mul() {
@@ -1,10 +1,14 @@
 pub mod vectors;
 pub mod colors;
 pub mod matrix;
+pub mod transform;
+pub mod geometry;
 
-pub use vectors::{Vector, Vector3, Vector4, Quaternion};
-pub use matrix::{Matrix, Matrix4x4};
+pub use vectors::{Vector, Vector2, Vector3, Vector4, Quaternion};
+pub use matrix::{Matrix, Matrix3x3, Matrix4x4};
 pub use colors::Color;
+pub use transform::Transform;
+pub use geometry::{Aabb, Frustum, Obb, Plane, Ray, Sphere, Triangle};
 
 pub fn perspective(fov_deg: f32, aspect: f32, near: f32, far: f32) -> Matrix4x4 {
     let scaling_factor = 
@@ -0,0 +1,237 @@
+use crate::math::{Matrix4x4, Quaternion, Vector, Vector3};
+
+// position, rotation and non uniform scale of an object
+// matrix form is translation * rotation * scale (scale applied first)
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    pub position: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+}
+impl Default for Transform {
+    fn default() -> Self { Self::IDENTITY }
+}
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        position: Vector::new([0.0, 0.0, 0.0]),
+        rotation: Quaternion::IDENTITY,
+        scale: Vector::new([1.0, 1.0, 1.0]),
+    };
+
+    pub fn new(position: Vector3, rotation: Quaternion, scale: Vector3) -> Self {
+        Self { position, rotation, scale }
+    }
+    pub fn from_position(position: Vector3) -> Self {
+        Self { position, ..Self::IDENTITY }
+    }
+    pub fn from_position_rotation(position: Vector3, rotation: Quaternion) -> Self {
+        Self { position, rotation, ..Self::IDENTITY }
+    }
+
+    pub fn translate(&mut self, offset: Vector3) {
+        self.position += offset;
+    }
+    // rotates around the objects own axes, renormalized so many small turns don't drift
+    pub fn rotate(&mut self, rot: Quaternion) {
+        self.rotation = (self.rotation*rot).normalize();
+    }
+
+    pub fn to_matrix(&self) -> Matrix4x4 {
+        let r = self.rotation.to_matrix4x4().as_arrays();
+        let (p, s) = (self.position.0, self.scale.0);
+        // same as translation_mat(p) * rotation * scale without the two matrix products
+        Matrix4x4::from_arrays([
+            [r[0][0]*s[0], r[0][1]*s[1], r[0][2]*s[2], p[0]],
+            [r[1][0]*s[0], r[1][1]*s[1], r[1][2]*s[2], p[1]],
+            [r[2][0]*s[0], r[2][1]*s[1], r[2][2]*s[2], p[2]],
+            [0.0,          0.0,          0.0,          1.0],
+        ])
+    }
+    // scale^-1 * rotation^T * translation^-1, doesn't need a general inverse
+    pub fn inverse_matrix(&self) -> Matrix4x4 {
+        let r = self.rotation.to_matrix4x4().as_arrays();
+        let s = self.scale.0;
+        let rows: [[f32; 3]; 3] = std::array::from_fn(|y|
+            std::array::from_fn(|x| r[x][y] / s[y])
+        );
+        let p = self.position.0;
+        let t: [f32; 3] = std::array::from_fn(|y|
+            -(rows[y][0]*p[0] + rows[y][1]*p[1] + rows[y][2]*p[2])
+        );
+        Matrix4x4::from_arrays([
+            [rows[0][0], rows[0][1], rows[0][2], t[0]],
+            [rows[1][0], rows[1][1], rows[1][2], t[1]],
+            [rows[2][0], rows[2][1], rows[2][2], t[2]],
+            [0.0,        0.0,        0.0,        1.0],
+        ])
+    }
+    // exact only for uniform scale, use inverse_matrix otherwise
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.conjugate();
+        let scale = Vector::new(self.scale.0.map(|s| 1.0/s));
+        let position = (self.position.rotate(rotation)*scale).invert();
+        Self { position, rotation, scale }
+    }
+
+    // self is the parent, child is relative to it. the result is the childs world transform
+    // non uniform parent scale combined with child rotation would need shear, which is dropped
+    pub fn combine(&self, child: &Self) -> Self {
+        Self {
+            position: self.transform_point(child.position),
+            rotation: self.rotation*child.rotation,
+            scale: self.scale*child.scale,
+        }
+    }
+
+    // applies scale, rotation and translation
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        (point*self.scale).rotate(self.rotation)+self.position
+    }
+    // applies scale and rotation, ignores translation
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        (vector*self.scale).rotate(self.rotation)
+    }
+    // only rotates, keeps the length
+    pub fn transform_direction(&self, direction: Vector3) -> Vector3 {
+        direction.rotate(self.rotation)
+    }
+    pub fn inverse_transform_point(&self, point: Vector3) -> Vector3 {
+        (point-self.position).rotate(self.rotation.conjugate())/self.scale
+    }
+
+    pub fn forward(&self) -> Vector3 { self.transform_direction(Vector::new([0.0, 0.0, 1.0])) }
+    pub fn right(&self) -> Vector3 { self.transform_direction(Vector::new([1.0, 0.0, 0.0])) }
+    pub fn up(&self) -> Vector3 { self.transform_direction(Vector::new([0.0, 1.0, 0.0])) }
+
+    // t=0 => self, t=1 => other
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            position: self.position+(other.position-self.position)*t,
+            rotation: self.rotation.nlerp(&other.rotation, t),
+            scale: self.scale+(other.scale-self.scale)*t,
+        }
+    }
+}
+impl std::ops::Mul for Transform {
+    type Output = Self;
+    fn mul(self, child: Self) -> Self { self.combine(&child) }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{Matrix, Matrix4x4, Quaternion, Transform, Vector, Vector3, Vector4};
+    use crate::vector;
+
+    fn approx_eq<const N: usize>(a: Vector<N>, b: Vector<N>) -> bool {
+        (0..N).all(|i| (a.0[i]-b.0[i]).abs() < 1e-5)
+    }
+    fn approx_eq_mat(a: Matrix4x4, b: Matrix4x4) -> bool {
+        a.as_arrays().iter().flatten()
+            .zip(b.as_arrays().iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-5)
+    }
+    fn sample() -> Transform {
+        Transform::new(
+            vector!(1.0, 2.0, 3.0),
+            Quaternion::from_angle_vect(90.0, vector!(0.0, 1.0, 0.0)),
+            vector!(2.0, 1.0, 0.5),
+        )
+    }
+
+    #[test]
+    fn identity_matrix() {
+        assert_eq!(Transform::IDENTITY.to_matrix(), Matrix4x4::IDENTITY);
+        assert_eq!(Transform::default().to_matrix(), Matrix4x4::IDENTITY);
+    }
+
+    #[test]
+    fn matrix_matches_trs() {
+        let t = sample();
+        let scale = Matrix::from_arrays([
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.5, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let trs = Matrix4x4::translation_mat(t.position) * t.rotation.to_matrix4x4() * scale;
+        assert!(approx_eq_mat(t.to_matrix(), trs));
+    }
+
+    #[test]
+    fn transform_point() {
+        let t = sample();
+        let p = vector!(1.0, 0.0, 0.0);
+        // scaled to (2,0,0), rotated 90deg around y to (0,0,-2), moved by (1,2,3)
+        assert!(approx_eq(t.transform_point(p), vector!(1.0, 2.0, 1.0)));
+        let m = t.to_matrix()*Vector4::new([1.0, 0.0, 0.0, 1.0]);
+        assert!(approx_eq(m, vector!(1.0, 2.0, 1.0, 1.0)));
+
+        assert!(approx_eq(t.transform_vector(p), vector!(0.0, 0.0, -2.0)));
+        assert!(approx_eq(t.transform_direction(p), vector!(0.0, 0.0, -1.0)));
+        assert!(approx_eq(t.inverse_transform_point(t.transform_point(p)), p));
+    }
+
+    #[test]
+    fn inverse_matrix() {
+        let t = sample();
+        assert!(approx_eq_mat(t.inverse_matrix(), t.to_matrix().inverse()));
+        assert!(approx_eq_mat(t.to_matrix()*t.inverse_matrix(), Matrix4x4::IDENTITY));
+    }
+
+    #[test]
+    fn inverse_transform() {
+        let t = Transform::new(
+            vector!(1.0, 2.0, 3.0),
+            Quaternion::from_angle_vect(30.0, vector!(1.0, 1.0, 0.0)),
+            vector!(2.0, 2.0, 2.0),
+        );
+        assert!(approx_eq_mat(t.inverse().to_matrix(), t.inverse_matrix()));
+        assert!(approx_eq_mat((t*t.inverse()).to_matrix(), Matrix4x4::IDENTITY));
+    }
+
+    #[test]
+    fn combine_with_parent() {
+        let parent = Transform::new(
+            vector!(0.0, 1.0, 0.0),
+            Quaternion::from_angle_vect(90.0, vector!(0.0, 0.0, 1.0)),
+            vector!(2.0, 2.0, 2.0),
+        );
+        let child = Transform::new(
+            vector!(1.0, 0.0, 0.0),
+            Quaternion::from_angle_vect(45.0, vector!(1.0, 0.0, 0.0)),
+            vector!(1.0, 1.0, 1.0),
+        );
+        let world = parent*child;
+        assert!(approx_eq(world.position, vector!(0.0, 3.0, 0.0)));
+        assert!(approx_eq_mat(world.to_matrix(), parent.to_matrix()*child.to_matrix()));
+
+        let p: Vector3 = vector!(0.5, -1.0, 2.0);
+        assert!(approx_eq(world.transform_point(p), parent.transform_point(child.transform_point(p))));
+    }
+
+    #[test]
+    fn lerp() {
+        let a = Transform::IDENTITY;
+        let b = Transform::new(
+            vector!(2.0, 0.0, -4.0),
+            Quaternion::from_angle_vect(90.0, vector!(0.0, 1.0, 0.0)),
+            vector!(3.0, 3.0, 3.0),
+        );
+        let half = a.lerp(&b, 0.5);
+        assert!(approx_eq(half.position, vector!(1.0, 0.0, -2.0)));
+        assert!(approx_eq(half.scale, vector!(2.0, 2.0, 2.0)));
+        let expected = Quaternion::from_angle_vect(45.0, vector!(0.0, 1.0, 0.0));
+        assert!(approx_eq(half.transform_direction(vector!(1.0, 0.0, 0.0)), vector!(1.0, 0.0, 0.0).rotate(expected)));
+
+        assert!(approx_eq_mat(a.lerp(&b, 0.0).to_matrix(), a.to_matrix()));
+        assert!(approx_eq_mat(a.lerp(&b, 1.0).to_matrix(), b.to_matrix()));
+    }
+
+    #[test]
+    fn many_small_rotations_stay_unit() {
+        let mut t = Transform::IDENTITY;
+        let step = Quaternion::from_angle_vect(0.37, vector!(0.3, 1.0, 0.2));
+        for _ in 0..100_000 { t.rotate(step); }
+        assert!((t.rotation.length() - 1.0).abs() < 1e-5);
+    }
+}
@@ -13,6 +13,7 @@ macro_rules! vector {
     };
 }
 
+pub type Vector2 = Vector<2>;
 pub type Vector3 = Vector<3>;
 pub type Vector4 = Vector<4>;
 
@@ -35,7 +36,52 @@ impl<const N: usize> Vector<N> {
         (0..N).map(|i| self.0[i]*other.0[i]).sum()
     }
     pub fn invert(&self) -> Self {
-        Self(std::array::from_fn(|i| self.0[i]*-1.0))
+        Self(std::array::from_fn(|i| -self.0[i]))
+    }
+    pub fn length_squared(&self) -> f32 { self.dot(self) }
+    pub fn distance(&self, other: &Self) -> f32 { (*other-*self).length() }
+    pub fn distance_squared(&self, other: &Self) -> f32 { (*other-*self).length_squared() }
+    // t = 0 is self, t = 1 is other, outside of that it keeps going
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] + (other.0[i]-self.0[i])*t))
+    }
+    // bounces off a surface with the given unit normal
+    pub fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal*(2.0*self.dot(normal))
+    }
+    // the part of self that points along onto, onto doesn't need to be unit
+    pub fn project(&self, onto: &Self) -> Self {
+        let length_squared = onto.length_squared();
+        if length_squared == 0.0 { return Self([0.0; N]) }
+        *onto*(self.dot(onto)/length_squared)
+    }
+    // what's left after the projection, perpendicular to onto
+    pub fn reject(&self, onto: &Self) -> Self {
+        *self - self.project(onto)
+    }
+    // radians in 0..pi, 0 if either is zero length
+    pub fn angle(&self, other: &Self) -> f32 {
+        let lengths = self.length()*other.length();
+        if lengths == 0.0 { return 0.0 }
+        (self.dot(other)/lengths).clamp(-1.0, 1.0).acos()
+    }
+
+    // per component
+    pub fn min(&self, other: &Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i].min(other.0[i])))
+    }
+    pub fn max(&self, other: &Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i].max(other.0[i])))
+    }
+    pub fn clamp(&self, min: &Self, max: &Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i].clamp(min.0[i], max.0[i])))
+    }
+    pub fn abs(&self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i].abs()))
+    }
+    // any components in any order, v.swizzle([2, 0]) is (z, x)
+    pub fn swizzle<const M: usize>(&self, components: [usize; M]) -> Vector<M> {
+        Vector(components.map(|i| self.0[i]))
     }
 }
 
@@ -74,8 +120,57 @@ impl<const N: usize> std::ops::Mul<f32> for Vector<N> {
         Self(std::array::from_fn(|i| self.0[i]*rhs))
     }
 }
+impl<const N: usize> std::ops::Div<f32> for Vector<N> {
+    type Output = Self;
+    fn div(self, rhs: f32) -> Self {
+        Self(std::array::from_fn(|i| self.0[i]/rhs))
+    }
+}
+impl<const N: usize> std::ops::Neg for Vector<N> {
+    type Output = Self;
+    fn neg(self) -> Self { self.invert() }
+}
+impl<const N: usize> std::ops::AddAssign for Vector<N> {
+    fn add_assign(&mut self, other: Self) { *self = *self + other }
+}
+impl<const N: usize> std::ops::SubAssign for Vector<N> {
+    fn sub_assign(&mut self, other: Self) { *self = *self - other }
+}
+impl<const N: usize> std::ops::MulAssign for Vector<N> {
+    fn mul_assign(&mut self, other: Self) { *self = *self * other }
+}
+impl<const N: usize> std::ops::DivAssign for Vector<N> {
+    fn div_assign(&mut self, other: Self) { *self = *self / other }
+}
+impl<const N: usize> std::ops::MulAssign<f32> for Vector<N> {
+    fn mul_assign(&mut self, rhs: f32) { *self = *self * rhs }
+}
+impl<const N: usize> std::ops::DivAssign<f32> for Vector<N> {
+    fn div_assign(&mut self, rhs: f32) { *self = *self / rhs }
+}
+impl<const N: usize> std::ops::Index<usize> for Vector<N> {
+    type Output = f32;
+    fn index(&self, i: usize) -> &f32 { &self.0[i] }
+}
+impl<const N: usize> std::ops::IndexMut<usize> for Vector<N> {
+    fn index_mut(&mut self, i: usize) -> &mut f32 { &mut self.0[i] }
+}
+
+impl Vector2 {
+    pub const fn x(&self) -> f32 { self.0[0] }
+    pub const fn y(&self) -> f32 { self.0[1] }
+    pub const fn yx(&self) -> Self { Self([self.0[1], self.0[0]]) }
+    pub const fn extend(&self, z: f32) -> Vector3 { Vector([self.0[0], self.0[1], z]) }
+}
 
 impl Vector4 {
+    pub const fn x(&self) -> f32 { self.0[0] }
+    pub const fn y(&self) -> f32 { self.0[1] }
+    pub const fn z(&self) -> f32 { self.0[2] }
+    pub const fn w(&self) -> f32 { self.0[3] }
+    pub const fn xy(&self) -> Vector2 { Vector([self.0[0], self.0[1]]) }
+    pub const fn xyz(&self) -> Vector3 { Vector([self.0[0], self.0[1], self.0[2]]) }
+
     pub fn scale(self, scale: Vector3) -> Self {
         Matrix4x4::from_arrays([
             [scale.0[0], 0.0, 0.0, 0.0],
@@ -84,11 +179,12 @@ impl Vector4 {
             [0.0, 0.0, 0.0, 1.0],
         ])*self
     }
+    // moves points (w = 1), directions (w = 0) stay put
     pub fn translate(self, trans: Vector3) -> Self {
         Matrix4x4::from_arrays([
-            [0.0, 0.0, 0.0, trans.0[0]],
-            [0.0, 0.0, 0.0, trans.0[1]],
-            [0.0, 0.0, 0.0, trans.0[2]],
+            [1.0, 0.0, 0.0, trans.0[0]],
+            [0.0, 1.0, 0.0, trans.0[1]],
+            [0.0, 0.0, 1.0, trans.0[2]],
             [0.0, 0.0, 0.0, 1.0],
         ])*self
     }
@@ -99,6 +195,14 @@ impl Vector4 {
 }
 
 impl Vector3 {
+    pub const fn x(&self) -> f32 { self.0[0] }
+    pub const fn y(&self) -> f32 { self.0[1] }
+    pub const fn z(&self) -> f32 { self.0[2] }
+    pub const fn xy(&self) -> Vector2 { Vector([self.0[0], self.0[1]]) }
+    pub const fn xz(&self) -> Vector2 { Vector([self.0[0], self.0[2]]) }
+    pub const fn yz(&self) -> Vector2 { Vector([self.0[1], self.0[2]]) }
+    pub const fn extend(&self, w: f32) -> Vector4 { Vector([self.0[0], self.0[1], self.0[2], w]) }
+
     pub fn scale(self, scale: Self) -> Self {
         Matrix3x3::from_arrays([
             [scale.0[0], 0.0, 0.0],
@@ -106,12 +210,9 @@ impl Vector3 {
             [0.0, 0.0, scale.0[2]],
         ])*self
     }
+    // a 3x3 matrix can't translate, it's just an add
     pub fn translate(self, trans: Self) -> Self {
-        Matrix3x3::from_arrays([
-            [0.0, 0.0, trans.0[0]],
-            [0.0, 0.0, trans.0[1]],
-            [0.0, 0.0, trans.0[2]],
-        ])*self
+        self + trans
     }
     pub fn cross(self, rhs: Self) -> Self {
         Self::new([
@@ -123,22 +224,16 @@ impl Vector3 {
     pub fn rotate(self, r: Quaternion) -> Self {
         r.to_matrix3x3()*self
     }
-    //ewww stinkyyy
-    /// takes in radians [yaw, pitch, roll]
+    /// takes in radians [yaw, pitch, roll], same order as Quaternion::from_euler
     pub fn rotate_euler(&self, rot: Vector3) -> Self {
-        //rewrite to add roll
-        let yaw = rot.0[0];
-        let pitch = rot.0[1];
-        let roll = rot.0[2];
-        Self::new([
-            yaw.cos() * pitch.cos(),
-            pitch.sin(),
-            yaw.sin() * pitch.cos(),
-        ])
+        self.rotate(Quaternion::from_euler(rot.0[0], rot.0[1], rot.0[2]))
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+// rotations. the angle/axis constructors don't care about the axis length,
+// everything else expects unit quaternions and keeps them unit.
+// euler angles are [yaw, pitch, roll] around y, x and z, applied in that order (y * x * z)
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Quaternion {
     pub w: f32,
     pub x: f32,
@@ -173,18 +268,136 @@ impl Quaternion {
     pub fn from_angle_vect(angle: f32, v: Vector3) -> Self {
         Self::from(vector!(angle.to_radians(), v.0[0], v.0[1], v.0[2]))
     }
-
+    pub const fn new(w: f32, x: f32, y: f32, z: f32) -> Self { Self { w, x, y, z } }
+    pub fn from_axis_angle(axis: Vector3, radians: f32) -> Self { Self::from_radian_vect(radians, axis) }
+    // radians
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        Self::from_radian_vect(yaw, vector!(0.0, 1.0, 0.0))
+            * Self::from_radian_vect(pitch, vector!(1.0, 0.0, 0.0))
+            * Self::from_radian_vect(roll, vector!(0.0, 0.0, 1.0))
+    }
+    // [yaw, pitch, roll] in radians, the inverse of from_euler.
+    // pitch is within -pi/2..pi/2, straight up or down the yaw/roll split is arbitrary
     pub fn to_euler(&self) -> Vector3 {
-        let w = self.w;
-        let x = self.x;
-        let y = self.y;
-        let z = self.z;
+        let (w, x, y, z) = (self.w, self.x, self.y, self.z);
         Vector3::new([
-            (2.0*(w*y - x*z)).atan2(w*w + x*x - y*y - z*z),
-            (2.0*(w*x + y*z)).asin(),
-            (2.0*(w*z - x*y)).atan2(w*w - x*x - y*y + z*z)
+            (2.0*(x*z + y*w)).atan2(1.0 - 2.0*(x*x + y*y)),
+            (2.0*(x*w - y*z)).clamp(-1.0, 1.0).asin(),
+            (2.0*(x*y + z*w)).atan2(1.0 - 2.0*(x*x + z*z)),
         ])
     }
+    // (unit axis, radians in 0..2pi). the identity has no axis, it gets x
+    pub fn to_axis_angle(&self) -> (Vector3, f32) {
+        let q = self.normalize();
+        let s = (1.0 - q.w*q.w).max(0.0).sqrt();
+        if s < 1e-6 { return (vector!(1.0, 0.0, 0.0), 0.0) }
+        (vector!(q.x/s, q.y/s, q.z/s), 2.0*q.w.clamp(-1.0, 1.0).acos())
+    }
+
+    // from a pure rotation matrix (no scale), picks the numerically stable branch
+    pub fn from_matrix(m: &Matrix3x3) -> Self {
+        let m = m.as_arrays();
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt()*2.0;
+            Self::new(0.25*s, (m[2][1] - m[1][2])/s, (m[0][2] - m[2][0])/s, (m[1][0] - m[0][1])/s)
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt()*2.0;
+            Self::new((m[2][1] - m[1][2])/s, 0.25*s, (m[0][1] + m[1][0])/s, (m[0][2] + m[2][0])/s)
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt()*2.0;
+            Self::new((m[0][2] - m[2][0])/s, (m[0][1] + m[1][0])/s, 0.25*s, (m[1][2] + m[2][1])/s)
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt()*2.0;
+            Self::new((m[1][0] - m[0][1])/s, (m[0][2] + m[2][0])/s, (m[1][2] + m[2][1])/s, 0.25*s)
+        };
+        q.normalize()
+    }
+    // turns +z (Transform::forward) to forward, with +y as close to up as it gets.
+    // forward parallel to up has no unique answer, it is then only turned to forward
+    pub fn look_rotation(forward: Vector3, up: Vector3) -> Self {
+        let z = forward.normalize();
+        let x = up.cross(z);
+        if x.length() < 1e-6 { return Self::from_to_rotation(vector!(0.0, 0.0, 1.0), z) }
+        let x = x.normalize();
+        let y = z.cross(x);
+        Self::from_matrix(&Matrix3x3::from_arrays([
+            [x.0[0], y.0[0], z.0[0]],
+            [x.0[1], y.0[1], z.0[1]],
+            [x.0[2], y.0[2], z.0[2]],
+        ]))
+    }
+    // the shortest rotation taking the direction of from to the direction of to
+    pub fn from_to_rotation(from: Vector3, to: Vector3) -> Self {
+        let (from, to) = (from.normalize(), to.normalize());
+        let dot = from.dot(&to);
+        if dot < -1.0 + 1e-6 {
+            // opposite, half a turn around anything perpendicular
+            let axis = vector!(1.0, 0.0, 0.0).cross(from);
+            let axis = if axis.length() < 1e-6 { vector!(0.0, 1.0, 0.0).cross(from) } else { axis };
+            return Self::from_radian_vect(std::f32::consts::PI, axis)
+        }
+        let axis = from.cross(to);
+        Self::new(1.0 + dot, axis.0[0], axis.0[1], axis.0[2]).normalize()
+    }
+
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.w*other.w + self.x*other.x + self.y*other.y + self.z*other.z
+    }
+    pub fn length(&self) -> f32 { self.dot(self).sqrt() }
+    // rotations picked up one after another drift away from length 1, this puts them back
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.0 { return Self::IDENTITY }
+        Self::new(self.w/length, self.x/length, self.y/length, self.z/length)
+    }
+    // the opposite rotation for unit quaternions
+    pub fn conjugate(&self) -> Self { Self::new(self.w, -self.x, -self.y, -self.z) }
+    pub fn inverse(&self) -> Self {
+        let length_squared = self.dot(self);
+        let c = self.conjugate();
+        Self::new(c.w/length_squared, c.x/length_squared, c.y/length_squared, c.z/length_squared)
+    }
+    // radians between the two orientations, 0..pi
+    pub fn angle_to(&self, other: &Self) -> f32 {
+        2.0*self.dot(other).abs().clamp(0.0, 1.0).acos()
+    }
+
+    // normalized lerp, takes the short way around. cheaper than slerp, not constant speed
+    pub fn nlerp(&self, other: &Self, t: f32) -> Self {
+        let b = if self.dot(other) < 0.0 { -*other } else { *other };
+        Self::new(
+            self.w + (b.w - self.w)*t,
+            self.x + (b.x - self.x)*t,
+            self.y + (b.y - self.y)*t,
+            self.z + (b.z - self.z)*t,
+        ).normalize()
+    }
+    // constant angular speed, the short way around. t=0 => self, t=1 => other
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        let dot = self.dot(other);
+        let (b, dot) = if dot < 0.0 { (-*other, -dot) } else { (*other, dot) };
+        // nearly the same rotation, the sine below would divide by ~0
+        if dot > 0.9995 { return self.nlerp(&b, t) }
+        let theta = dot.acos();
+        let (wa, wb) = (((1.0 - t)*theta).sin()/theta.sin(), (t*theta).sin()/theta.sin());
+        Self::new(
+            self.w*wa + b.w*wb,
+            self.x*wa + b.x*wb,
+            self.y*wa + b.y*wb,
+            self.z*wa + b.z*wb,
+        )
+    }
+
+    // every component within epsilon
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        (self.w - other.w).abs() <= epsilon && (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon && (self.z - other.z).abs() <= epsilon
+    }
+    // q and -q are the same rotation
+    pub fn rotation_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.approx_eq(other, epsilon) || self.approx_eq(&-*other, epsilon)
+    }
     
     pub fn to_matrix4x4(&self) -> Matrix4x4 {
         let (w, x, y, z) = (self.w, self.x, self.y, self.z);
@@ -238,7 +451,10 @@ impl Quaternion {
     pub const IDENTITY: Self = Self{w: 1.0, x: 0.0, y: 0.0, z: 0.0};
 
 }
-//TODO write unit test for quat operations
+impl std::ops::Neg for Quaternion {
+    type Output = Self;
+    fn neg(self) -> Self { Self::new(-self.w, -self.x, -self.y, -self.z) }
+}
 impl std::ops::Mul for Quaternion {
     type Output = Self;
     fn mul(self, other: Self) -> Self::Output {
@@ -254,7 +470,7 @@ impl std::ops::Mul for Quaternion {
 #[cfg(test)]
 mod test {
     use std::f32::consts::PI;
-    use crate::math::{Vector4, Vector, Quaternion};
+    use crate::math::{Matrix3x3, Vector2, Vector3, Vector4, Vector, Quaternion};
 
     fn approx_eq(a: f32, b: f32) -> bool {
         (a - b).abs() < 1e-6
@@ -274,6 +490,122 @@ mod test {
         dbg!(v.rotate(q), w);
     }
 
+    #[test]
+    fn translate_vector() {
+        let offset = vector!(1.0, -2.0, 3.0);
+        assert_eq!(vector!(1.0, 1.0, 1.0).translate(offset), vector!(2.0, -1.0, 4.0));
+        assert_eq!(vector!(1.0, 1.0, 1.0, 1.0).translate(offset), vector!(2.0, -1.0, 4.0, 1.0));
+        // directions don't move
+        assert_eq!(vector!(1.0, 1.0, 1.0, 0.0).translate(offset), vector!(1.0, 1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn rotate_euler_vector() {
+        let v = vector!(1.0, 2.0, 3.0);
+        assert_eq!(v.rotate_euler(vector!(0.0, 0.0, 0.0)), v);
+        // yaw turns +z to +x, pitch +z to -y, roll +x to +y
+        assert!(vec_eq(vector!(0.0, 0.0, 1.0).rotate_euler(vector!(PI/2.0, 0.0, 0.0)), vector!(1.0, 0.0, 0.0)));
+        assert!(vec_eq(vector!(0.0, 0.0, 1.0).rotate_euler(vector!(0.0, PI/2.0, 0.0)), vector!(0.0, -1.0, 0.0)));
+        assert!(vec_eq(vector!(1.0, 0.0, 0.0).rotate_euler(vector!(0.0, 0.0, PI/2.0)), vector!(0.0, 1.0, 0.0)));
+        let rot = vector!(0.3, -0.4, 1.1);
+        assert!(vec_eq(v.rotate_euler(rot), v.rotate(Quaternion::from_euler(0.3, -0.4, 1.1))));
+        assert!(approx_eq_eps(v.rotate_euler(rot).length(), v.length(), 1e-5));
+    }
+
+    #[test]
+    fn vector_lerp_and_distance() {
+        let a = vector!(0.0, 2.0, -4.0);
+        let b = vector!(4.0, 2.0, 0.0);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.25), vector!(1.0, 2.0, -3.0));
+        assert_eq!(a.lerp(&b, 2.0), vector!(8.0, 2.0, 4.0));
+        assert_eq!(vector!(0.0, 0.0).distance(&vector!(3.0, 4.0)), 5.0);
+        assert_eq!(vector!(0.0, 0.0).distance_squared(&vector!(3.0, 4.0)), 25.0);
+        assert_eq!(vector!(3.0, 4.0).length_squared(), 25.0);
+    }
+
+    #[test]
+    fn vector_reflect_project_reject() {
+        let up = vector!(0.0, 1.0, 0.0);
+        assert_eq!(vector!(1.0, -1.0, 0.0).reflect(&up), vector!(1.0, 1.0, 0.0));
+        let v = vector!(3.0, 4.0, 5.0);
+        // onto doesn't need to be unit
+        assert_eq!(v.project(&vector!(0.0, 2.0, 0.0)), vector!(0.0, 4.0, 0.0));
+        assert_eq!(v.reject(&up), vector!(3.0, 0.0, 5.0));
+        assert_eq!(v.project(&up) + v.reject(&up), v);
+        assert_eq!(v.project(&vector!(0.0, 0.0, 0.0)), vector!(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn vector_angle() {
+        let x = vector!(1.0, 0.0, 0.0);
+        assert!(approx_eq(x.angle(&vector!(0.0, 3.0, 0.0)), PI/2.0));
+        assert!(approx_eq(x.angle(&vector!(2.0, 2.0, 0.0)), PI/4.0));
+        assert!(approx_eq(x.angle(&-x), PI));
+        assert_eq!(x.angle(&x), 0.0);
+        assert_eq!(x.angle(&vector!(0.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn vector_components() {
+        let a = vector!(1.0, -5.0, 3.0);
+        let b = vector!(-2.0, 4.0, 3.0);
+        assert_eq!(a.min(&b), vector!(-2.0, -5.0, 3.0));
+        assert_eq!(a.max(&b), vector!(1.0, 4.0, 3.0));
+        assert_eq!(a.abs(), vector!(1.0, 5.0, 3.0));
+        assert_eq!(a.clamp(&vector!(0.0, 0.0, 0.0), &vector!(2.0, 2.0, 2.0)), vector!(1.0, 0.0, 2.0));
+    }
+
+    #[test]
+    fn vector_ops() {
+        let mut v = vector!(1.0, 2.0, 3.0);
+        assert_eq!(-v, vector!(-1.0, -2.0, -3.0));
+        v += vector!(1.0, 1.0, 1.0);
+        assert_eq!(v, vector!(2.0, 3.0, 4.0));
+        v -= vector!(0.0, 1.0, 2.0);
+        assert_eq!(v, vector!(2.0, 2.0, 2.0));
+        v *= vector!(1.0, 2.0, 3.0);
+        assert_eq!(v, vector!(2.0, 4.0, 6.0));
+        v /= vector!(2.0, 2.0, 3.0);
+        assert_eq!(v, vector!(1.0, 2.0, 2.0));
+        v *= 3.0;
+        assert_eq!(v, vector!(3.0, 6.0, 6.0));
+        v /= 3.0;
+        assert_eq!(v, vector!(1.0, 2.0, 2.0));
+        assert_eq!(v/2.0, vector!(0.5, 1.0, 1.0));
+        assert_eq!(v[1], 2.0);
+        v[2] = 7.0;
+        assert_eq!(v, vector!(1.0, 2.0, 7.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn vector_index_out_of_range() {
+        let v = vector!(1.0, 2.0);
+        let _ = v[2];
+    }
+
+    #[test]
+    fn vector_accessors_and_swizzles() {
+        let v2: Vector2 = vector!(1.0, 2.0);
+        assert_eq!((v2.x(), v2.y()), (1.0, 2.0));
+        assert_eq!(v2.yx(), vector!(2.0, 1.0));
+        assert_eq!(v2.extend(3.0), vector!(1.0, 2.0, 3.0));
+        let v3 = vector!(1.0, 2.0, 3.0);
+        assert_eq!((v3.x(), v3.y(), v3.z()), (1.0, 2.0, 3.0));
+        assert_eq!(v3.xy(), vector!(1.0, 2.0));
+        assert_eq!(v3.xz(), vector!(1.0, 3.0));
+        assert_eq!(v3.yz(), vector!(2.0, 3.0));
+        assert_eq!(v3.extend(1.0), vector!(1.0, 2.0, 3.0, 1.0));
+        let v4 = vector!(1.0, 2.0, 3.0, 4.0);
+        assert_eq!((v4.x(), v4.y(), v4.z(), v4.w()), (1.0, 2.0, 3.0, 4.0));
+        assert_eq!(v4.xy(), vector!(1.0, 2.0));
+        assert_eq!(v4.xyz(), v3);
+        assert_eq!(v3.swizzle([2, 1, 0]), vector!(3.0, 2.0, 1.0));
+        assert_eq!(v4.swizzle([3, 3]), vector!(4.0, 4.0));
+    }
+
     #[test]
     fn cross_vector() {
         let a = vector!(3.0, 0.0, 2.0);
@@ -299,6 +631,160 @@ mod test {
         let id = Quaternion::from_angle_vect(90.0, vector!(0.0, 1.0, 0.0));
         
         let mul = q*id;
-        dbg!(mul);
+        assert!(mul.rotation_eq(&Quaternion::from_angle_vect(180.0, vector!(0.0, 1.0, 0.0)), 1e-6));
+    }
+
+    fn vec_eq(a: Vector3, b: Vector3) -> bool {
+        (0..3).all(|i| approx_eq_eps(a.0[i], b.0[i], 1e-5))
+    }
+    fn approx_eq_eps(a: f32, b: f32, eps: f32) -> bool {
+        (a - b).abs() < eps
+    }
+    fn samples() -> Vec<Quaternion> {
+        vec![
+            Quaternion::IDENTITY,
+            Quaternion::from_angle_vect(90.0, vector!(0.0, 1.0, 0.0)),
+            Quaternion::from_angle_vect(179.0, vector!(1.0, 0.0, 0.0)),
+            Quaternion::from_angle_vect(180.0, vector!(0.0, 0.0, 1.0)),
+            Quaternion::from_angle_vect(33.0, vector!(1.0, -2.0, 0.5)),
+            Quaternion::from_angle_vect(-250.0, vector!(0.2, 0.1, 1.0)),
+        ]
+    }
+
+    #[test]
+    fn quat_normalize() {
+        let q = Quaternion::new(2.0, 0.0, 2.0, 0.0).normalize();
+        assert!(q.approx_eq(&Quaternion::new(0.70710677, 0.0, 0.70710677, 0.0), 1e-6));
+        assert!(approx_eq(q.length(), 1.0));
+        assert_eq!(Quaternion::new(0.0, 0.0, 0.0, 0.0).normalize(), Quaternion::IDENTITY);
+    }
+
+    #[test]
+    fn quat_conjugate_and_inverse() {
+        for q in samples() {
+            assert!((q*q.conjugate()).approx_eq(&Quaternion::IDENTITY, 1e-6));
+            assert!(q.inverse().approx_eq(&q.conjugate(), 1e-6));
+        }
+        // inverse doesn't need a unit quaternion
+        let q = Quaternion::new(1.0, 2.0, 3.0, 4.0);
+        assert!((q*q.inverse()).approx_eq(&Quaternion::IDENTITY, 1e-6));
+        let v = vector!(1.0, 2.0, 3.0);
+        let r = samples()[4];
+        assert!(vec_eq(v.rotate(r).rotate(r.inverse()), v));
+    }
+
+    #[test]
+    fn quat_dot_and_angle() {
+        let a = Quaternion::from_angle_vect(30.0, vector!(0.0, 1.0, 0.0));
+        let b = Quaternion::from_angle_vect(100.0, vector!(0.0, 1.0, 0.0));
+        assert!(approx_eq(a.dot(&a), 1.0));
+        assert!(approx_eq_eps(a.angle_to(&b), 70f32.to_radians(), 1e-5));
+        // -b is the same orientation
+        assert!(approx_eq_eps(a.angle_to(&-b), 70f32.to_radians(), 1e-5));
+    }
+
+    #[test]
+    fn quat_equality() {
+        let q = samples()[4];
+        assert_eq!(q, q);
+        assert_ne!(q, -q);
+        assert!(q.rotation_eq(&-q, 1e-6));
+        assert!(!q.approx_eq(&-q, 1e-6));
+        assert!(!q.rotation_eq(&samples()[1], 1e-3));
+    }
+
+    #[test]
+    fn quat_slerp() {
+        let y = vector!(0.0, 1.0, 0.0);
+        let a = Quaternion::IDENTITY;
+        let b = Quaternion::from_angle_vect(90.0, y);
+        assert!(a.slerp(&b, 0.0).approx_eq(&a, 1e-6));
+        assert!(a.slerp(&b, 1.0).approx_eq(&b, 1e-6));
+        for t in [0.25, 0.5, 0.8] {
+            let expected = Quaternion::from_angle_vect(90.0*t, y);
+            assert!(a.slerp(&b, t).approx_eq(&expected, 1e-5));
+            // nlerp ends up at the same place but not at the same speed
+            assert!(approx_eq(a.nlerp(&b, t).length(), 1.0));
+        }
+        assert!(a.nlerp(&b, 0.5).approx_eq(&a.slerp(&b, 0.5), 1e-5));
+        // 270 degrees one way is 90 the other way
+        let far = Quaternion::from_angle_vect(270.0, y);
+        let mid = a.slerp(&far, 0.5);
+        assert!(mid.rotation_eq(&Quaternion::from_angle_vect(-45.0, y), 1e-5));
+        // nearly the same rotation doesn't divide by zero
+        let close = Quaternion::from_angle_vect(0.001, y);
+        assert!(approx_eq(a.slerp(&close, 0.5).length(), 1.0));
+    }
+
+    #[test]
+    fn quat_euler() {
+        let (yaw, pitch, roll) = (0.3, 0.5, -0.7);
+        let q = Quaternion::from_euler(yaw, pitch, roll);
+        assert!(vec_eq(q.to_euler(), vector!(yaw, pitch, roll)));
+        // yaw alone turns forward (+z) towards +x
+        let q = Quaternion::from_euler(PI/2.0, 0.0, 0.0);
+        assert!(vec_eq(vector!(0.0, 0.0, 1.0).rotate(q), vector!(1.0, 0.0, 0.0)));
+        // yaw first, then pitch around the turned x axis
+        let q = Quaternion::from_euler(PI/2.0, PI/4.0, 0.0);
+        let expected = Quaternion::from_angle_vect(90.0, vector!(0.0, 1.0, 0.0))
+            * Quaternion::from_angle_vect(45.0, vector!(1.0, 0.0, 0.0));
+        assert!(q.approx_eq(&expected, 1e-6));
+        for q in samples() {
+            let e = q.to_euler();
+            assert!(Quaternion::from_euler(e.0[0], e.0[1], e.0[2]).rotation_eq(&q, 1e-5));
+        }
+    }
+
+    #[test]
+    fn quat_matrix_round_trip() {
+        for q in samples() {
+            assert!(Quaternion::from_matrix(&q.to_matrix3x3()).rotation_eq(&q, 1e-5));
+        }
+        assert_eq!(Quaternion::from_matrix(&Matrix3x3::IDENTITY), Quaternion::IDENTITY);
+    }
+
+    #[test]
+    fn quat_axis_angle() {
+        let axis = vector!(1.0, 2.0, 2.0).normalize();
+        let q = Quaternion::from_axis_angle(axis, 1.2);
+        let (a, angle) = q.to_axis_angle();
+        assert!(vec_eq(a, axis));
+        assert!(approx_eq_eps(angle, 1.2, 1e-5));
+        assert_eq!(Quaternion::IDENTITY.to_axis_angle().1, 0.0);
+    }
+
+    #[test]
+    fn quat_look_rotation() {
+        let up = vector!(0.0, 1.0, 0.0);
+        for forward in [vector!(1.0, 0.0, 0.0), vector!(0.0, 0.0, -1.0), vector!(1.0, 2.0, -3.0)] {
+            let q = Quaternion::look_rotation(forward, up);
+            assert!(vec_eq(vector!(0.0, 0.0, 1.0).rotate(q), forward.normalize()));
+            // right stays level
+            assert!(approx_eq_eps(vector!(1.0, 0.0, 0.0).rotate(q).0[1], 0.0, 1e-5));
+            assert!(vector!(0.0, 1.0, 0.0).rotate(q).0[1] > 0.0);
+        }
+        assert!(Quaternion::look_rotation(vector!(0.0, 0.0, 1.0), up).approx_eq(&Quaternion::IDENTITY, 1e-6));
+        // straight up still looks up
+        let q = Quaternion::look_rotation(up, up);
+        assert!(vec_eq(vector!(0.0, 0.0, 1.0).rotate(q), up));
+    }
+
+    #[test]
+    fn quat_from_to_rotation() {
+        let pairs = [
+            (vector!(1.0, 0.0, 0.0), vector!(0.0, 1.0, 0.0)),
+            (vector!(1.0, 2.0, 3.0), vector!(-2.0, 0.5, 1.0)),
+            (vector!(0.0, 0.0, 2.0), vector!(0.0, 0.0, 5.0)),
+            (vector!(1.0, 0.0, 0.0), vector!(-1.0, 0.0, 0.0)),
+            (vector!(0.0, 1.0, 1.0), vector!(0.0, -1.0, -1.0)),
+        ];
+        for (from, to) in pairs {
+            let q = Quaternion::from_to_rotation(from, to);
+            assert!(approx_eq(q.length(), 1.0));
+            assert!(vec_eq(from.normalize().rotate(q), to.normalize()));
+        }
+        // the shortest way, not some roundabout one
+        let q = Quaternion::from_to_rotation(vector!(1.0, 0.0, 0.0), vector!(0.0, 1.0, 0.0));
+        assert!(q.approx_eq(&Quaternion::from_angle_vect(90.0, vector!(0.0, 0.0, 1.0)), 1e-6));
     }
 }
\ No newline at end of file
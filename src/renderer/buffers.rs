@@ -1,40 +1,44 @@
 use crate::Error;
-use crate::renderer::Vertex;
+use crate::renderer::resources::{GlObject, ResourceKind};
 
-pub struct VertexArrayObject(gl::types::GLuint);
+#[derive(Debug)]
+pub struct VertexArrayObject(GlObject);
 impl VertexArrayObject {
     pub fn new() -> Result<Self, Error> {
         let mut vao = 0;
         unsafe { gl::GenVertexArrays(1, &mut vao); }
         if vao!=0 {
-            return Ok(VertexArrayObject(vao))
+            Ok(VertexArrayObject(GlObject::new(ResourceKind::VertexArray, vao)))
         }
         else{ Err(Error::VAOGenError("cannot create vao object")) }
     }
 
     pub fn bind(&self) {
-        unsafe {gl::BindVertexArray(self.0);}
+        unsafe {gl::BindVertexArray(self.0.name());}
     }
     pub fn _unbind(&self) {
         unsafe {gl::BindVertexArray(0);}
     }
+    pub fn set_label(&mut self, label: &str) { self.0.set_label(label) }
 }
 
-pub struct VertexBufferObject(gl::types::GLuint);
+#[derive(Debug)]
+pub struct VertexBufferObject(GlObject);
 impl VertexBufferObject {
     pub fn new() -> Result<Self, Error> {
         let mut vbo = 0;
         unsafe { gl::GenBuffers(1, &mut vbo); }
-        if vbo != 0 {return Ok(Self(vbo))}
-        else {return Err(Error::VBOGenError("cannot create vbo"))}
+        if vbo != 0 {Ok(Self(GlObject::new(ResourceKind::Buffer, vbo)))}
+        else {Err(Error::VBOGenError("cannot create vbo"))}
     }
     pub fn bind(&self) {
-        unsafe {gl::BindBuffer(gl::ARRAY_BUFFER, self.0);}
+        unsafe {gl::BindBuffer(gl::ARRAY_BUFFER, self.0.name());}
     }
     pub fn _unbind(&self) {
         unsafe {gl::BindBuffer(gl::ARRAY_BUFFER, 0);}
     }
-    pub fn buffer(&self, vertices: &[Vertex], usage: gl::types::GLenum) {
+    pub fn set_label(&mut self, label: &str) { self.0.set_label(label) }
+    pub fn buffer<V>(&self, vertices: &[V], usage: gl::types::GLenum) {
         //dbg!(&vertices);
         unsafe{
             gl::BufferData(
@@ -45,23 +49,46 @@ impl VertexBufferObject {
             );
         }
     }
+    // new uninitialized storage. on a buffer the gpu may still be reading this orphans the old
+    // storage instead of waiting for it
+    pub fn allocate(&self, bytes: usize, usage: gl::types::GLenum) {
+        unsafe { gl::BufferData(gl::ARRAY_BUFFER, bytes as isize, std::ptr::null(), usage); }
+    }
+    // immutable storage, needed for persistent mapping
+    pub fn storage(&self, bytes: usize, flags: gl::types::GLbitfield) {
+        unsafe { gl::BufferStorage(gl::ARRAY_BUFFER, bytes as isize, std::ptr::null(), flags); }
+    }
+    // overwrites part of the existing storage, offset in bytes
+    pub fn sub_data<V>(&self, offset: usize, vertices: &[V]) {
+        unsafe { gl::BufferSubData(gl::ARRAY_BUFFER, offset as isize, size_of_val(vertices) as isize, vertices.as_ptr().cast()); }
+    }
+    // null if it failed. has to be unmapped before drawing unless it's a persistent mapping
+    pub fn map_range(&self, offset: usize, bytes: usize, access: gl::types::GLbitfield) -> *mut u8 {
+        unsafe { gl::MapBufferRange(gl::ARRAY_BUFFER, offset as isize, bytes as isize, access).cast() }
+    }
+    pub fn unmap(&self) {
+        unsafe { gl::UnmapBuffer(gl::ARRAY_BUFFER); }
+    }
+    pub fn id(&self) -> u32 { self.0.name() }
+    pub fn is_current(&self) -> bool { self.0.is_current() }
 }
 
-#[derive(PartialEq)]
-pub struct ElementBufferObject(gl::types::GLuint);
+#[derive(Debug)]
+pub struct ElementBufferObject(GlObject);
 impl ElementBufferObject {
     pub fn new() -> Result<Self, Error> {
         let mut ebo = 0;
         unsafe { gl::GenBuffers(1, &mut ebo); }
-        if ebo != 0 {return Ok(Self(ebo))}
-        else {return Err(Error::EBOGenError("cannot create ebo"))}
+        if ebo != 0 {Ok(Self(GlObject::new(ResourceKind::Buffer, ebo)))}
+        else {Err(Error::EBOGenError("cannot create ebo"))}
     }
     pub fn bind(&self) {
-        unsafe { gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.0); }
+        unsafe { gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.0.name()); }
     }
     pub fn _unbind(&self) {
         unsafe {gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);}
     }
+    pub fn set_label(&mut self, label: &str) { self.0.set_label(label) }
     pub fn buffer_elements(&self, indices: Vec<i32>, usage: gl::types::GLenum) {
         //dbg!(&indices);
         unsafe{
@@ -73,4 +100,10 @@ impl ElementBufferObject {
             );
         }
     }
-}
\ No newline at end of file
+    pub fn allocate(&self, bytes: usize, usage: gl::types::GLenum) {
+        unsafe { gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, bytes as isize, std::ptr::null(), usage); }
+    }
+    pub fn sub_data(&self, offset: usize, indices: &[i32]) {
+        unsafe { gl::BufferSubData(gl::ELEMENT_ARRAY_BUFFER, offset as isize, size_of_val(indices) as isize, indices.as_ptr().cast()); }
+    }
+}
@@ -1,9 +1,8 @@
-use crate::{math::{self, Matrix4x4, Quaternion, Vector, Vector3}, vector};
+use crate::{math::{self, Frustum, Matrix4x4, Quaternion, Transform, Vector, Vector3}, vector};
 
 #[derive(Debug)]
 pub struct Camera {
-    pub position: Vector3,
-    pub rotation: Quaternion,
+    pub transform: Transform,
     pub fov: f32,
     aspect_ratio: f32,
     pub near: f32,
@@ -13,8 +12,7 @@ pub struct Camera {
 impl Camera {
     pub fn new(position: Vector3, rotation: Quaternion) -> Self {
         Self{
-            position,
-            rotation,
+            transform: Transform::from_position_rotation(position, rotation),
             fov: 45.0,
             aspect_ratio: 1.0,
             near: 0.1,
@@ -40,8 +38,18 @@ impl Camera {
         self.perspective = math::perspective(fov, self.aspect_ratio, self.near, self.far)
     }
     //pub fn translate(&mut self, offset: Vector3) { self.local_position = self.local_position+offset}
-    pub fn set_world_position(&mut self, pos: Vector3) { self.position=pos; }
-    pub fn set_rotation(&mut self, rot: Quaternion) { self.rotation=rot; }
+    pub fn set_world_position(&mut self, pos: Vector3) { self.transform.position=pos; }
+    pub fn set_rotation(&mut self, rot: Quaternion) { self.transform.rotation=rot; }
+    pub fn set_transform(&mut self, transform: Transform) { self.transform=transform; }
+
+    // what the renderer puts in the view uniform
+    pub fn view(&self) -> Matrix4x4 {
+        self.look_at(vector!(0.0, 0.0, 1.0).rotate(self.transform.rotation))
+    }
+    // everything the camera can see, in world space
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&(self.perspective*self.view()))
+    }
 
     pub fn look_at(&self, target: Vector3) -> Matrix4x4 {
         let forward = target.normalize();
@@ -49,10 +57,50 @@ impl Camera {
         let up = right.cross(forward);
 
         Matrix4x4::from_arrays([
-            [right.0[0],    right.0[1],    right.0[2],    right.dot(&self.position) ],
-            [up.0[0],       up.0[1],       up.0[2],       up.dot(&self.position)     ],
-            [-forward.0[0], -forward.0[1], -forward.0[2], -forward.dot(&self.position)],
+            [right.0[0],    right.0[1],    right.0[2],    right.dot(&self.transform.position) ],
+            [up.0[0],       up.0[1],       up.0[2],       up.dot(&self.transform.position)     ],
+            [-forward.0[0], -forward.0[1], -forward.0[2], -forward.dot(&self.transform.position)],
             [0.0,           0.0,           0.0,           1.0]
         ])
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{Quaternion, Vector, Vector3};
+    use crate::renderer::camera::Camera;
+    use crate::vector;
+
+    // inside the clip volume after perspective * view, what the gpu would keep
+    fn on_screen(camera: &Camera, point: Vector3) -> bool {
+        let clip = camera.perspective*camera.view()*point.extend(1.0);
+        (0..3).all(|i| clip[i].abs() <= clip.w())
+    }
+
+    #[test]
+    fn frustum_matches_clip_space() {
+        let mut camera = Camera::new(vector!(1.0, 2.0, -3.0), Quaternion::from_euler(0.4, -0.2, 0.0));
+        camera.set_perspective(60.0, 16.0/9.0, 0.1, 50.0);
+        let frustum = camera.frustum();
+        let mut visible = 0;
+        for x in -10..=10 {
+            for y in -10..=10 {
+                for z in -10..=10 {
+                    let point = vector!(x as f32*3.1, y as f32*1.7, z as f32*4.3);
+                    assert_eq!(frustum.contains_point(point), on_screen(&camera, point), "{:?}", point);
+                    visible += on_screen(&camera, point) as usize;
+                }
+            }
+        }
+        assert!(visible > 0);
+    }
+
+    #[test]
+    fn frustum_faces_forward() {
+        let camera = Camera::new(vector!(0.0, 0.0, 0.0), Quaternion::IDENTITY);
+        let frustum = camera.frustum();
+        assert!(frustum.contains_point(vector!(0.0, 0.0, 5.0)));
+        assert!(!frustum.contains_point(vector!(0.0, 0.0, -5.0)));
+        assert!(!frustum.contains_point(vector!(50.0, 0.0, 5.0)));
+    }
+}
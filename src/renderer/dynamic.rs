@@ -0,0 +1,383 @@
+use std::{cell::Cell, rc::Rc};
+
+use crate::{
+    Error,
+    math::{Aabb, Matrix4x4, Transform, Vector},
+    renderer::{
+        buffers::{ElementBufferObject, VertexArrayObject, VertexBufferObject},
+        layout::{VertexFormat, VertexLayout},
+        material::Material,
+        textures::Texture,
+    },
+};
+
+// how new vertices get to the gpu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Streaming {
+    // glBufferSubData into the same storage. cheapest for small edits, but the driver may
+    // wait if the last draw still reads the buffer
+    SubData,
+    // fresh storage for every full update, the old one is freed once the gpu is done with it.
+    // range updates still go through glBufferSubData
+    Orphan,
+    // this many copies of the vertices written round robin, with a fence per copy so a write
+    // never waits for the draw that was just issued. persistently mapped when glBufferStorage
+    // is there, otherwise mapped for each write
+    Ring(usize),
+}
+
+// a full update never shrinks, and grows at least twice as big so growing one vertex at a
+// time doesn't reallocate every frame
+fn grown_capacity(current: usize, needed: usize) -> usize {
+    if needed <= current { current } else { needed.max(current*2) }
+}
+
+fn check_format<V: VertexFormat>(layout: &VertexLayout) -> Result<(), Error> {
+    if size_of::<V>() != layout.stride() || V::layout() != *layout {
+        return Err(Error::BufferError(format!(
+            "{} doesn't match the layout the mesh was made with", std::any::type_name::<V>()
+        )))
+    }
+    Ok(())
+}
+
+struct Ring {
+    segments: usize,
+    // the segment the last write went to, and the next draw reads
+    current: usize,
+    // one per segment, set when a draw reads it, null when nothing is pending
+    fences: Vec<Cell<gl::types::GLsync>>,
+    // the whole buffer, when persistently mapped
+    mapped: Option<*mut u8>,
+}
+impl Ring {
+    fn new(segments: usize, mapped: Option<*mut u8>) -> Self {
+        Self { segments, current: 0, fences: (0..segments).map(|_| Cell::new(std::ptr::null())).collect(), mapped }
+    }
+    fn next(&self) -> usize { (self.current+1) % self.segments }
+    // blocks until the gpu is done with the segment, usually it already is
+    fn wait(&self, segment: usize) {
+        let fence = self.fences[segment].replace(std::ptr::null());
+        if fence.is_null() { return }
+        unsafe {
+            while gl::ClientWaitSync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, 1_000_000_000) == gl::TIMEOUT_EXPIRED {}
+            gl::DeleteSync(fence);
+        }
+    }
+    fn fence(&self) {
+        let fence = unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) };
+        let old = self.fences[self.current].replace(fence);
+        if !old.is_null() { unsafe { gl::DeleteSync(old) } }
+    }
+    fn clear(&self) {
+        for fence in &self.fences {
+            let fence = fence.replace(std::ptr::null());
+            if !fence.is_null() { unsafe { gl::DeleteSync(fence) } }
+        }
+    }
+}
+
+// a mesh whose vertices (and indices) change after creation: animated vertices, geometry
+// built every frame, debug lines. it keeps room for `capacity` vertices and grows when an
+// update needs more
+pub struct DynamicMesh {
+    layout: VertexLayout,
+    primitive: gl::types::GLenum,
+    streaming: Streaming,
+    // vertices that fit without growing, per ring segment
+    capacity: usize,
+    vertex_count: usize,
+    index_capacity: usize,
+    index_count: usize,
+    ring: Option<Ring>,
+
+    textures: Vec<Rc<Texture>>,
+    // None uses the renderer's default material
+    material: Option<Rc<Material>>,
+    transform: Transform,
+    bounds: Aabb,
+    // kept so a grown buffer gets it too
+    label: Option<String>,
+
+    vao: VertexArrayObject,
+    vbo: VertexBufferObject,
+    // None draws the vertices in order
+    ebo: Option<ElementBufferObject>,
+}
+impl DynamicMesh {
+    // empty until the first update. primitive is gl::TRIANGLES, gl::LINES and so on
+    pub fn new<V: VertexFormat>(primitive: gl::types::GLenum, capacity: usize, streaming: Streaming) -> Result<Self, Error> {
+        let layout = V::layout();
+        let capacity = capacity.max(1);
+        let vao = VertexArrayObject::new()?;
+        vao.bind();
+        let (vbo, ring) = Self::create_buffer(&layout, capacity, streaming)?;
+        Ok(Self {
+            layout,
+            primitive,
+            streaming,
+            capacity,
+            vertex_count: 0,
+            index_capacity: 0,
+            index_count: 0,
+            ring,
+            textures: Vec::new(),
+            material: None,
+            transform: Transform::IDENTITY,
+            bounds: Aabb::new(Vector::new([0.0; 3]), Vector::new([0.0; 3])),
+            label: None,
+            vao, vbo,
+            ebo: None,
+        })
+    }
+    pub fn with_textures(mut self, textures: Vec<Rc<Texture>>) -> Self { self.textures = textures; self }
+
+    // expects the vao to be bound, leaves the new buffer bound and attached to it
+    fn create_buffer(layout: &VertexLayout, capacity: usize, streaming: Streaming) -> Result<(VertexBufferObject, Option<Ring>), Error> {
+        let vbo = VertexBufferObject::new()?;
+        vbo.bind();
+        let ring = match streaming {
+            Streaming::Ring(segments) => {
+                let segments = segments.max(1);
+                let bytes = capacity*layout.stride()*segments;
+                let mut mapped = None;
+                if gl::BufferStorage::is_loaded() {
+                    let flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+                    vbo.storage(bytes, flags);
+                    mapped = Some(vbo.map_range(0, bytes, flags)).filter(|p| !p.is_null());
+                } else {
+                    vbo.allocate(bytes, gl::STREAM_DRAW);
+                }
+                Some(Ring::new(segments, mapped))
+            }
+            Streaming::Orphan => { vbo.allocate(capacity*layout.stride(), gl::STREAM_DRAW); None }
+            Streaming::SubData => { vbo.allocate(capacity*layout.stride(), gl::DYNAMIC_DRAW); None }
+        };
+        layout.apply();
+        Ok((vbo, ring))
+    }
+    fn usage(&self) -> gl::types::GLenum {
+        if self.streaming == Streaming::SubData { gl::DYNAMIC_DRAW } else { gl::STREAM_DRAW }
+    }
+    // the buffer is sized and copied by the layout's stride, a different vertex would read
+    // or write past it
+    fn check_format<V: VertexFormat>(&self) -> Result<(), Error> {
+        check_format::<V>(&self.layout)
+    }
+    fn segment_offset(&self, segment: usize) -> usize { segment*self.capacity*self.layout.stride() }
+
+    // replaces all vertices
+    pub fn update<V: VertexFormat>(&mut self, vertices: &[V]) -> Result<(), Error> {
+        self.check_format::<V>()?;
+        if vertices.len() > self.capacity { self.grow(grown_capacity(self.capacity, vertices.len()))?; }
+        self.vbo.bind();
+        match self.streaming {
+            Streaming::SubData => self.vbo.sub_data(0, vertices),
+            Streaming::Orphan => {
+                self.vbo.allocate(self.capacity*self.layout.stride(), self.usage());
+                self.vbo.sub_data(0, vertices);
+            }
+            Streaming::Ring(_) => {
+                let next = self.ring().next();
+                self.ring().wait(next);
+                self.write(self.segment_offset(next), vertices);
+                self.ring_mut().current = next;
+            }
+        }
+        self.vertex_count = vertices.len();
+        self.bounds = Aabb::from_points(V::positions(vertices)).unwrap_or(Aabb::new(Vector::new([0.0; 3]), Vector::new([0.0; 3])));
+        Ok(())
+    }
+    // overwrites the vertices from first on and keeps the rest, can append but not leave a gap
+    pub fn update_range<V: VertexFormat>(&mut self, first: usize, vertices: &[V]) -> Result<(), Error> {
+        self.check_format::<V>()?;
+        if first > self.vertex_count {
+            return Err(Error::BufferError(format!("range starts at {} but the mesh only has {} vertices", first, self.vertex_count)))
+        }
+        let end = first+vertices.len();
+        if end > self.capacity { self.grow(grown_capacity(self.capacity, end))?; }
+        let stride = self.layout.stride();
+        self.vbo.bind();
+        if let Some(ring) = &self.ring {
+            // the next segment gets the untouched vertices copied over on the gpu, so the
+            // write below doesn't have to wait for the copy. with one segment they're already there
+            let next = ring.next();
+            let (from, to) = (self.segment_offset(ring.current), self.segment_offset(next));
+            ring.wait(next);
+            let copy = |start: usize, end: usize| if end > start && from != to { unsafe {
+                gl::CopyBufferSubData(gl::ARRAY_BUFFER, gl::ARRAY_BUFFER, (from+start*stride) as isize, (to+start*stride) as isize, ((end-start)*stride) as isize);
+            } };
+            copy(0, first);
+            copy(end, self.vertex_count);
+            self.write(to+first*stride, vertices);
+            self.ring_mut().current = next;
+        } else {
+            self.vbo.sub_data(first*stride, vertices);
+        }
+        let range = Aabb::from_points(V::positions(vertices));
+        self.bounds = match range {
+            Some(range) if first == 0 && end >= self.vertex_count => range,
+            Some(range) => self.bounds.merge(&range),
+            None => self.bounds,
+        };
+        self.vertex_count = self.vertex_count.max(end);
+        Ok(())
+    }
+    // into the ring buffer, which is bound
+    fn write<V>(&self, offset: usize, vertices: &[V]) {
+        let bytes = size_of_val(vertices);
+        if bytes == 0 { return }
+        let source = vertices.as_ptr().cast::<u8>();
+        if let Some(base) = self.ring().mapped {
+            unsafe { std::ptr::copy_nonoverlapping(source, base.add(offset), bytes) }
+            return
+        }
+        // unsynchronized is fine, the fence already said the gpu is done with this part
+        let target = self.vbo.map_range(offset, bytes, gl::MAP_WRITE_BIT | gl::MAP_UNSYNCHRONIZED_BIT | gl::MAP_INVALIDATE_RANGE_BIT);
+        if target.is_null() {
+            self.vbo.sub_data(offset, vertices);
+        } else {
+            unsafe { std::ptr::copy_nonoverlapping(source, target, bytes) }
+            self.vbo.unmap();
+        }
+    }
+    fn ring(&self) -> &Ring { self.ring.as_ref().unwrap() }
+    fn ring_mut(&mut self) -> &mut Ring { self.ring.as_mut().unwrap() }
+
+    // moves the current vertices into a bigger buffer, the old one is deleted once the gpu is done
+    fn grow(&mut self, capacity: usize) -> Result<(), Error> {
+        self.vao.bind();
+        let (vbo, ring) = Self::create_buffer(&self.layout, capacity, self.streaming)?;
+        let from = self.ring.as_ref().map_or(0, |ring| self.segment_offset(ring.current));
+        if self.vertex_count > 0 { unsafe {
+            gl::BindBuffer(gl::COPY_READ_BUFFER, self.vbo.id());
+            gl::CopyBufferSubData(gl::COPY_READ_BUFFER, gl::ARRAY_BUFFER, from as isize, 0, (self.vertex_count*self.layout.stride()) as isize);
+        } }
+        if let Some(old) = &self.ring { old.clear() }
+        self.vbo = vbo;
+        self.ring = ring;
+        self.capacity = capacity;
+        if let Some(label) = &self.label { self.vbo.set_label(&format!("{} vertices", label)) }
+        Ok(())
+    }
+
+    // replaces the indices, drawing goes through them from now on
+    pub fn set_indices(&mut self, indices: &[i32]) -> Result<(), Error> {
+        // the element buffer binding is part of the vao
+        self.vao.bind();
+        if self.ebo.is_none() {
+            let mut ebo = ElementBufferObject::new()?;
+            ebo.bind();
+            if let Some(label) = &self.label { ebo.set_label(&format!("{} indices", label)) }
+            self.ebo = Some(ebo);
+            self.index_capacity = 0;
+        }
+        let ebo = self.ebo.as_ref().unwrap();
+        ebo.bind();
+        if indices.len() > self.index_capacity || self.streaming != Streaming::SubData {
+            self.index_capacity = grown_capacity(self.index_capacity, indices.len());
+            ebo.allocate(self.index_capacity*size_of::<i32>(), self.usage());
+        }
+        ebo.sub_data(0, indices);
+        self.index_count = indices.len();
+        Ok(())
+    }
+    // back to drawing the vertices in order
+    pub fn clear_indices(&mut self) {
+        self.ebo = None;
+        self.index_count = 0;
+        self.index_capacity = 0;
+    }
+
+    // like StaticMesh::draw
+    pub fn draw(&self, material: &Material, parent: Matrix4x4) {
+        if self.vertex_count == 0 { return }
+        for (i, text) in self.textures.iter().enumerate() { text.bind(i as u32); }
+        material.apply(self.textures.len() as u32, parent*self.transform.to_matrix());
+        self.vao.bind();
+        // the ring's current segment starts this many vertices in
+        let base = self.ring.as_ref().map_or(0, |ring| ring.current*self.capacity) as i32;
+        unsafe {
+            match &self.ebo {
+                Some(_) => gl::DrawElementsBaseVertex(self.primitive, self.index_count as i32, gl::UNSIGNED_INT, std::ptr::null(), base),
+                None => gl::DrawArrays(self.primitive, base, self.vertex_count as i32),
+            }
+        }
+        if let Some(ring) = &self.ring { ring.fence() }
+    }
+
+    // what the last update left on the gpu, for tests and debugging. waits for the gpu
+    pub fn read_back<V: VertexFormat>(&self) -> Result<Vec<V>, Error> {
+        self.check_format::<V>()?;
+        let mut vertices = Vec::<V>::with_capacity(self.vertex_count);
+        let offset = self.ring.as_ref().map_or(0, |ring| self.segment_offset(ring.current));
+        self.vbo.bind();
+        unsafe {
+            gl::GetBufferSubData(gl::ARRAY_BUFFER, offset as isize, (self.vertex_count*self.layout.stride()) as isize, vertices.as_mut_ptr().cast());
+            vertices.set_len(self.vertex_count);
+        }
+        Ok(vertices)
+    }
+
+    pub fn vertex_count(&self) -> usize { self.vertex_count }
+    pub fn index_count(&self) -> usize { self.index_count }
+    pub fn capacity(&self) -> usize { self.capacity }
+    pub fn streaming(&self) -> Streaming { self.streaming }
+    // only for Streaming::Ring, false when every write maps the buffer again
+    pub fn persistently_mapped(&self) -> bool { self.ring.as_ref().is_some_and(|ring| ring.mapped.is_some()) }
+    pub fn layout(&self) -> &VertexLayout { &self.layout }
+    pub fn textures(&self) -> &[Rc<Texture>] { &self.textures }
+    pub fn material(&self) -> Option<&Rc<Material>> { self.material.as_ref() }
+    // like StaticMesh::set_material
+    pub fn set_material(&mut self, material: Option<Rc<Material>>) -> Result<(), Error> {
+        if let Some(material) = &material { material.check_layout(&self.layout)? }
+        self.material = material;
+        Ok(())
+    }
+    pub fn transform(&self) -> &Transform { &self.transform }
+    pub fn set_transform(&mut self, transform: Transform) { self.transform = transform; }
+    // around the vertices, before the transform. range updates only ever grow it
+    pub fn bounds(&self) -> Aabb { self.bounds }
+    pub fn world_bounds(&self, parent: Matrix4x4) -> Aabb {
+        self.bounds.transform(&(parent*self.transform.to_matrix()))
+    }
+    pub fn set_label(&mut self, label: &str) {
+        self.vao.set_label(&format!("{} vertex array", label));
+        self.vbo.set_label(&format!("{} vertices", label));
+        if let Some(ebo) = &mut self.ebo { ebo.set_label(&format!("{} indices", label)) }
+        self.label = Some(label.to_string());
+    }
+}
+impl Drop for DynamicMesh {
+    fn drop(&mut self) {
+        // deleting the buffer unmaps it, the fences have to go by hand
+        if let Some(ring) = &self.ring && self.vbo.is_current() { ring.clear() }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        renderer::{Vertex, dynamic::{check_format, grown_capacity}, layout::VertexFormat},
+        vertex_layout,
+    };
+
+    #[repr(C)]
+    struct Point { position: [f32; 3] }
+    vertex_layout!(Point { position: Position });
+
+    #[test]
+    fn capacity_doubles() {
+        assert_eq!(grown_capacity(16, 10), 16);
+        assert_eq!(grown_capacity(16, 17), 32);
+        assert_eq!(grown_capacity(16, 100), 100);
+        assert_eq!(grown_capacity(0, 3), 3);
+    }
+
+    #[test]
+    fn vertices_must_match_the_layout() {
+        assert!(check_format::<Vertex>(&Vertex::layout()).is_ok());
+        assert!(check_format::<Point>(&Vertex::layout()).is_err());
+        assert!(check_format::<Vertex>(&Point::layout()).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use image::RgbaImage;
+
+use crate::Error;
+use crate::renderer::resources::{GlObject, ResourceKind};
+
+// offscreen render target: rgba8 color texture + 24 bit depth / 8 bit stencil renderbuffer
+#[derive(Debug)]
+pub struct Framebuffer {
+    fbo: GlObject,
+    color: GlObject,
+    depth: GlObject,
+    width: i32,
+    height: i32,
+}
+impl Framebuffer {
+    pub fn new(width: i32, height: i32) -> Result<Self, Error> {
+        let (mut fbo, mut color, mut depth) = (0, 0, 0);
+        // owned right away, so an incomplete framebuffer doesn't leak its attachments
+        let objects = unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::GenTextures(1, &mut color);
+            gl::GenRenderbuffers(1, &mut depth);
+            (
+                GlObject::new(ResourceKind::Framebuffer, fbo),
+                GlObject::new(ResourceKind::Texture, color),
+                GlObject::new(ResourceKind::Renderbuffer, depth),
+            )
+        };
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+            gl::BindTexture(gl::TEXTURE_2D, color);
+            gl::TexImage2D(
+                gl::TEXTURE_2D, 0, gl::RGBA8 as i32, width, height, 0,
+                gl::RGBA, gl::UNSIGNED_BYTE, std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, color, 0);
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, width, height);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::RENDERBUFFER, depth);
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(Error::FramebufferError(format!("framebuffer incomplete: 0x{:x}", status)))
+            }
+        }
+        let (mut fbo, mut color, mut depth) = objects;
+        fbo.set_label("offscreen framebuffer");
+        color.set_label("offscreen color");
+        depth.set_label("offscreen depth");
+        Ok(Self { fbo, color, depth, width, height })
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo.name());
+            gl::Viewport(0, 0, self.width, self.height);
+        }
+    }
+    // back to the windows framebuffer
+    pub fn unbind(&self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0); }
+    }
+    pub fn size(&self) -> (i32, i32) { (self.width, self.height) }
+    pub fn read_pixels(&self) -> Result<RgbaImage, Error> {
+        unsafe { gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo.name()); }
+        read_pixels(self.width, self.height)
+    }
+    pub fn color_texture(&self) -> u32 { self.color.name() }
+    pub fn depth_renderbuffer(&self) -> u32 { self.depth.name() }
+}
+
+// reads the currently bound read framebuffer
+pub fn read_pixels(width: i32, height: i32) -> Result<RgbaImage, Error> {
+    if width <= 0 || height <= 0 {
+        return Err(Error::ScreenshotError(format!("can't read a {}x{} framebuffer", width, height)))
+    }
+    let mut data = vec![0_u8; width as usize * height as usize * 4];
+    unsafe {
+        // rows are tightly packed, rgba8 is always 4 byte aligned anyway
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(0, 0, width, height, gl::RGBA, gl::UNSIGNED_BYTE, data.as_mut_ptr().cast());
+    }
+    image_from_gl_rows(width as u32, height as u32, data)
+}
+
+// gl gives the bottom row first, images start at the top
+fn image_from_gl_rows(width: u32, height: u32, data: Vec<u8>) -> Result<RgbaImage, Error> {
+    let mut img = RgbaImage::from_raw(width, height, data)
+        .ok_or(Error::ScreenshotError("pixel buffer has the wrong size".to_string()))?;
+    image::imageops::flip_vertical_in_place(&mut img);
+    Ok(img)
+}
+
+pub fn save_png(img: &RgbaImage, path: impl AsRef<std::path::Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| Error::ScreenshotError(format!("couldn't create {}: {}", dir.display(), e)))?;
+    }
+    img.save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| Error::ScreenshotError(format!("couldn't save {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::renderer::framebuffer::image_from_gl_rows;
+
+    #[test]
+    fn rows_are_flipped() {
+        // 1x2, bottom row red, top row blue as gl returns it
+        let data = vec![
+            255, 0, 0, 255,
+            0, 0, 255, 255,
+        ];
+        let img = image_from_gl_rows(1, 2, data).unwrap();
+        assert_eq!(img.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(img.get_pixel(0, 1).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn wrong_buffer_size() {
+        assert!(image_from_gl_rows(2, 2, vec![0; 4]).is_err());
+    }
+}
@@ -0,0 +1,386 @@
+use crate::{Error, math::{Vector, Vector3}};
+
+// describes how a vertex struct sits in a buffer, so meshes aren't tied to one Vertex.
+// attributes get consecutive locations in the order they're added, matching
+// `layout (location = n)` in the shader
+//
+//     #[repr(C)]
+//     struct Skinned { position: [f32; 3], uv: [f32; 2], joints: [u8; 4], weights: [u8; 4] }
+//     vertex_layout!(Skinned {
+//         position: Position,
+//         uv: TexCoord(0),
+//         joints: Joints,
+//         weights: Weights => normalized,
+//     });
+#[macro_export]
+macro_rules! vertex_layout {
+    ($vertex:ty { $($field:ident : $semantic:ident $(($arg:expr))? $(=> $flag:ident)?),* $(,)? }) => {
+        // the offsets and types come from the struct itself, so the layout always matches it
+        unsafe impl $crate::renderer::layout::VertexFormat for $vertex {
+            fn layout() -> $crate::renderer::layout::VertexLayout {
+                $crate::renderer::layout::VertexLayout::new(::std::mem::size_of::<$vertex>())
+                    $(.attribute($crate::renderer::layout::VertexAttribute::of_field(
+                        $crate::renderer::layout::Semantic::$semantic $(($arg))?,
+                        ::std::mem::offset_of!($vertex, $field),
+                        |v: &$vertex| &v.$field,
+                    )$(.$flag())?))*
+            }
+        }
+    };
+}
+
+// what an attribute is for, the shader only sees the location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Semantic {
+    Position,
+    Normal,
+    Tangent,
+    Color,
+    TexCoord(u8),
+    // bone indices and how much each of them pulls
+    Joints,
+    Weights,
+    Custom(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentType { F32, I8, U8, I16, U16, I32, U32 }
+impl ComponentType {
+    pub fn gl_enum(self) -> gl::types::GLenum {
+        match self {
+            Self::F32 => gl::FLOAT,
+            Self::I8 => gl::BYTE,
+            Self::U8 => gl::UNSIGNED_BYTE,
+            Self::I16 => gl::SHORT,
+            Self::U16 => gl::UNSIGNED_SHORT,
+            Self::I32 => gl::INT,
+            Self::U32 => gl::UNSIGNED_INT,
+        }
+    }
+    pub fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::F32 | Self::I32 | Self::U32 => 4,
+        }
+    }
+    pub fn is_integer(self) -> bool { self != Self::F32 }
+}
+
+// rust types that can be a vertex attribute
+pub trait Scalar { const TYPE: ComponentType; }
+impl Scalar for f32 { const TYPE: ComponentType = ComponentType::F32; }
+impl Scalar for i8 { const TYPE: ComponentType = ComponentType::I8; }
+impl Scalar for u8 { const TYPE: ComponentType = ComponentType::U8; }
+impl Scalar for i16 { const TYPE: ComponentType = ComponentType::I16; }
+impl Scalar for u16 { const TYPE: ComponentType = ComponentType::U16; }
+impl Scalar for i32 { const TYPE: ComponentType = ComponentType::I32; }
+impl Scalar for u32 { const TYPE: ComponentType = ComponentType::U32; }
+
+pub trait AttributeType {
+    const TYPE: ComponentType;
+    const COUNT: i32;
+}
+impl<T: Scalar> AttributeType for T {
+    const TYPE: ComponentType = T::TYPE;
+    const COUNT: i32 = 1;
+}
+impl<T: Scalar, const N: usize> AttributeType for [T; N] {
+    const TYPE: ComponentType = T::TYPE;
+    const COUNT: i32 = N as i32;
+}
+impl<const N: usize> AttributeType for Vector<N> {
+    const TYPE: ComponentType = ComponentType::F32;
+    const COUNT: i32 = N as i32;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VertexAttribute {
+    pub semantic: Semantic,
+    // set by the layout when it's added
+    pub location: u32,
+    pub component: ComponentType,
+    // 1 to 4
+    pub count: i32,
+    // integers mapped to 0..1 (or -1..1) floats. integers that aren't normalized reach
+    // the shader as ints, for ivec/uvec inputs
+    pub normalized: bool,
+    // bytes from the start of the vertex
+    pub offset: usize,
+    // 0 advances every vertex, n every n instances
+    pub divisor: u32,
+}
+impl VertexAttribute {
+    pub fn new(semantic: Semantic, component: ComponentType, count: i32, offset: usize) -> Self {
+        assert!((1..=4).contains(&count), "vertex attributes have 1 to 4 components, got {}", count);
+        Self { semantic, location: 0, component, count, normalized: false, offset, divisor: 0 }
+    }
+    // the closure only tells the compiler the field's type, it's never called
+    pub fn of_field<V, F: AttributeType>(semantic: Semantic, offset: usize, _field: fn(&V) -> &F) -> Self {
+        Self::new(semantic, F::TYPE, F::COUNT, offset)
+    }
+    pub fn normalized(self) -> Self { Self { normalized: true, ..self } }
+    pub fn divisor(self, divisor: u32) -> Self { Self { divisor, ..self } }
+    // goes through glVertexAttribIPointer and needs an int input in the shader
+    pub fn is_integer(&self) -> bool { self.component.is_integer() && !self.normalized }
+    pub fn size(&self) -> usize { self.component.size()*self.count as usize }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VertexLayout {
+    stride: usize,
+    next_location: u32,
+    attributes: Vec<VertexAttribute>,
+}
+impl VertexLayout {
+    pub fn new(stride: usize) -> Self {
+        Self { stride, next_location: 0, attributes: Vec::new() }
+    }
+    // for a second buffer whose attributes come after the first one's
+    pub fn starting_at(self, location: u32) -> Self {
+        let shift = location as i64 - self.attributes.first().map_or(self.next_location, |a| a.location) as i64;
+        let moved = |l: u32| (l as i64 + shift) as u32;
+        Self {
+            next_location: moved(self.next_location),
+            attributes: self.attributes.into_iter().map(|a| VertexAttribute { location: moved(a.location), ..a }).collect(),
+            ..self
+        }
+    }
+    // every attribute advances once per `divisor` instances instead of every vertex
+    pub fn per_instance(self, divisor: u32) -> Self {
+        Self { attributes: self.attributes.into_iter().map(|a| a.divisor(divisor)).collect(), ..self }
+    }
+    pub fn attribute(mut self, mut attribute: VertexAttribute) -> Self {
+        assert!(attribute.offset+attribute.size() <= self.stride,
+            "{:?} at offset {} doesn't fit in a {} byte vertex", attribute.semantic, attribute.offset, self.stride);
+        attribute.location = self.next_location;
+        self.next_location += 1;
+        self.attributes.push(attribute);
+        self
+    }
+
+    pub fn stride(&self) -> usize { self.stride }
+    pub fn attributes(&self) -> &[VertexAttribute] { &self.attributes }
+    pub fn find(&self, semantic: Semantic) -> Option<&VertexAttribute> {
+        self.attributes.iter().find(|a| a.semantic == semantic)
+    }
+    pub fn at_location(&self, location: u32) -> Option<&VertexAttribute> {
+        self.attributes.iter().find(|a| a.location == location)
+    }
+
+    // points the bound vao at the bound vbo
+    pub fn apply(&self) {
+        let stride = self.stride as i32;
+        for a in &self.attributes {
+            unsafe {
+                if a.is_integer() {
+                    gl::VertexAttribIPointer(a.location, a.count, a.component.gl_enum(), stride, a.offset as *const _);
+                } else {
+                    let normalized = if a.normalized { gl::TRUE } else { gl::FALSE };
+                    gl::VertexAttribPointer(a.location, a.count, a.component.gl_enum(), normalized, stride, a.offset as *const _);
+                }
+                gl::VertexAttribDivisor(a.location, a.divisor);
+                gl::EnableVertexAttribArray(a.location);
+            }
+        }
+    }
+
+    // every input the shader reads has to come from an attribute of the same kind (float or int)
+    // with no more components than the input has. attributes the shader doesn't use are fine
+    pub fn check(&self, inputs: &[ActiveAttribute]) -> Result<(), Error> {
+        for input in inputs {
+            let Some(attribute) = self.at_location(input.location) else {
+                return Err(Error::LayoutError(format!("shader input {} (location {}) isn't in the vertex layout", input.name, input.location)))
+            };
+            if attribute.is_integer() != input.integer {
+                let kind = |int| if int { "integers" } else { "floats" };
+                return Err(Error::LayoutError(format!(
+                    "shader input {} (location {}) reads {} but {:?} gives it {}",
+                    input.name, input.location, kind(input.integer), attribute.semantic, kind(attribute.is_integer())
+                )))
+            }
+            if attribute.count > input.count {
+                return Err(Error::LayoutError(format!(
+                    "shader input {} (location {}) has {} components but {:?} has {}",
+                    input.name, input.location, input.count, attribute.semantic, attribute.count
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Vertex structs that know their own layout, see `vertex_layout!`.
+///
+/// # Safety
+/// `positions` reads the vertices through the layout, so every attribute has to lie
+/// inside the struct over an initialized field of that type. The macro guarantees it.
+pub unsafe trait VertexFormat: Sized {
+    fn layout() -> VertexLayout;
+
+    // the Position attribute of every vertex, empty when there is none or it isn't f32.
+    // a 2 component position gets z = 0
+    fn positions(vertices: &[Self]) -> Vec<Vector3> {
+        let layout = Self::layout();
+        let Some(position) = layout.find(Semantic::Position).filter(|a| a.component == ComponentType::F32)
+            else { return Vec::new() };
+        let count = position.count.min(3) as usize;
+        vertices.iter().map(|vertex| {
+            let start = (vertex as *const Self).cast::<u8>().wrapping_add(position.offset).cast::<f32>();
+            let mut p = [0.0; 3];
+            for (i, c) in p.iter_mut().enumerate().take(count) {
+                // inside the vertex and initialized, that's what implementing the trait promises
+                *c = unsafe { start.add(i).read_unaligned() };
+            }
+            Vector::new(p)
+        }).collect()
+    }
+}
+
+// an input of a linked shader program, from glGetActiveAttrib.
+// matrices take one location per column and show up as one input each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveAttribute {
+    pub name: String,
+    pub location: u32,
+    pub count: i32,
+    // int or uint in the shader
+    pub integer: bool,
+}
+impl ActiveAttribute {
+    // None for types a vertex attribute can't have
+    pub fn from_gl(name: &str, location: u32, ty: gl::types::GLenum) -> Option<Vec<Self>> {
+        let (count, columns, integer) = match ty {
+            gl::FLOAT => (1, 1, false),
+            gl::FLOAT_VEC2 => (2, 1, false),
+            gl::FLOAT_VEC3 => (3, 1, false),
+            gl::FLOAT_VEC4 => (4, 1, false),
+            gl::INT | gl::UNSIGNED_INT => (1, 1, true),
+            gl::INT_VEC2 | gl::UNSIGNED_INT_VEC2 => (2, 1, true),
+            gl::INT_VEC3 | gl::UNSIGNED_INT_VEC3 => (3, 1, true),
+            gl::INT_VEC4 | gl::UNSIGNED_INT_VEC4 => (4, 1, true),
+            gl::FLOAT_MAT2 => (2, 2, false),
+            gl::FLOAT_MAT3 => (3, 3, false),
+            gl::FLOAT_MAT4 => (4, 4, false),
+            _ => return None,
+        };
+        if columns == 1 {
+            return Some(vec![Self { name: name.to_string(), location, count, integer }])
+        }
+        Some((0..columns).map(|c| Self { name: format!("{}[{}]", name, c), location: location+c, count, integer }).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Vector,
+        renderer::{Vertex, layout::{ActiveAttribute, ComponentType, Semantic, VertexFormat, VertexLayout}},
+        vector,
+    };
+
+    #[repr(C)]
+    struct Skinned {
+        position: [f32; 3],
+        normal: Vector<3>,
+        joints: [u8; 4],
+        weights: [u16; 4],
+    }
+    vertex_layout!(Skinned {
+        position: Position,
+        normal: Normal,
+        joints: Joints,
+        weights: Weights => normalized,
+    });
+
+    fn input(name: &str, location: u32, count: i32, integer: bool) -> ActiveAttribute {
+        ActiveAttribute { name: name.to_string(), location, count, integer }
+    }
+
+    #[test]
+    fn vertex_layout() {
+        let layout = Vertex::layout();
+        assert_eq!(layout.stride(), 36);
+        let attributes: Vec<_> = layout.attributes().iter()
+            .map(|a| (a.semantic, a.location, a.count, a.offset, a.component))
+            .collect();
+        assert_eq!(attributes, vec![
+            (Semantic::Position, 0, 3, 0, ComponentType::F32),
+            (Semantic::Color, 1, 4, 12, ComponentType::F32),
+            (Semantic::TexCoord(0), 2, 2, 28, ComponentType::F32),
+        ]);
+        assert!(layout.attributes().iter().all(|a| !a.is_integer() && a.divisor == 0));
+    }
+
+    #[test]
+    fn integer_and_normalized_attributes() {
+        let layout = Skinned::layout();
+        assert_eq!(layout.stride(), size_of::<Skinned>());
+        let joints = layout.find(Semantic::Joints).unwrap();
+        assert_eq!((joints.location, joints.component, joints.count), (2, ComponentType::U8, 4));
+        assert!(joints.is_integer());
+        let weights = layout.find(Semantic::Weights).unwrap();
+        assert!(weights.normalized && !weights.is_integer());
+        // fields left out of the macro don't get an attribute
+        assert_eq!(layout.find(Semantic::TexCoord(0)), None);
+
+        let vertex = Skinned { position: [1.0, 0.0, 0.0], normal: vector!(0.0, 1.0, 0.0), joints: [0; 4], weights: [0; 4] };
+        assert_eq!(Skinned::positions(&[vertex]), vec![vector!(1.0, 0.0, 0.0)]);
+    }
+
+    #[test]
+    fn instance_layouts() {
+        let layout = Skinned::layout().per_instance(1).starting_at(3);
+        let locations: Vec<_> = layout.attributes().iter().map(|a| (a.location, a.divisor)).collect();
+        assert_eq!(locations, vec![(3, 1), (4, 1), (5, 1), (6, 1)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn attributes_must_fit_the_stride() {
+        let _ = VertexLayout::new(8).attribute(crate::renderer::layout::VertexAttribute::new(Semantic::Position, ComponentType::F32, 3, 0));
+    }
+
+    #[test]
+    fn checked_against_shader_inputs() {
+        let layout = Vertex::layout();
+        let shader = [input("aPos", 0, 3, false), input("aColor", 1, 4, false), input("aTexCoord", 2, 2, false)];
+        assert!(layout.check(&shader).is_ok());
+        // unused attributes are fine
+        assert!(layout.check(&shader[..1]).is_ok());
+        // more components than the input is a mismatch, fewer get filled in by gl
+        assert!(layout.check(&[input("aColor", 1, 3, false)]).is_err());
+        assert!(layout.check(&[input("aPos", 0, 4, false)]).is_ok());
+        // nothing at that location
+        assert!(layout.check(&[input("aNormal", 3, 3, false)]).is_err());
+        // ivec input from float data
+        assert!(layout.check(&[input("aPos", 0, 3, true)]).is_err());
+
+        let skinned = Skinned::layout();
+        assert!(skinned.check(&[input("aJoints", 2, 4, true), input("aWeights", 3, 4, false)]).is_ok());
+        assert!(skinned.check(&[input("aJoints", 2, 4, false)]).is_err());
+    }
+
+    #[test]
+    fn matrix_inputs_take_a_location_per_column() {
+        let inputs = ActiveAttribute::from_gl("aModel", 4, gl::FLOAT_MAT4).unwrap();
+        assert_eq!(inputs.iter().map(|i| i.location).collect::<Vec<_>>(), vec![4, 5, 6, 7]);
+        assert_eq!(inputs[1].name, "aModel[1]");
+        assert_eq!(ActiveAttribute::from_gl("aJoints", 0, gl::UNSIGNED_INT_VEC4), Some(vec![input("aJoints", 0, 4, true)]));
+        assert_eq!(ActiveAttribute::from_gl("aSampler", 0, gl::SAMPLER_2D), None);
+    }
+
+    #[test]
+    fn positions_are_read_through_the_layout() {
+        let vertices = [
+            Vertex::new([1.0, 2.0, 3.0], [0.0; 4], [0.0; 2]),
+            Vertex::new([-1.0, 0.5, 0.0], [1.0; 4], [1.0; 2]),
+        ];
+        assert_eq!(Vertex::positions(&vertices), vec![vector!(1.0, 2.0, 3.0), vector!(-1.0, 0.5, 0.0)]);
+
+        #[repr(C)]
+        struct Flat { uv: [f32; 2], position: [f32; 2] }
+        vertex_layout!(Flat { uv: TexCoord(0), position: Position });
+        assert_eq!(Flat::positions(&[Flat { uv: [9.0; 2], position: [4.0, 5.0] }]), vec![vector!(4.0, 5.0, 0.0)]);
+    }
+}
@@ -0,0 +1,185 @@
+use std::rc::Rc;
+
+use crate::{
+    Error,
+    math::{Matrix3x3, Matrix4x4, Vector2, Vector3, Vector4},
+    renderer::{
+        layout::VertexLayout,
+        shaders::ShaderProgram,
+        textures::Texture,
+        uniforms::{ActiveUniform, Uniform, UniformValue},
+    },
+};
+
+// a value for one uniform of a material
+#[derive(Debug, Clone)]
+pub enum MaterialParam {
+    Float(f32),
+    // ints, bools, and sampler units set by hand
+    Int(i32),
+    Vec2(Vector2),
+    Vec3(Vector3),
+    // colors too
+    Vec4(Vector4),
+    Mat3(Matrix3x3),
+    Mat4(Matrix4x4),
+    // bound to a texture unit of its own for every draw, the sampler is pointed at it
+    Texture(Rc<Texture>),
+}
+impl MaterialParam {
+    // whether a uniform of this gl type can take it
+    fn fits(&self, uniform: &ActiveUniform) -> bool {
+        match self {
+            Self::Float(x) => x.fits(uniform),
+            Self::Int(x) => x.fits(uniform),
+            Self::Vec2(v) => v.fits(uniform),
+            Self::Vec3(v) => v.fits(uniform),
+            Self::Vec4(v) => v.fits(uniform),
+            Self::Mat3(m) => m.fits(uniform),
+            Self::Mat4(m) => m.fits(uniform),
+            Self::Texture(_) => uniform.ty == gl::SAMPLER_2D,
+        }
+    }
+}
+impl From<f32> for MaterialParam { fn from(x: f32) -> Self { Self::Float(x) } }
+impl From<i32> for MaterialParam { fn from(x: i32) -> Self { Self::Int(x) } }
+impl From<Vector2> for MaterialParam { fn from(v: Vector2) -> Self { Self::Vec2(v) } }
+impl From<Vector3> for MaterialParam { fn from(v: Vector3) -> Self { Self::Vec3(v) } }
+impl From<Vector4> for MaterialParam { fn from(v: Vector4) -> Self { Self::Vec4(v) } }
+impl From<Matrix3x3> for MaterialParam { fn from(m: Matrix3x3) -> Self { Self::Mat3(m) } }
+impl From<Matrix4x4> for MaterialParam { fn from(m: Matrix4x4) -> Self { Self::Mat4(m) } }
+impl From<Rc<Texture>> for MaterialParam { fn from(t: Rc<Texture>) -> Self { Self::Texture(t) } }
+
+// the uniform a param goes to, or why it can't go anywhere. unlike ShaderProgram::set a
+// name the program doesn't use is an error, a material is set up once and should match
+fn check_param(found: Option<(Uniform, &ActiveUniform)>, name: &str, value: &MaterialParam) -> Result<Uniform, Error> {
+    let Some((uniform, active)) = found else {
+        return Err(Error::MaterialError(format!("the shader has no uniform {} (or doesn't use it)", name)))
+    };
+    if active.location < 0 {
+        return Err(Error::MaterialError(format!("{} is in a uniform block, it's set through the block's buffer", name)))
+    }
+    if !value.fits(active) {
+        return Err(Error::MaterialError(format!("uniform {} has gl type {:#x}, it can't take {:?}", name, active.ty, value)))
+    }
+    Ok(uniform)
+}
+
+#[derive(Debug)]
+struct Param {
+    name: String,
+    value: MaterialParam,
+    uniform: Uniform,
+}
+
+// a shader program with values for its uniforms. the renderer fills in model, view and
+// perspective if the shader has them, everything else is a param
+#[derive(Debug)]
+pub struct Material {
+    program: Rc<ShaderProgram>,
+    params: Vec<Param>,
+    model: Option<Uniform>,
+    view: Option<Uniform>,
+    perspective: Option<Uniform>,
+}
+impl Material {
+    pub fn new(program: Rc<ShaderProgram>) -> Self {
+        let engine = |name: &str| program.uniform(name).filter(|u| u.ty == gl::FLOAT_MAT4).map(|u| u.uniform());
+        Self {
+            model: engine("model"),
+            view: engine("view"),
+            perspective: engine("perspective"),
+            program,
+            params: Vec::new(),
+        }
+    }
+    pub fn with(mut self, name: &str, value: impl Into<MaterialParam>) -> Result<Self, Error> {
+        self.set(name, value)?;
+        Ok(self)
+    }
+    // an error if the program has no such uniform or it has a different type
+    pub fn set(&mut self, name: &str, value: impl Into<MaterialParam>) -> Result<(), Error> {
+        let value = value.into();
+        let uniform = check_param(self.program.find(name), name, &value)?;
+        match self.params.iter_mut().find(|p| p.name == name) {
+            Some(param) => param.value = value,
+            None => self.params.push(Param { name: name.to_string(), value, uniform }),
+        }
+        Ok(())
+    }
+    pub fn get(&self, name: &str) -> Option<&MaterialParam> {
+        self.params.iter().find(|p| p.name == name).map(|p| &p.value)
+    }
+    pub fn program(&self) -> &Rc<ShaderProgram> { &self.program }
+    // whether meshes with this layout can be drawn with it
+    pub fn check_layout(&self, layout: &VertexLayout) -> Result<(), Error> {
+        layout.check(self.program.attributes())
+    }
+
+    // uses the program and sets the camera, once per frame for every program that's drawn with
+    pub fn bind(&self, view: Matrix4x4, perspective: Matrix4x4) {
+        self.program.use_program();
+        if let Some(uniform) = self.view { uniform.setmat4(view) }
+        if let Some(uniform) = self.perspective { uniform.setmat4(perspective) }
+    }
+    // sets the params for one draw, the program has to be in use. textures go to the units
+    // from first_unit on, the ones below are the mesh's own
+    pub fn apply(&self, first_unit: u32, model: Matrix4x4) {
+        if let Some(uniform) = self.model { uniform.setmat4(model) }
+        let mut unit = first_unit;
+        for param in &self.params {
+            let uniform = &param.uniform;
+            match &param.value {
+                MaterialParam::Float(x) => x.set(*uniform),
+                MaterialParam::Int(x) => x.set(*uniform),
+                MaterialParam::Vec2(v) => v.set(*uniform),
+                MaterialParam::Vec3(v) => v.set(*uniform),
+                MaterialParam::Vec4(v) => v.set(*uniform),
+                MaterialParam::Mat3(m) => m.set(*uniform),
+                MaterialParam::Mat4(m) => m.set(*uniform),
+                MaterialParam::Texture(texture) => {
+                    texture.bind(unit);
+                    uniform.seti1(unit as i32);
+                    unit += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        Error,
+        math::{Matrix4x4, Vector},
+        renderer::{material::{MaterialParam, check_param}, uniforms::{ActiveUniform, Uniform}},
+        vector,
+    };
+
+    fn check(name: &str, value: MaterialParam) -> Result<Uniform, Error> {
+        let uniforms = [
+            ActiveUniform::new("model", gl::FLOAT_MAT4, 1, 0),
+            ActiveUniform::new("tint", gl::FLOAT_VEC4, 1, 1),
+            ActiveUniform::new("shininess", gl::FLOAT, 1, 2),
+            ActiveUniform::new("albedo", gl::SAMPLER_2D, 1, 3),
+            ActiveUniform::new("exposure", gl::FLOAT, 1, -1),
+        ];
+        let found = uniforms.iter().find(|u| u.name == name).map(|u| (u.uniform(), u));
+        check_param(found, name, &value)
+    }
+
+    #[test]
+    fn params_match_uniform_types() {
+        assert_eq!(check("tint", vector!(1.0, 0.5, 0.0, 1.0).into()).unwrap().location(), 1);
+        assert!(check("shininess", 32.0.into()).is_ok());
+        assert!(check("model", Matrix4x4::IDENTITY.into()).is_ok());
+        // a sampler can be given its unit directly
+        assert!(check("albedo", MaterialParam::Int(2)).is_ok());
+
+        assert!(check("tint", vector!(1.0, 0.5, 0.0).into()).is_err());
+        assert!(check("shininess", MaterialParam::Int(32)).is_err());
+        assert!(check("roughness", 0.5.into()).is_err());
+        // lives in a uniform block, there's no location to set
+        assert!(check("exposure", 1.0.into()).is_err());
+    }
+}
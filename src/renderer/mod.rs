@@ -1,21 +1,32 @@
 mod buffers;
-mod shaders;
+pub mod dynamic;
+pub mod layout;
+pub mod framebuffer;
+pub mod material;
+pub mod shaders;
 pub mod textures;
-mod uniforms;
+pub mod uniforms;
 pub mod objects;
 pub mod camera;
+pub mod resources;
 
-use shaders::{Shader, ShaderProgram};
-use uniforms::Uniform;
+use dynamic::DynamicMesh;
+use framebuffer::Framebuffer;
+use image::RgbaImage;
+use layout::{VertexFormat, VertexLayout};
+use material::Material;
+use resources::ContextGuard;
+use shaders::ShaderProgram;
+use std::{collections::HashMap, rc::Rc};
 
 use crate::{
     Error,
-    WINDOW_SIZE_X, WINDOW_SIZE_Y,
-    game::Player,
+    config::Config,
     math::{
-        Color, Matrix, Matrix4x4, Vector, Vector3
+        Aabb, Color, Frustum, Matrix4x4, Vector2, Vector3
     },
-    renderer::objects::{AxesArrows, StaticMesh}, vector
+    renderer::{camera::Camera, objects::{AxesArrows, StaticMesh}},
+    scene::Scene,
 };
 
 #[derive(Debug)]
@@ -29,38 +40,70 @@ impl Vertex {
     pub fn new(position: [f32; 3], color: [f32; 4], tex_coord: [f32; 2]) -> Self {
         Self{position, color, tex_coord}
     }
-    pub fn from_vectors(position: Vector3, color: Color, tex_coord: Vector<2>) -> Self {
+    pub fn from_vectors(position: Vector3, color: Color, tex_coord: Vector2) -> Self {
         Self::new(position.as_array(), color.as_array(), tex_coord.as_array())
     }
 }
+crate::vertex_layout!(Vertex {
+    position: Position,
+    color: Color,
+    tex_coord: TexCoord(0),
+});
+
+// what the last render did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub drawn: usize,
+    // outside the camera's frustum, never sent to the gpu
+    pub culled: usize,
+}
+impl FrameStats {
+    pub fn meshes(&self) -> usize { self.drawn + self.culled }
+}
 
 pub struct Renderer {
-    shader_program: Vec<ShaderProgram>,
-    uniforms: Vec<Uniform>,
+    // for meshes without a material of their own
+    material: Rc<Material>,
+    // the program in use this frame, so switching to it again doesn't set the camera again
+    bound_program: Option<u32>,
+    view: Matrix4x4,
+    perspective: Matrix4x4,
     wireframe: bool,
+    background: Color,
+    // None renders to the window
+    target: Option<Framebuffer>,
+    window_size: (i32, i32),
+
+    // the camera's, set by render. None draws everything
+    frustum: Option<Frustum>,
+    culling: bool,
+    stats: FrameStats,
 
-    axes_arrows: AxesArrows
+    // whether the default material can draw a layout, checked the first time a mesh with it is drawn.
+    // meshes with a material of their own were checked by set_material
+    default_layouts: HashMap<VertexLayout, bool>,
+    // layouts that didn't fit, until take_layout_error reports them
+    layout_errors: Vec<Error>,
+
+    axes_arrows: AxesArrows,
+    // last, so the renderer's own resources are deleted while the context still counts as alive
+    _context: ContextGuard,
 }
 impl Renderer {
-    pub fn init(window: &mut glfw::Window) -> Self {
+    pub fn init(window: &mut glfw::Window, config: &Config) -> Result<Self, Error> {
         gl::load_with(|s| window.get_proc_address(s).unwrap() as *const _);
+        let context = ContextGuard::begin();
 
-        Self::set_viewport(WINDOW_SIZE_X.try_into().unwrap(), WINDOW_SIZE_Y.try_into().unwrap());
+        let (width, height) = window.get_framebuffer_size();
+        Self::set_viewport(width, height);
         
-        let vert_shader1 = Shader::from_file("src/shaders/shader.vert", gl::VERTEX_SHADER).unwrap();
-        let frag_shader1 = Shader::from_file("src/shaders/shader.frag", gl::FRAGMENT_SHADER).unwrap();
-        let shader_program= vec![
-            ShaderProgram::create(vert_shader1, frag_shader1).unwrap()
-        ];
-
-        let uniforms = vec![
-            Uniform::from_name("texture1\0", &shader_program[0]).unwrap(),
-            Uniform::from_name("texture2\0", &shader_program[0]).unwrap(),
-            Uniform::from_name("model\0", &shader_program[0]).unwrap(),
-            Uniform::from_name("view\0", &shader_program[0]).unwrap(),
-            Uniform::from_name("perspective\0", &shader_program[0]).unwrap(),
-            Uniform::from_name("transform\0", &shader_program[0]).unwrap(),
-        ];
+        let program = ShaderProgram::from_files(config.asset("shaders/shader.vert"), config.asset("shaders/shader.frag"))?;
+        // mixes the mesh's first two textures
+        let material = Material::new(Rc::new(program))
+            .with("texture1", 0)?
+            .with("texture2", 1)?;
+        // the built in meshes all use Vertex
+        material.check_layout(&Vertex::layout())?;
 
         // let textures = HashMap::from([
         //     ("container", Texture::from_file("src/textures/container.jpg").unwrap()),
@@ -68,40 +111,112 @@ impl Renderer {
         // ]);
         unsafe {
             gl::Enable(gl::DEPTH_TEST);
+            // the window was created with config.msaa_samples samples
+            if config.msaa_samples > 0 { gl::Enable(gl::MULTISAMPLE); }
         }
         Self::set_texture_params();
 
-        return Self{
-            shader_program,
-            uniforms,
+        Ok(Self{
+            material: Rc::new(material),
+            bound_program: None,
+            view: Matrix4x4::IDENTITY,
+            perspective: Matrix4x4::IDENTITY,
             wireframe: true,
-            axes_arrows: AxesArrows::new()
-        };
+            background: Color::new(config.background_color),
+            target: None,
+            window_size: (width, height),
+            frustum: None,
+            culling: true,
+            stats: FrameStats::default(),
+            default_layouts: HashMap::new(),
+            layout_errors: Vec::new(),
+            axes_arrows: AxesArrows::new(),
+            _context: context,
+        })
     }
     
-    pub fn render(&mut self, meshes: &Vec<StaticMesh>, player: &mut Player) -> Result<(), Error> {
-        
-        self.clear_color(crate::BACKGROUND_COLOR.as_array());
-        self.clear();
-        
-        self.shader_program[0].use_program();
-        
-        self.uniforms[0].seti1(0);
-        self.uniforms[1].seti1(1);
+    // meshes the default material can't draw are skipped, see take_layout_error
+    pub fn render(&mut self, scene: &Scene, camera: &Camera) -> Result<(), Error> {
+        if let Some(target) = &self.target { target.bind(); }
 
-        let view = player.camera.look_at(vector!(0.0, 0.0, 1.0).rotate(player.camera.rotation));
+        self.clear_color(self.background.as_array());
+        self.clear();
         
-        self.uniforms[3].setmat4(view);
-        self.uniforms[4].setmat4(player.camera.perspective);
+        self.view = camera.view();
+        self.perspective = camera.perspective;
+        self.bound_program = None;
 
-        for mesh in meshes {
-            mesh.draw(&self.uniforms[5], &self.uniforms[2]);
+        self.stats = FrameStats::default();
+        self.frustum = self.culling.then(|| camera.frustum());
+        for (mesh, world) in scene.meshes() {
+            self.draw_mesh(mesh, world);
         }
 
-        self.axes_arrows.draw(&self.uniforms[2]);
+        let material = self.material.clone();
+        self.use_material(&material);
+        self.axes_arrows.draw(&material);
 
-        return Ok(())
+        Ok(())
+    }
+
+    // expects render to have been called this frame, so the camera is set up.
+    // false when the mesh is outside the camera's view or its layout doesn't fit the material, and got skipped
+    pub fn draw_mesh(&mut self, mesh: &StaticMesh, world: Matrix4x4) -> bool {
+        if !self.fits(mesh.material(), mesh.layout()) || !self.visible(mesh.world_bounds(world)) { return false }
+        let material = mesh.material().unwrap_or(&self.material).clone();
+        self.use_material(&material);
+        mesh.draw(&material, world);
+        true
+    }
+    // same as draw_mesh, culled by the bounds of its last update
+    pub fn draw_dynamic(&mut self, mesh: &DynamicMesh, world: Matrix4x4) -> bool {
+        if !self.fits(mesh.material(), mesh.layout()) || !self.visible(mesh.world_bounds(world)) { return false }
+        let material = mesh.material().unwrap_or(&self.material).clone();
+        self.use_material(&material);
+        mesh.draw(&material, world);
+        true
     }
+    fn use_material(&mut self, material: &Material) {
+        let program = material.program().id();
+        if self.bound_program != Some(program) {
+            material.bind(self.view, self.perspective);
+            self.bound_program = Some(program);
+        }
+    }
+    fn fits(&mut self, material: Option<&Rc<Material>>, layout: &VertexLayout) -> bool {
+        if material.is_some() { return true }
+        if let Some(&fits) = self.default_layouts.get(layout) { return fits }
+        let result = self.material.check_layout(layout);
+        self.default_layouts.insert(layout.clone(), result.is_ok());
+        match result {
+            Ok(()) => true,
+            Err(e) => { self.layout_errors.push(e); false },
+        }
+    }
+    // counts it as drawn or culled
+    fn visible(&mut self, bounds: Aabb) -> bool {
+        if let Some(frustum) = &self.frustum
+            && !frustum.intersects_aabb(&bounds) {
+            self.stats.culled += 1;
+            return false
+        }
+        self.stats.drawn += 1;
+        true
+    }
+    // whether meshes with this layout can be drawn with the default material
+    pub fn check_layout(&self, layout: &VertexLayout) -> Result<(), Error> {
+        self.material.check_layout(layout)
+    }
+    // why a mesh was skipped, once for every layout the default material can't draw, oldest first
+    pub fn take_layout_error(&mut self) -> Option<Error> {
+        (!self.layout_errors.is_empty()).then(|| self.layout_errors.remove(0))
+    }
+    // its program is a good start for materials that want the standard vertex inputs
+    pub fn default_material(&self) -> &Rc<Material> { &self.material }
+    pub fn stats(&self) -> FrameStats { self.stats }
+    pub fn culling(&self) -> bool { self.culling }
+    // off draws everything, for checking that culling isn't what makes something disappear
+    pub fn set_culling(&mut self, culling: bool) { self.culling = culling; }
 
     fn clear_color(&self, bg_color: [f32; 4]) {
         unsafe { gl::ClearColor(bg_color[0], bg_color[1], bg_color[2], bg_color[3]); }
@@ -119,9 +234,34 @@ impl Renderer {
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST.try_into().unwrap());
 
     } }
-    pub fn resize(&self, x: i32, y: i32) { 
+    pub fn resize(&mut self, x: i32, y: i32) { 
         println!("resize: {:?}", (x, y));
-        Self::set_viewport(x, y)
+        self.window_size = (x, y);
+        // an offscreen target keeps its own size
+        if self.target.is_none() { Self::set_viewport(x, y) }
+    }
+
+    // draw into an offscreen framebuffer instead of the window, None goes back to the window
+    pub fn set_render_target(&mut self, target: Option<Framebuffer>) {
+        if let Some(old) = &self.target { old.unbind(); }
+        self.target = target;
+    }
+    pub fn render_target(&self) -> Option<&Framebuffer> { self.target.as_ref() }
+    pub fn set_background(&mut self, color: Color) { self.background = color; }
+
+    // whatever was rendered last, from the render target or the windows back buffer
+    // so for the window it has to be called before swap_buffers
+    pub fn screenshot(&self) -> Result<RgbaImage, Error> {
+        match &self.target {
+            Some(target) => target.read_pixels(),
+            None => {
+                unsafe {
+                    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+                    gl::ReadBuffer(gl::BACK);
+                }
+                framebuffer::read_pixels(self.window_size.0, self.window_size.1)
+            }
+        }
     }
     pub fn switch_wireframe(&mut self) {
         if self.wireframe { unsafe { gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE); }; self.wireframe=false }
@@ -1,50 +1,49 @@
-use std::mem::offset_of;
+use std::rc::Rc;
 
 use crate::{
-    math::{Color, Matrix4x4, Vector, Vector3, vectors::Quaternion},
+    Error,
+    math::{Aabb, Color, Matrix4x4, Transform, Vector, Vector3, vectors::Quaternion},
     renderer::{
         Vertex,
         buffers::{ElementBufferObject, VertexArrayObject, VertexBufferObject},
-        textures::Texture, uniforms::Uniform
+        layout::{VertexFormat, VertexLayout},
+        material::Material,
+        textures::Texture,
     },
     vector
 };
 
 pub struct StaticMesh {
-    textures: Vec<Texture>,
+    // shared with every other mesh that uses the same image
+    textures: Vec<Rc<Texture>>,
+    // None uses the renderer's default material
+    material: Option<Rc<Material>>,
     indices_count: i32,
 
-    world_position: Vector3,
-    orientation: Quaternion,
+    transform: Transform,
+    // around the vertices, before the transform
+    bounds: Aabb,
+    layout: VertexLayout,
 
     vao: VertexArrayObject,
     vbo: VertexBufferObject,
     ebo: ElementBufferObject,
 }
 impl StaticMesh {
-    pub fn new(vertices: Vec<Vertex>, indices: Vec<i32>, world_position: Vector3, orientation: Quaternion, textures: Vec<Texture>, usage: gl::types::GLuint) -> Self {
+    // any vertex struct with a layout, see vertex_layout!
+    pub fn new<V: VertexFormat>(vertices: Vec<V>, indices: Vec<i32>, transform: Transform, textures: Vec<Rc<Texture>>, usage: gl::types::GLuint) -> Self {
+        let bounds = Aabb::from_points(V::positions(&vertices))
+            .unwrap_or(Aabb::new(Vector::new([0.0; 3]), Vector::new([0.0; 3])));
+        let layout = V::layout();
         let vao = VertexArrayObject::new().unwrap();
         let vbo = VertexBufferObject::new().unwrap();
         
         vao.bind();
         vbo.bind();
         vbo.buffer(&vertices, usage);
+        layout.apply();
 
-        //implement this as a function
-        unsafe {
-            let stride = size_of::<Vertex>() as i32;
-            //vertices
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, offset_of!(Vertex, position) as *const _);
-            gl::EnableVertexAttribArray(0);
-            //color
-            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, stride, offset_of!(Vertex, color) as *const _);
-            gl::EnableVertexAttribArray(1);
-            //texture
-            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, stride, offset_of!(Vertex, tex_coord) as *const _);
-            gl::EnableVertexAttribArray(2);
-        }
-
-        let indices_count = indices.len().clone() as i32;
+        let indices_count = indices.len() as i32;
 
         let ebo = ElementBufferObject::new().unwrap();
         ebo.bind();
@@ -52,38 +51,63 @@ impl StaticMesh {
 
         Self {
             textures,
-            world_position,
-            orientation,
+            material: None,
+            transform,
+            bounds,
+            layout,
             indices_count,
             vao, vbo, ebo
         }
     }
 
-    pub fn draw(&self, transform_uniform: &Uniform, model_uniform: &Uniform) {
+    // parent is the world matrix of whatever owns the mesh, identity if it's free standing.
+    // the material has to be bound already, the mesh's textures take the first units
+    pub fn draw(&self, material: &Material, parent: Matrix4x4) {
         for (i, text) in self.textures.iter().enumerate() { text.bind(i as u32); }
-        transform_uniform.setf3(self.world_position.0[0], self.world_position.0[1], self.world_position.0[2]);
-        model_uniform.setmat4(self.orientation.to_matrix4x4());
+        material.apply(self.textures.len() as u32, parent*self.transform.to_matrix());
         self.vao.bind();
         self.ebo.bind();
         //dbg!(&self.indices_count);
         // each triangle*3 vertices
         unsafe { gl::DrawElements(gl::TRIANGLES, self.indices_count, gl::UNSIGNED_INT, std::ptr::null()); }
     }
-    pub fn translate(&mut self, pos: Vector3) {
-        self.world_position = pos;
+    pub fn transform(&self) -> &Transform { &self.transform }
+    pub fn set_transform(&mut self, transform: Transform) { self.transform = transform; }
+    pub fn textures(&self) -> &[Rc<Texture>] { &self.textures }
+    pub fn material(&self) -> Option<&Rc<Material>> { self.material.as_ref() }
+    // an error if the material's program wants inputs the mesh's vertices don't have.
+    // None goes back to the renderer's default
+    pub fn set_material(&mut self, material: Option<Rc<Material>>) -> Result<(), Error> {
+        if let Some(material) = &material { material.check_layout(&self.layout)? }
+        self.material = material;
+        Ok(())
+    }
+    // names the buffers in gl debuggers, "<label> vertices" and so on
+    pub fn set_label(&mut self, label: &str) {
+        self.vao.set_label(&format!("{} vertex array", label));
+        self.vbo.set_label(&format!("{} vertices", label));
+        self.ebo.set_label(&format!("{} indices", label));
     }
-    pub fn set_rotation(&mut self, rot: Quaternion) {
-        self.orientation = rot;
+    // in the mesh's own space
+    pub fn bounds(&self) -> Aabb { self.bounds }
+    pub fn layout(&self) -> &VertexLayout { &self.layout }
+    // where draw(_, parent) puts it
+    pub fn world_bounds(&self, parent: Matrix4x4) -> Aabb {
+        self.bounds.transform(&(parent*self.transform.to_matrix()))
     }
+
+    pub fn translate(&mut self, offset: Vector3) { self.transform.translate(offset); }
+    pub fn set_position(&mut self, pos: Vector3) { self.transform.position = pos; }
+    pub fn set_rotation(&mut self, rot: Quaternion) { self.transform.rotation = rot; }
+    pub fn set_scale(&mut self, scale: Vector3) { self.transform.scale = scale; }
 }
 
-pub struct Triangle{
-    world_pos: Vector3,
-    orientation: Quaternion,
+pub struct Triangle {
+    transform: Transform,
     pub mesh: StaticMesh,
 }
 impl Triangle {
-    pub fn new((x, y): (f32, f32), position: Vector3, orientation: Quaternion, color: Color, textures: Vec<Texture>, usage: gl::types::GLuint) -> Self {
+    pub fn new((x, y): (f32, f32), transform: Transform, color: Color, textures: Vec<Rc<Texture>>, usage: gl::types::GLuint) -> Self {
         let vertices = vec![
             Vertex::from_vectors(vector!(-x/2.0, -y/2.0, 0.0), color, vector!(1.0, 1.0)),
             Vertex::from_vectors(vector!(0.0, y/2.0, 0.0), color, vector!(1.0, -1.0)),
@@ -91,31 +115,25 @@ impl Triangle {
         ];
         let indices = vec![0, 1, 2];
 
-        let mesh = StaticMesh::new(vertices, indices, position, orientation, textures, usage);
+        let mesh = StaticMesh::new(vertices, indices, transform, textures, usage);
 
-        Self { 
-            world_pos: position,
-            orientation,
-            mesh
-        }
+        Self { transform, mesh }
     }
     pub fn update_mesh(&mut self) {
-        self.mesh.translate(self.world_pos);
-        self.mesh.set_rotation(self.orientation);
+        self.mesh.set_transform(self.transform);
     }
     pub fn translate(&mut self, offset: Vector3) {
-        self.world_pos = self.world_pos+offset;
+        self.transform.translate(offset);
         self.update_mesh();
     }
 }
 
 pub struct Rectangle {
-    world_pos: Vector3,
-    orientation: Quaternion,
+    transform: Transform,
     pub mesh: StaticMesh,
 }
 impl Rectangle {
-    pub fn new((x, y): (f32, f32), position: Vector3, orientation: Quaternion, color: Color, textures: Vec<Texture>, usage: gl::types::GLuint) -> Self {
+    pub fn new((x, y): (f32, f32), transform: Transform, color: Color, textures: Vec<Rc<Texture>>, usage: gl::types::GLuint) -> Self {
         let vertices = vec![
             Vertex::from_vectors(vector!(x, y, 0.0), color, vector!(1.0, 1.0)),
             Vertex::from_vectors(vector!(-x, y, 0.0), color, vector!(0.0, 1.0)),
@@ -124,36 +142,33 @@ impl Rectangle {
         ];
         let indices = vec![1, 0, 2, 1, 2, 3];
 
-        let mesh = StaticMesh::new(vertices, indices, position, orientation, textures, usage);
+        let mesh = StaticMesh::new(vertices, indices, transform, textures, usage);
 
-        Self { 
-            world_pos: position,
-            orientation,
-            mesh
-        }
+        Self { transform, mesh }
     }
     pub fn update_mesh(&mut self) {
-        self.mesh.translate(self.world_pos);
-        self.mesh.set_rotation(self.orientation);
+        self.mesh.set_transform(self.transform);
     }
     pub fn translate(&mut self, offset: Vector3) {
-        self.world_pos = self.world_pos+offset;
+        self.transform.translate(offset);
+        self.update_mesh();
+    }
+    pub fn rotate(&mut self, rot: Quaternion) {
+        self.transform.rotate(rot);
         self.update_mesh();
     }
-    pub fn rotate(&mut self, _rot: Quaternion) {
-        todo!("broken!!!");
-        // self.orientation = self.orientation*rot;
-        // self.update_mesh();
+    pub fn set_scale(&mut self, scale: Vector3) {
+        self.transform.scale = scale;
+        self.update_mesh();
     }
 }
 
 pub struct Cube {
-    world_pos: Vector3,
-    orientation: Quaternion,
+    transform: Transform,
     pub mesh: StaticMesh,
 }
 impl Cube {
-    pub fn new((x, y, z): (f32, f32, f32), position: Vector3, orientation: Quaternion, color: Color, textures: Vec<Texture>, usage: gl::types::GLuint) -> Self {
+    pub fn new((x, y, z): (f32, f32, f32), transform: Transform, color: Color, textures: Vec<Rc<Texture>>, usage: gl::types::GLuint) -> Self {
         //copy fucking pasted from chatgpt
         let vertices = vec![
             // back face
@@ -214,31 +229,31 @@ impl Cube {
             30, 31, 32, 33, 34, 35,
         ];
 
-        let mesh = StaticMesh::new(vertices, indices, position, orientation, textures, usage);
+        let mesh = StaticMesh::new(vertices, indices, transform, textures, usage);
 
-        Self { 
-            world_pos: position,
-            orientation,
-            mesh
-        }
+        Self { transform, mesh }
     }
     pub fn update_mesh(&mut self) {
-        self.mesh.translate(self.world_pos);
-        self.mesh.set_rotation(self.orientation);
+        self.mesh.set_transform(self.transform);
     }
     pub fn translate(&mut self, offset: Vector3) {
-        self.world_pos = self.world_pos+offset;
+        self.transform.translate(offset);
         self.update_mesh();
     }
-    pub fn rotate(&mut self, _rot: Quaternion) {
-        todo!("broken!!!");
-        // self.orientation = self.orientation*rot;
-        // self.update_mesh();
+    pub fn rotate(&mut self, rot: Quaternion) {
+        self.transform.rotate(rot);
+        self.update_mesh();
+    }
+    pub fn set_scale(&mut self, scale: Vector3) {
+        self.transform.scale = scale;
+        self.update_mesh();
     }
 }
 
 pub struct AxesArrows {
     vao: VertexArrayObject,
+    // deleting it while the vao is bound would detach it
+    _vbo: VertexBufferObject,
 }
 impl AxesArrows {
     pub fn new() -> Self {
@@ -254,32 +269,25 @@ impl AxesArrows {
             Vertex::from_vectors(vector!(0.0, 0.0, 1.0), vector!(0.0, 0.0, 1.0, 1.0), vector!(0.0, 0.0)),
         ];
 
-        let vao = VertexArrayObject::new().unwrap();
-        let vbo = VertexBufferObject::new().unwrap();
+        let mut vao = VertexArrayObject::new().unwrap();
+        let mut vbo = VertexBufferObject::new().unwrap();
         
         vao.bind();
         vbo.bind();
         vbo.buffer(&vertices, gl::STATIC_DRAW);
-
-        //implement this as a function
-        unsafe {
-            let stride = size_of::<Vertex>() as i32;
-            //vertices
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, offset_of!(Vertex, position) as *const _);
-            gl::EnableVertexAttribArray(0);
-            //color
-            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, stride, offset_of!(Vertex, color) as *const _);
-            gl::EnableVertexAttribArray(1);
-            //texture
-            gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, stride, offset_of!(Vertex, tex_coord) as *const _);
-            gl::EnableVertexAttribArray(2);
-        }
-        Self{ vao }
+        vao.set_label("axes arrows vertex array");
+        vbo.set_label("axes arrows vertices");
+        Vertex::layout().apply();
+        Self{ vao, _vbo: vbo }
     }
-    pub fn draw(&self, model_uniform: &Uniform) {
+    pub fn draw(&self, material: &Material) {
         self.vao.bind();
-        model_uniform.setmat4(Matrix4x4::IDENTITY);
+        material.apply(0, Matrix4x4::IDENTITY);
         unsafe { gl::DrawArrays(gl::LINES, 0, 6);}
         self.vao._unbind();
     }
+}
+// needs a current gl context, like new
+impl Default for AxesArrows {
+    fn default() -> Self { Self::new() }
 }
\ No newline at end of file
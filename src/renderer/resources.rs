@@ -0,0 +1,191 @@
+use std::cell::Cell;
+
+// gl names only mean something in the context that made them, and deleting one after that
+// context is gone is an error at best. every wrapper (buffers, textures, shaders, ...) holds a
+// GlObject that remembers the context it was created in and deletes its name on drop, but
+// only while that context is still the live one. the renderer holds the ContextGuard, so
+// whatever outlives it is left for the driver to free along with the context.
+// everything is per thread, like a current gl context
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    Buffer,
+    VertexArray,
+    Texture,
+    Shader,
+    Program,
+    Framebuffer,
+    Renderbuffer,
+}
+impl ResourceKind {
+    // what glObjectLabel calls it
+    fn identifier(self) -> gl::types::GLenum {
+        match self {
+            Self::Buffer => gl::BUFFER,
+            Self::VertexArray => gl::VERTEX_ARRAY,
+            Self::Texture => gl::TEXTURE,
+            Self::Shader => gl::SHADER,
+            Self::Program => gl::PROGRAM,
+            Self::Framebuffer => gl::FRAMEBUFFER,
+            Self::Renderbuffer => gl::RENDERBUFFER,
+        }
+    }
+    unsafe fn delete(self, name: u32) { unsafe {
+        match self {
+            Self::Buffer => gl::DeleteBuffers(1, &name),
+            Self::VertexArray => gl::DeleteVertexArrays(1, &name),
+            Self::Texture => gl::DeleteTextures(1, &name),
+            Self::Shader => gl::DeleteShader(name),
+            Self::Program => gl::DeleteProgram(name),
+            Self::Framebuffer => gl::DeleteFramebuffers(1, &name),
+            Self::Renderbuffer => gl::DeleteRenderbuffers(1, &name),
+        }
+    } }
+}
+
+// how many of each exist right now, for finding leaks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiveResources {
+    pub buffers: usize,
+    pub vertex_arrays: usize,
+    pub textures: usize,
+    pub shaders: usize,
+    pub programs: usize,
+    pub framebuffers: usize,
+    pub renderbuffers: usize,
+}
+impl LiveResources {
+    pub fn total(&self) -> usize {
+        self.buffers + self.vertex_arrays + self.textures + self.shaders
+            + self.programs + self.framebuffers + self.renderbuffers
+    }
+    fn count_mut(&mut self, kind: ResourceKind) -> &mut usize {
+        match kind {
+            ResourceKind::Buffer => &mut self.buffers,
+            ResourceKind::VertexArray => &mut self.vertex_arrays,
+            ResourceKind::Texture => &mut self.textures,
+            ResourceKind::Shader => &mut self.shaders,
+            ResourceKind::Program => &mut self.programs,
+            ResourceKind::Framebuffer => &mut self.framebuffers,
+            ResourceKind::Renderbuffer => &mut self.renderbuffers,
+        }
+    }
+}
+
+thread_local! {
+    static CONTEXT: Cell<Option<u64>> = const { Cell::new(None) };
+    static NEXT_CONTEXT: Cell<u64> = const { Cell::new(0) };
+    static LIVE: Cell<LiveResources> = Cell::new(LiveResources::default());
+}
+
+pub fn live_resources() -> LiveResources { LIVE.with(|live| live.get()) }
+pub fn context_alive() -> bool { CONTEXT.with(|c| c.get()).is_some() }
+
+fn count(kind: ResourceKind, created: bool) {
+    LIVE.with(|live| {
+        let mut counts = live.get();
+        let count = counts.count_mut(kind);
+        *count = if created { *count + 1 } else { count.saturating_sub(1) };
+        live.set(counts);
+    });
+}
+
+// the context is usable from new until drop. a new guard replaces the old one,
+// resources from before can't delete their names anymore
+#[derive(Debug)]
+pub struct ContextGuard {
+    id: u64,
+}
+impl ContextGuard {
+    // the context has to be current with gl loaded
+    pub fn begin() -> Self {
+        let id = NEXT_CONTEXT.with(|next| { let id = next.get(); next.set(id+1); id });
+        CONTEXT.with(|c| c.set(Some(id)));
+        Self { id }
+    }
+}
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|c| if c.get() == Some(self.id) { c.set(None) });
+    }
+}
+
+// owns one gl name and deletes it exactly once, which is why nothing holding one is Clone
+#[derive(Debug)]
+pub struct GlObject {
+    name: u32,
+    kind: ResourceKind,
+    context: Option<u64>,
+    label: Option<String>,
+}
+impl GlObject {
+    // takes over a name that was just generated
+    pub fn new(kind: ResourceKind, name: u32) -> Self {
+        count(kind, true);
+        Self { name, kind, context: CONTEXT.with(|c| c.get()), label: None }
+    }
+    pub fn name(&self) -> u32 { self.name }
+    pub fn kind(&self) -> ResourceKind { self.kind }
+    pub fn label(&self) -> Option<&str> { self.label.as_deref() }
+    // shows up in gl debuggers and debug output. most names only become objects once they are
+    // bound, so label after the first bind
+    pub fn set_label(&mut self, label: &str) {
+        if self.is_current() && gl::ObjectLabel::is_loaded() {
+            unsafe { gl::ObjectLabel(self.kind.identifier(), self.name, label.len() as i32, label.as_ptr().cast()) }
+        }
+        self.label = Some(label.to_string());
+    }
+    // created in the context that is still alive
+    pub fn is_current(&self) -> bool {
+        self.context.is_some() && self.context == CONTEXT.with(|c| c.get())
+    }
+}
+impl Drop for GlObject {
+    fn drop(&mut self) {
+        if self.is_current() { unsafe { self.kind.delete(self.name) } }
+        count(self.kind, false);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::renderer::resources::{ContextGuard, GlObject, ResourceKind, context_alive, live_resources};
+
+    // without a guard nothing touches gl, so these run without a context
+    #[test]
+    fn counts_follow_ownership() {
+        let before = live_resources();
+        let texture = GlObject::new(ResourceKind::Texture, 7);
+        let buffers = [GlObject::new(ResourceKind::Buffer, 1), GlObject::new(ResourceKind::Buffer, 2)];
+        let now = live_resources();
+        assert_eq!(now.textures, before.textures+1);
+        assert_eq!(now.buffers, before.buffers+2);
+        assert_eq!(now.total(), before.total()+3);
+        drop(buffers);
+        assert_eq!(live_resources().buffers, before.buffers);
+        drop(texture);
+        assert_eq!(live_resources(), before);
+    }
+
+    #[test]
+    fn labels_are_kept() {
+        let mut program = GlObject::new(ResourceKind::Program, 3);
+        assert_eq!(program.label(), None);
+        program.set_label("shaders/shader.vert + shaders/shader.frag");
+        assert_eq!(program.label(), Some("shaders/shader.vert + shaders/shader.frag"));
+        assert_eq!((program.name(), program.kind()), (3, ResourceKind::Program));
+    }
+
+    #[test]
+    fn guards_replace_each_other() {
+        assert!(!context_alive());
+        let first = ContextGuard::begin();
+        assert!(context_alive());
+        let second = ContextGuard::begin();
+        // the old one going away doesn't end the new one
+        drop(first);
+        assert!(context_alive());
+        drop(second);
+        assert!(!context_alive());
+    }
+}
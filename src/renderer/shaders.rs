@@ -1,11 +1,21 @@
+use std::{cell::RefCell, collections::{HashMap, HashSet}, ffi::CString};
+
 use crate::Error;
+use crate::renderer::{
+    layout::ActiveAttribute,
+    resources::{GlObject, ResourceKind},
+    uniforms::{ActiveUniform, Uniform, UniformBlock, UniformValue},
+};
 
-pub struct Shader(u32);
+#[derive(Debug)]
+pub struct Shader(GlObject);
 impl Shader {
     fn create(source: &[u8], shader_type: u32) -> Result<Self, Error> {
         unsafe {
             let shader = gl::CreateShader(shader_type);
             if shader == 0 {return Err(Error::ShaderError("couldn't create shader".to_string()))}
+            // deleted again if it doesn't compile
+            let object = GlObject::new(ResourceKind::Shader, shader);
             gl::ShaderSource(
                 shader,
                 1,
@@ -25,49 +35,162 @@ impl Shader {
                 let msg = format!("Shader compile error: {}", String::from_utf8_lossy(&info_buffer));
                 return Err(Error::ShaderError(msg))
             }
-            return Ok(Self(shader));
+            Ok(Self(object))
         }
     }
-    pub fn from_file(path: &'static str, shader_type: u32) -> Result<Self, Error> {
+    pub fn from_file(path: impl AsRef<std::path::Path>, shader_type: u32) -> Result<Self, Error> {
+        let path = path.as_ref();
         let source = match std::fs::read(path) {
             Ok(d) => d,
-            Err(_) => return Err(Error::ShaderError(format!("couldn't find: {}", path).to_string()))
+            Err(_) => return Err(Error::ShaderError(format!("couldn't find: {}", path.display()).to_string()))
         };
-        Self::create(&source, shader_type)
+        let mut shader = Self::create(&source, shader_type)?;
+        shader.0.set_label(&path.display().to_string());
+        Ok(shader)
     }
+    pub fn label(&self) -> Option<&str> { self.0.label() }
 }
+
+#[derive(Debug)]
 pub struct ShaderProgram {
-    pub program: u32,
-    _vertex_shader: Shader,
-    _frag_shader:   Shader,
+    program: GlObject,
+    // read once after linking, none of it changes afterwards
+    uniforms: HashMap<String, ActiveUniform>,
+    attributes: Vec<ActiveAttribute>,
+    blocks: Vec<UniformBlock>,
+    // names set() already warned about, so setting one every frame only warns once
+    warned: RefCell<HashSet<String>>,
 }
 impl ShaderProgram {
+    // the shaders are only needed for linking, they are deleted once it is done
     pub fn create(vertex_shader: Shader, frag_shader: Shader) -> Result<Self, Error> {
         unsafe{
-            let program = gl::CreateProgram();
+            let name = gl::CreateProgram();
+            if name == 0 {return Err(Error::ShaderError("couldn't create shader program".to_string()))}
+            let mut program = GlObject::new(ResourceKind::Program, name);
             
-            gl::AttachShader(program, vertex_shader.0.clone());
-            gl::AttachShader(program, frag_shader.0.clone());
+            gl::AttachShader(name, vertex_shader.0.name());
+            gl::AttachShader(name, frag_shader.0.name());
+
+            gl::LinkProgram(name);
 
-            gl::LinkProgram(program);
+            gl::DetachShader(name, vertex_shader.0.name());
+            gl::DetachShader(name, frag_shader.0.name());
             
             let mut success: i32 = 0;
-            gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+            gl::GetProgramiv(name, gl::LINK_STATUS, &mut success);
             if success == 0 { 
                 let mut info_buffer: Vec<u8> = Vec::with_capacity(1024);
                 let mut log_len = 0_i32;
-                gl::GetShaderInfoLog(program, 1024, &mut log_len, info_buffer.as_mut_ptr().cast());
+                gl::GetProgramInfoLog(name, 1024, &mut log_len, info_buffer.as_mut_ptr().cast());
                 info_buffer.set_len(log_len.try_into().unwrap());
                 let msg = format!("Shader program link error: {}", String::from_utf8_lossy(&info_buffer));
                 return Err(Error::ShaderError(msg))
             }
 
-            gl::DeleteShader(vertex_shader.0.clone());
-            gl::DeleteShader(frag_shader.0.clone());
-            return Ok(ShaderProgram { program, _vertex_shader: vertex_shader, _frag_shader: frag_shader })
+            let label = format!("{} + {}", vertex_shader.label().unwrap_or("?"), frag_shader.label().unwrap_or("?"));
+            program.set_label(&label);
+            Ok(ShaderProgram {
+                uniforms: active_uniforms(name).into_iter().map(|u| (u.name.clone(), u)).collect(),
+                attributes: active_attributes(name),
+                blocks: uniform_blocks(name),
+                warned: RefCell::new(HashSet::new()),
+                program,
+            })
         }
     }
+    pub fn from_files(vertex: impl AsRef<std::path::Path>, fragment: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        Self::create(Shader::from_file(vertex, gl::VERTEX_SHADER)?, Shader::from_file(fragment, gl::FRAGMENT_SHADER)?)
+    }
+    pub fn id(&self) -> u32 { self.program.name() }
+    pub fn label(&self) -> Option<&str> { self.program.label() }
     pub fn use_program(&self) {
-        unsafe { gl::UseProgram(self.program); }
+        unsafe { gl::UseProgram(self.program.name()); }
+    }
+
+    // the vertex inputs the program actually reads, built-ins like gl_VertexID left out
+    pub fn attributes(&self) -> &[ActiveAttribute] { &self.attributes }
+    // the uniforms the program actually uses, the compiler drops the rest. in no particular order
+    pub fn uniforms(&self) -> impl Iterator<Item = &ActiveUniform> { self.uniforms.values() }
+    // arrays by their name, without [0]
+    pub fn uniform(&self, name: &str) -> Option<&ActiveUniform> { self.uniforms.get(name) }
+    pub fn uniform_blocks(&self) -> &[UniformBlock] { &self.blocks }
+    pub fn uniform_block(&self, name: &str) -> Option<&UniformBlock> { self.blocks.iter().find(|b| b.name == name) }
+
+    // where set() writes to. array elements ("lights[2]") are asked from gl, everything else
+    // comes from the cache
+    pub fn find(&self, name: &str) -> Option<(Uniform, &ActiveUniform)> {
+        if let Some(uniform) = self.uniforms.get(name) { return Some((uniform.uniform(), uniform)) }
+        let (base, _) = name.split_once('[')?;
+        let uniform = self.uniforms.get(base)?;
+        let name = CString::new(name).ok()?;
+        let location = unsafe { gl::GetUniformLocation(self.program.name(), name.as_ptr()) };
+        (location >= 0).then(|| (Uniform::from_location(location), uniform))
+    }
+    // the program has to be in use. a name the program doesn't use (or the compiler dropped)
+    // is only a warning, the wrong type for one it does use is an error
+    pub fn set(&self, name: &str, value: impl UniformValue) -> Result<(), Error> {
+        let Some((uniform, active)) = self.find(name) else {
+            if self.warned.borrow_mut().insert(name.to_string()) {
+                eprintln!("warning: {} has no active uniform {}, setting it does nothing", self.label().unwrap_or("shader program"), name);
+            }
+            return Ok(())
+        };
+        if active.location < 0 {
+            return Err(Error::UniformError(format!("{} is in a uniform block, it's set through the block's buffer", name)))
+        }
+        if !value.fits(active) {
+            return Err(Error::UniformError(format!("{} has gl type {:#x}, it can't take a {}", name, active.ty, std::any::type_name_of_val(&value))))
+        }
+        value.set(uniform);
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+// names gl writes into a buffer, NUL terminated and at most 255 bytes
+fn gl_name(buffer: &[u8], len: i32) -> String {
+    String::from_utf8_lossy(&buffer[..len as usize]).into_owned()
+}
+fn active_attributes(program: u32) -> Vec<ActiveAttribute> {
+    let mut count = 0;
+    unsafe { gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count); }
+    let mut attributes = Vec::new();
+    for i in 0..count as u32 {
+        let mut buffer = [0_u8; 256];
+        let (mut len, mut size, mut ty) = (0, 0, 0);
+        let location = unsafe {
+            gl::GetActiveAttrib(program, i, buffer.len() as i32, &mut len, &mut size, &mut ty, buffer.as_mut_ptr().cast());
+            gl::GetAttribLocation(program, buffer.as_ptr().cast())
+        };
+        if location < 0 { continue }
+        attributes.extend(ActiveAttribute::from_gl(&gl_name(&buffer, len), location as u32, ty).into_iter().flatten());
+    }
+    attributes
+}
+fn active_uniforms(program: u32) -> Vec<ActiveUniform> {
+    let mut count = 0;
+    unsafe { gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count); }
+    (0..count as u32).map(|i| {
+        let mut buffer = [0_u8; 256];
+        let (mut len, mut size, mut ty) = (0, 0, 0);
+        let location = unsafe {
+            gl::GetActiveUniform(program, i, buffer.len() as i32, &mut len, &mut size, &mut ty, buffer.as_mut_ptr().cast());
+            gl::GetUniformLocation(program, buffer.as_ptr().cast())
+        };
+        ActiveUniform::new(&gl_name(&buffer, len), ty, size, location)
+    }).collect()
+}
+fn uniform_blocks(program: u32) -> Vec<UniformBlock> {
+    let mut count = 0;
+    unsafe { gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count); }
+    (0..count as u32).map(|index| {
+        let mut buffer = [0_u8; 256];
+        let (mut len, mut size, mut binding) = (0, 0, 0);
+        unsafe {
+            gl::GetActiveUniformBlockName(program, index, buffer.len() as i32, &mut len, buffer.as_mut_ptr().cast());
+            gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_DATA_SIZE, &mut size);
+            gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+        }
+        UniformBlock { name: gl_name(&buffer, len), index, size: size as usize, binding: binding as u32 }
+    }).collect()
+}
@@ -1,17 +1,21 @@
 use std::io::Cursor;
 use image::ImageReader;
 use crate::Error;
+use crate::renderer::resources::{GlObject, ResourceKind};
 
+// not Clone, meshes that use the same image share it through an Rc
+#[derive(Debug)]
 pub struct Texture {
-    texture: u32,
+    texture: GlObject,
     width:  u32,
     height: u32,
 }
 impl Texture {
-    pub fn from_file(path: &'static str) -> Result<Self, Error> {
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
         let source = match std::fs::read(path) {
             Ok(d) => d,
-            Err(_) => return Err(Error::TextureError(format!("couldn't find: {}", path)))
+            Err(_) => return Err(Error::TextureError(format!("couldn't find: {}", path.display())))
         };
         let img = match ImageReader::new(
             Cursor::new(source)
@@ -21,11 +25,15 @@ impl Texture {
         };
 
         let (width, height) = img.dimensions();
-        let mut texture: u32 = 0;
+        let mut name: u32 = 0;
 
         unsafe {
-            gl::GenTextures(1, &mut texture);
-            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::GenTextures(1, &mut name);
+            if name == 0 { return Err(Error::TextureError("couldn't create texture".to_string())) }
+        }
+        let mut texture = GlObject::new(ResourceKind::Texture, name);
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, name);
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
@@ -39,10 +47,14 @@ impl Texture {
             );
             gl::GenerateMipmap(gl::TEXTURE_2D);
         }
-        return Ok(Self { texture, width, height })
+        texture.set_label(&path.display().to_string());
+        Ok(Self { texture, width, height })
     }
     pub fn bind(&self, texture_unit: u32) { unsafe { 
         gl::ActiveTexture(gl::TEXTURE0+texture_unit);
-        gl::BindTexture(gl::TEXTURE_2D, self.texture);
+        gl::BindTexture(gl::TEXTURE_2D, self.texture.name());
     } }
+    pub fn size(&self) -> (u32, u32) { (self.width, self.height) }
+    // the file it was loaded from
+    pub fn label(&self) -> Option<&str> { self.texture.label() }
 }
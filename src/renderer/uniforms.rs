@@ -1,14 +1,17 @@
-use crate::Error;
-use crate::math::Matrix;
+use crate::math::{Matrix, Matrix3x3, Matrix4x4, Vector};
 use crate::renderer::ShaderProgram;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Uniform(i32);
 impl Uniform {
-    pub fn from_name(name: &'static str, program: &ShaderProgram) -> Result<Self, Error> {
-        let uni = unsafe{ gl::GetUniformLocation(program.program, name.as_ptr().cast())};
-        if uni==-1 {return Err(Error::UniformError(name));}
-        return Ok(Self(uni));
+    // -1 is a valid location that gl ignores
+    pub fn from_location(location: i32) -> Self { Self(location) }
+    pub fn location(&self) -> i32 { self.0 }
+    // a uniform the program doesn't use gets location -1, setting it does nothing
+    pub fn from_name(name: &str, program: &ShaderProgram) -> Self {
+        program.find(name).map_or(Self(-1), |(uniform, _)| uniform)
     }
+    pub fn is_active(&self) -> bool { self.0 >= 0 }
     pub fn setf1(&self, x: f32) { unsafe { gl::Uniform1f(self.0, x); } }
     pub fn setf2(&self, x: f32, y: f32) { unsafe { gl::Uniform2f(self.0, x, y); } }
     pub fn setf3(&self, x: f32, y: f32, z: f32) { unsafe { gl::Uniform3f(self.0, x, y, z); } }
@@ -18,6 +21,7 @@ impl Uniform {
     pub fn seti2(&self, x: i32, y: i32) { unsafe { gl::Uniform2i(self.0, x, y); } }
     pub fn seti3(&self, x: i32, y: i32, z: i32) { unsafe { gl::Uniform3i(self.0, x, y, z); } }
     pub fn seti4(&self, x: i32, y: i32, z: i32, w: i32) { unsafe { gl::Uniform4i(self.0, x, y, z, w); } }
+    pub fn setu1(&self, x: u32) { unsafe { gl::Uniform1ui(self.0, x); } }
 
     pub fn setmat3(&self, mat: Matrix<3, 3>) { unsafe { gl::UniformMatrix3fv(self.0, 1, gl::FALSE, mat.column_major().as_ptr().cast()); } }
     pub fn setmat4(&self, mat: Matrix<4, 4>) {
@@ -29,4 +33,110 @@ impl Uniform {
                 mat.column_major().as_ptr().cast());
         } 
     }
-}
\ No newline at end of file
+}
+
+// a uniform of a linked program, from glGetActiveUniform. arrays are named without the [0]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveUniform {
+    pub name: String,
+    pub ty: gl::types::GLenum,
+    // array length, 1 otherwise
+    pub size: i32,
+    // -1 for uniforms in a uniform block
+    pub location: i32,
+}
+impl ActiveUniform {
+    pub fn new(name: &str, ty: gl::types::GLenum, size: i32, location: i32) -> Self {
+        Self { name: name.strip_suffix("[0]").unwrap_or(name).to_string(), ty, size, location }
+    }
+    pub fn is_sampler(&self) -> bool {
+        matches!(self.ty, gl::SAMPLER_1D | gl::SAMPLER_2D | gl::SAMPLER_3D | gl::SAMPLER_CUBE
+            | gl::SAMPLER_2D_ARRAY | gl::SAMPLER_2D_SHADOW | gl::SAMPLER_2D_MULTISAMPLE
+            | gl::INT_SAMPLER_2D | gl::UNSIGNED_INT_SAMPLER_2D)
+    }
+    pub fn uniform(&self) -> Uniform { Uniform(self.location) }
+}
+
+// a uniform block of a linked program, filled from a uniform buffer bound at `binding`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformBlock {
+    pub name: String,
+    pub index: u32,
+    // bytes the buffer needs
+    pub size: usize,
+    pub binding: u32,
+}
+
+// values ShaderProgram::set takes, fits says which uniform types can hold them
+pub trait UniformValue {
+    fn fits(&self, uniform: &ActiveUniform) -> bool;
+    fn set(&self, uniform: Uniform);
+}
+impl UniformValue for f32 {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::FLOAT }
+    fn set(&self, uniform: Uniform) { uniform.setf1(*self) }
+}
+// samplers take the texture unit
+impl UniformValue for i32 {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { matches!(uniform.ty, gl::INT | gl::BOOL) || uniform.is_sampler() }
+    fn set(&self, uniform: Uniform) { uniform.seti1(*self) }
+}
+impl UniformValue for u32 {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::UNSIGNED_INT }
+    fn set(&self, uniform: Uniform) { uniform.setu1(*self) }
+}
+impl UniformValue for bool {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::BOOL }
+    fn set(&self, uniform: Uniform) { uniform.seti1(*self as i32) }
+}
+impl UniformValue for Vector<2> {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::FLOAT_VEC2 }
+    fn set(&self, uniform: Uniform) { uniform.setf2(self.0[0], self.0[1]) }
+}
+impl UniformValue for Vector<3> {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::FLOAT_VEC3 }
+    fn set(&self, uniform: Uniform) { uniform.setf3(self.0[0], self.0[1], self.0[2]) }
+}
+// colors too
+impl UniformValue for Vector<4> {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::FLOAT_VEC4 }
+    fn set(&self, uniform: Uniform) { uniform.setf4(self.0[0], self.0[1], self.0[2], self.0[3]) }
+}
+impl UniformValue for Matrix3x3 {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::FLOAT_MAT3 }
+    fn set(&self, uniform: Uniform) { uniform.setmat3(*self) }
+}
+impl UniformValue for Matrix4x4 {
+    fn fits(&self, uniform: &ActiveUniform) -> bool { uniform.ty == gl::FLOAT_MAT4 }
+    fn set(&self, uniform: Uniform) { uniform.setmat4(*self) }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::{Matrix4x4, Vector},
+        renderer::uniforms::{ActiveUniform, UniformValue},
+        vector,
+    };
+
+    #[test]
+    fn arrays_drop_the_index() {
+        let lights = ActiveUniform::new("lights[0]", gl::FLOAT_VEC3, 4, 7);
+        assert_eq!((lights.name.as_str(), lights.size), ("lights", 4));
+        assert_eq!(ActiveUniform::new("model", gl::FLOAT_MAT4, 1, 0).name, "model");
+    }
+
+    #[test]
+    fn values_fit_their_types() {
+        let uniform = |ty| ActiveUniform::new("u", ty, 1, 0);
+        assert!(1.0.fits(&uniform(gl::FLOAT)));
+        assert!(!1.0.fits(&uniform(gl::INT)));
+        assert!(2.fits(&uniform(gl::SAMPLER_2D)));
+        assert!(true.fits(&uniform(gl::BOOL)));
+        assert!(!2_u32.fits(&uniform(gl::INT)));
+        assert!(vector!(1.0, 0.0, 0.0, 1.0).fits(&uniform(gl::FLOAT_VEC4)));
+        assert!(!vector!(1.0, 0.0, 0.0).fits(&uniform(gl::FLOAT_VEC4)));
+        assert!(Matrix4x4::IDENTITY.fits(&uniform(gl::FLOAT_MAT4)));
+        assert!(!Matrix4x4::IDENTITY.fits(&uniform(gl::FLOAT_MAT3)));
+    }
+}
@@ -0,0 +1,375 @@
+use crate::{
+    Error,
+    math::{Color, Matrix4x4, Transform},
+    renderer::{camera::Camera, objects::StaticMesh},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub color: Color,
+    pub intensity: f32,
+}
+
+// index into the scene plus a generation so handles to removed nodes don't alias new ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeHandle {
+    index: usize,
+    generation: u32,
+}
+
+pub struct Node {
+    pub name: String,
+    local: Transform,
+    world: Transform,
+    world_matrix: Matrix4x4,
+    dirty: bool,
+
+    parent: Option<NodeHandle>,
+    children: Vec<NodeHandle>,
+
+    pub mesh: Option<StaticMesh>,
+    pub camera: Option<Camera>,
+    pub light: Option<Light>,
+}
+impl Node {
+    fn new(name: &str, local: Transform, parent: Option<NodeHandle>) -> Self {
+        Self {
+            name: name.to_string(),
+            local,
+            world: local,
+            world_matrix: local.to_matrix(),
+            dirty: true,
+            parent,
+            children: Vec::new(),
+            mesh: None,
+            camera: None,
+            light: None,
+        }
+    }
+    pub fn transform(&self) -> &Transform { &self.local }
+    // world values are only valid after Scene::update_transforms
+    pub fn world_transform(&self) -> &Transform { &self.world }
+    pub fn world_matrix(&self) -> Matrix4x4 { self.world_matrix }
+    pub fn parent(&self) -> Option<NodeHandle> { self.parent }
+    pub fn children(&self) -> &[NodeHandle] { &self.children }
+
+    // marks the node dirty, its children get updated with it
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.local = transform;
+        self.dirty = true;
+    }
+    pub fn transform_mut(&mut self) -> &mut Transform {
+        self.dirty = true;
+        &mut self.local
+    }
+}
+
+type ParentWorld = (Transform, Matrix4x4);
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+#[derive(Default)]
+pub struct Scene {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    roots: Vec<NodeHandle>,
+    active_camera: Option<NodeHandle>,
+}
+impl Scene {
+    pub fn new() -> Self { Self::default() }
+
+    fn insert(&mut self, node: Node) -> NodeHandle {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.node = Some(node);
+                NodeHandle { index, generation: slot.generation }
+            },
+            None => {
+                self.slots.push(Slot { generation: 0, node: Some(node) });
+                NodeHandle { index: self.slots.len()-1, generation: 0 }
+            }
+        }
+    }
+
+    pub fn add(&mut self, name: &str, transform: Transform) -> NodeHandle {
+        let handle = self.insert(Node::new(name, transform, None));
+        self.roots.push(handle);
+        handle
+    }
+    pub fn add_child(&mut self, parent: NodeHandle, name: &str, transform: Transform) -> Result<NodeHandle, Error> {
+        if !self.contains(parent) { return Err(Error::SceneError("parent node doesn't exist")) }
+        let handle = self.insert(Node::new(name, transform, Some(parent)));
+        self.get_mut(parent).unwrap().children.push(handle);
+        Ok(handle)
+    }
+    // the mesh's buffers get labeled with the node's name
+    pub fn add_mesh(&mut self, name: &str, transform: Transform, mut mesh: StaticMesh) -> NodeHandle {
+        mesh.set_label(name);
+        let handle = self.add(name, transform);
+        self.get_mut(handle).unwrap().mesh = Some(mesh);
+        handle
+    }
+
+    // removes the node together with all of its children
+    pub fn remove(&mut self, handle: NodeHandle) -> Result<(), Error> {
+        let parent = match self.get(handle) {
+            Some(node) => node.parent,
+            None => return Err(Error::SceneError("node doesn't exist")),
+        };
+        self.detach(handle, parent);
+
+        let mut stack = vec![handle];
+        while let Some(h) = stack.pop() {
+            let slot = &mut self.slots[h.index];
+            if let Some(node) = slot.node.take() {
+                stack.extend(node.children);
+            }
+            slot.generation += 1;
+            self.free.push(h.index);
+            if self.active_camera == Some(h) { self.active_camera = None; }
+        }
+        Ok(())
+    }
+
+    // keeps the local transform, so the node moves with its new parent
+    pub fn reparent(&mut self, handle: NodeHandle, new_parent: Option<NodeHandle>) -> Result<(), Error> {
+        let old_parent = match self.get(handle) {
+            Some(node) => node.parent,
+            None => return Err(Error::SceneError("node doesn't exist")),
+        };
+        if let Some(p) = new_parent {
+            if !self.contains(p) { return Err(Error::SceneError("parent node doesn't exist")) }
+            if self.is_ancestor(handle, p) {
+                return Err(Error::SceneError("can't parent a node to itself or its descendant"))
+            }
+        }
+        self.detach(handle, old_parent);
+        match new_parent {
+            Some(p) => self.get_mut(p).unwrap().children.push(handle),
+            None => self.roots.push(handle),
+        }
+        let node = self.get_mut(handle).unwrap();
+        node.parent = new_parent;
+        node.dirty = true;
+        Ok(())
+    }
+
+    fn detach(&mut self, handle: NodeHandle, parent: Option<NodeHandle>) {
+        let siblings = match parent {
+            Some(p) => &mut self.get_mut(p).unwrap().children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|h| *h != handle);
+    }
+
+    // true if `ancestor` is `handle` or one of its parents
+    fn is_ancestor(&self, ancestor: NodeHandle, mut handle: NodeHandle) -> bool {
+        loop {
+            if handle == ancestor { return true }
+            match self.get(handle).and_then(|n| n.parent) {
+                Some(p) => handle = p,
+                None => return false,
+            }
+        }
+    }
+
+    pub fn contains(&self, handle: NodeHandle) -> bool { self.get(handle).is_some() }
+    pub fn get(&self, handle: NodeHandle) -> Option<&Node> {
+        self.slots.get(handle.index)
+            .filter(|s| s.generation == handle.generation)
+            .and_then(|s| s.node.as_ref())
+    }
+    pub fn get_mut(&mut self, handle: NodeHandle) -> Option<&mut Node> {
+        self.slots.get_mut(handle.index)
+            .filter(|s| s.generation == handle.generation)
+            .and_then(|s| s.node.as_mut())
+    }
+    // first node with the name, in no particular order
+    pub fn find(&self, name: &str) -> Option<NodeHandle> {
+        self.iter().find(|(_, n)| n.name == name).map(|(h, _)| h)
+    }
+    pub fn roots(&self) -> &[NodeHandle] { &self.roots }
+    pub fn len(&self) -> usize { self.slots.len() - self.free.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NodeHandle, &Node)> {
+        self.slots.iter().enumerate().filter_map(|(index, s)|
+            s.node.as_ref().map(|n| (NodeHandle { index, generation: s.generation }, n))
+        )
+    }
+    pub fn meshes(&self) -> impl Iterator<Item = (&StaticMesh, Matrix4x4)> {
+        self.iter().filter_map(|(_, n)| n.mesh.as_ref().map(|m| (m, n.world_matrix)))
+    }
+
+    pub fn set_transform(&mut self, handle: NodeHandle, transform: Transform) -> Result<(), Error> {
+        match self.get_mut(handle) {
+            Some(node) => { node.set_transform(transform); Ok(()) },
+            None => Err(Error::SceneError("node doesn't exist")),
+        }
+    }
+
+    pub fn set_active_camera(&mut self, handle: Option<NodeHandle>) -> Result<(), Error> {
+        if let Some(h) = handle
+            && self.get(h).is_none_or(|n| n.camera.is_none()) {
+            return Err(Error::SceneError("node has no camera"))
+        }
+        self.active_camera = handle;
+        Ok(())
+    }
+    pub fn active_camera(&self) -> Option<&Camera> {
+        self.active_camera.and_then(|h| self.get(h)).and_then(|n| n.camera.as_ref())
+    }
+    pub fn active_camera_mut(&mut self) -> Option<&mut Camera> {
+        let h = self.active_camera?;
+        self.get_mut(h).and_then(|n| n.camera.as_mut())
+    }
+
+    // recomputes world transforms of dirty nodes and everything below them
+    pub fn update_transforms(&mut self) {
+        // (node, parents world, parent was recomputed)
+        let mut stack: Vec<(NodeHandle, Option<ParentWorld>, bool)> =
+            self.roots.iter().map(|h| (*h, None, false)).collect();
+        while let Some((handle, parent, parent_dirty)) = stack.pop() {
+            let node = self.get_mut(handle).unwrap();
+            let dirty = node.dirty || parent_dirty;
+            if dirty {
+                (node.world, node.world_matrix) = match parent {
+                    Some((world, matrix)) => (world.combine(&node.local), matrix*node.local.to_matrix()),
+                    None => (node.local, node.local.to_matrix()),
+                };
+                if let Some(camera) = node.camera.as_mut() { camera.set_transform(node.world); }
+                node.dirty = false;
+            }
+            let world = (node.world, node.world_matrix);
+            stack.extend(node.children.iter().map(|c| (*c, Some(world), dirty)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::{Matrix4x4, Quaternion, Transform, Vector, Vector3},
+        renderer::camera::Camera,
+        scene::Scene,
+        vector,
+    };
+
+    fn approx_eq(a: Vector3, b: Vector3) -> bool {
+        (0..3).all(|i| (a.0[i]-b.0[i]).abs() < 1e-5)
+    }
+
+    #[test]
+    fn add_and_find() {
+        let mut scene = Scene::new();
+        let player = scene.add("player", Transform::IDENTITY);
+        let hand = scene.add_child(player, "hand", Transform::IDENTITY).unwrap();
+
+        assert_eq!(scene.find("player"), Some(player));
+        assert_eq!(scene.find("hand"), Some(hand));
+        assert_eq!(scene.find("nothing"), None);
+        assert_eq!(scene.len(), 2);
+        assert_eq!(scene.roots(), &[player]);
+        assert_eq!(scene.get(player).unwrap().children(), &[hand]);
+        assert_eq!(scene.get(hand).unwrap().parent(), Some(player));
+    }
+
+    #[test]
+    fn world_matrices_follow_parent() {
+        let mut scene = Scene::new();
+        let parent = scene.add("parent", Transform::from_position(vector!(1.0, 0.0, 0.0)));
+        let child = scene.add_child(parent, "child", Transform::from_position(vector!(0.0, 2.0, 0.0))).unwrap();
+        scene.update_transforms();
+
+        assert!(approx_eq(scene.get(child).unwrap().world_transform().position, vector!(1.0, 2.0, 0.0)));
+
+        scene.set_transform(parent, Transform::from_position_rotation(
+            vector!(0.0, 0.0, 5.0),
+            Quaternion::from_angle_vect(90.0, vector!(0.0, 0.0, 1.0)),
+        )).unwrap();
+        scene.update_transforms();
+
+        let node = scene.get(child).unwrap();
+        assert!(approx_eq(node.world_transform().position, vector!(-2.0, 0.0, 5.0)));
+        let expected = scene.get(parent).unwrap().world_matrix()*Transform::from_position(vector!(0.0, 2.0, 0.0)).to_matrix();
+        assert_eq!(node.world_matrix(), expected);
+    }
+
+    #[test]
+    fn clean_nodes_are_not_recomputed() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", Transform::IDENTITY);
+        scene.update_transforms();
+        // dirty flag is cleared, the cached matrix stays even if nothing marks it dirty
+        assert!(!scene.get(a).unwrap().dirty);
+        scene.get_mut(a).unwrap().transform_mut().position = vector!(3.0, 0.0, 0.0);
+        assert_eq!(scene.get(a).unwrap().world_matrix(), Matrix4x4::IDENTITY);
+        scene.update_transforms();
+        assert_eq!(scene.get(a).unwrap().world_matrix(), Matrix4x4::translation_mat(vector!(3.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn remove_subtree() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", Transform::IDENTITY);
+        let b = scene.add_child(a, "b", Transform::IDENTITY).unwrap();
+        let c = scene.add_child(b, "c", Transform::IDENTITY).unwrap();
+        let d = scene.add("d", Transform::IDENTITY);
+
+        scene.remove(b).unwrap();
+        assert!(!scene.contains(b));
+        assert!(!scene.contains(c));
+        assert!(scene.get(a).unwrap().children().is_empty());
+        assert_eq!(scene.len(), 2);
+        assert!(scene.remove(b).is_err());
+
+        // the freed slot is reused, but the old handle stays dead
+        let e = scene.add("e", Transform::IDENTITY);
+        assert!(!scene.contains(b) && !scene.contains(c));
+        assert_eq!(scene.get(e).unwrap().name, "e");
+        assert!(scene.contains(d));
+    }
+
+    #[test]
+    fn reparent() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", Transform::from_position(vector!(1.0, 0.0, 0.0)));
+        let b = scene.add("b", Transform::from_position(vector!(0.0, 1.0, 0.0)));
+        let c = scene.add_child(a, "c", Transform::from_position(vector!(0.0, 0.0, 1.0))).unwrap();
+        scene.update_transforms();
+
+        scene.reparent(c, Some(b)).unwrap();
+        scene.update_transforms();
+        assert!(approx_eq(scene.get(c).unwrap().world_transform().position, vector!(0.0, 1.0, 1.0)));
+        assert!(scene.get(a).unwrap().children().is_empty());
+        assert_eq!(scene.get(b).unwrap().children(), &[c]);
+
+        assert!(scene.reparent(b, Some(c)).is_err());
+        assert!(scene.reparent(b, Some(b)).is_err());
+
+        scene.reparent(c, None).unwrap();
+        assert_eq!(scene.roots(), &[a, b, c]);
+        scene.update_transforms();
+        assert!(approx_eq(scene.get(c).unwrap().world_transform().position, vector!(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn attached_camera() {
+        let mut scene = Scene::new();
+        let player = scene.add("player", Transform::from_position(vector!(0.0, 0.0, 4.0)));
+        let head = scene.add_child(player, "head", Transform::from_position(vector!(0.0, 1.0, 0.0))).unwrap();
+        assert!(scene.set_active_camera(Some(head)).is_err());
+
+        scene.get_mut(head).unwrap().camera = Some(Camera::new(Vector::new([0.0; 3]), Quaternion::IDENTITY));
+        scene.set_active_camera(Some(head)).unwrap();
+        scene.update_transforms();
+        assert!(approx_eq(scene.active_camera().unwrap().transform.position, vector!(0.0, 1.0, 4.0)));
+
+        scene.remove(player).unwrap();
+        assert!(scene.active_camera().is_none());
+    }
+}
@@ -0,0 +1,246 @@
+// anything that can tell the time in seconds, glfw in the game and ManualClock in tests
+pub trait Clock {
+    fn now(&mut self) -> f64;
+}
+impl Clock for glfw::Glfw {
+    fn now(&mut self) -> f64 { self.get_time() }
+}
+
+// only moves when told to, for tests and scripted runs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ManualClock {
+    pub now: f64,
+}
+impl ManualClock {
+    pub fn new(now: f64) -> Self { Self { now } }
+    pub fn advance(&mut self, seconds: f64) { self.now += seconds; }
+}
+impl Clock for ManualClock {
+    fn now(&mut self) -> f64 { self.now }
+}
+
+// frame timing. every frame: tick once, then while step_fixed() { simulate(fixed_delta) },
+// then render with alpha() to blend between the last two simulation states
+#[derive(Debug, Clone)]
+pub struct Time {
+    last: Option<f64>,
+    fixed_step: f64,
+    max_frame_time: f64,
+    scale: f64,
+    paused: bool,
+
+    accumulator: f64,
+    raw_delta: f64,
+    delta: f64,
+    elapsed: f64,
+    real_elapsed: f64,
+
+    frame_count: u64,
+    fixed_step_count: u64,
+    frame_fixed_steps: u32,
+}
+impl Default for Time {
+    fn default() -> Self { Self::new(1.0/60.0) }
+}
+// anything else would never be used up by step_fixed and hang the fixed step loop
+fn assert_step(seconds: f64) {
+    assert!(seconds.is_finite() && seconds > 0.0, "fixed step must be positive, got {}", seconds);
+}
+impl Time {
+    // panics unless fixed_step is positive
+    pub fn new(fixed_step: f64) -> Self {
+        assert_step(fixed_step);
+        Self {
+            last: None,
+            fixed_step,
+            max_frame_time: 0.25,
+            scale: 1.0,
+            paused: false,
+            accumulator: 0.0,
+            raw_delta: 0.0,
+            delta: 0.0,
+            elapsed: 0.0,
+            real_elapsed: 0.0,
+            frame_count: 0,
+            fixed_step_count: 0,
+            frame_fixed_steps: 0,
+        }
+    }
+
+    pub fn tick(&mut self, clock: &mut impl Clock) {
+        let now = clock.now();
+        self.advance_to(now);
+    }
+    // the first frame has a delta of 0 instead of the time since the clock started
+    pub fn advance_to(&mut self, now: f64) {
+        let raw = match self.last {
+            Some(last) => (now-last).max(0.0),
+            None => 0.0,
+        };
+        self.last = Some(now);
+
+        // a breakpoint or a window drag shouldn't make the simulation try to catch up seconds at once
+        self.raw_delta = raw.min(self.max_frame_time);
+        self.delta = if self.paused { 0.0 } else { self.raw_delta*self.scale };
+        self.accumulator += self.delta;
+        self.elapsed += self.delta;
+        self.real_elapsed += raw;
+        self.frame_count += 1;
+        self.frame_fixed_steps = 0;
+    }
+
+    // true while there is a whole fixed step left to simulate this frame
+    pub fn step_fixed(&mut self) -> bool {
+        if self.accumulator < self.fixed_step { return false }
+        self.accumulator -= self.fixed_step;
+        self.fixed_step_count += 1;
+        self.frame_fixed_steps += 1;
+        true
+    }
+    // how far between the previous and the next fixed step this frame is, 0..1
+    pub fn alpha(&self) -> f32 { (self.accumulator/self.fixed_step) as f32 }
+
+    // scaled seconds since the last frame, 0 while paused
+    pub fn delta(&self) -> f32 { self.delta as f32 }
+    pub fn unscaled_delta(&self) -> f32 { self.raw_delta as f32 }
+    pub fn fixed_delta(&self) -> f32 { self.fixed_step as f32 }
+    pub fn fixed_step(&self) -> f64 { self.fixed_step }
+    // scaled game time
+    pub fn elapsed(&self) -> f64 { self.elapsed }
+    // wall clock time since the first tick
+    pub fn real_elapsed(&self) -> f64 { self.real_elapsed }
+    pub fn frame_count(&self) -> u64 { self.frame_count }
+    pub fn fixed_step_count(&self) -> u64 { self.fixed_step_count }
+    pub fn fixed_steps_this_frame(&self) -> u32 { self.frame_fixed_steps }
+
+    // panics unless seconds is positive
+    pub fn set_fixed_step(&mut self, seconds: f64) {
+        assert_step(seconds);
+        self.fixed_step = seconds;
+    }
+    pub fn set_max_frame_time(&mut self, seconds: f64) { self.max_frame_time = seconds; }
+    pub fn time_scale(&self) -> f64 { self.scale }
+    pub fn set_time_scale(&mut self, scale: f64) { self.scale = scale.max(0.0); }
+    pub fn pause(&mut self) { self.paused = true; }
+    pub fn resume(&mut self) { self.paused = false; }
+    pub fn is_paused(&self) -> bool { self.paused }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::time::{ManualClock, Time};
+
+    fn approx_eq(a: f64, b: f64) -> bool { (a-b).abs() < 1e-9 }
+
+    // ticks once and counts the fixed steps taken
+    fn frame(time: &mut Time, clock: &mut ManualClock, seconds: f64) -> u32 {
+        clock.advance(seconds);
+        time.tick(clock);
+        let mut steps = 0;
+        while time.step_fixed() { steps += 1; }
+        steps
+    }
+
+    #[test]
+    fn first_frame_has_no_delta() {
+        let mut clock = ManualClock::new(12.0);
+        let mut time = Time::default();
+        time.tick(&mut clock);
+        assert_eq!(time.delta(), 0.0);
+        assert_eq!(time.frame_count(), 1);
+    }
+
+    #[test]
+    fn delta_is_positive() {
+        let mut clock = ManualClock::new(1.0);
+        let mut time = Time::default();
+        time.tick(&mut clock);
+        clock.advance(0.016);
+        time.tick(&mut clock);
+        assert!((time.delta() - 0.016).abs() < 1e-6);
+        assert!(approx_eq(time.elapsed(), 0.016));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_fixed_step() { Time::new(0.0); }
+
+    #[test]
+    #[should_panic]
+    fn nan_fixed_step() { Time::default().set_fixed_step(f64::NAN); }
+
+    #[test]
+    #[should_panic]
+    fn negative_fixed_step() { Time::default().set_fixed_step(-0.01); }
+
+    #[test]
+    fn fixed_steps_accumulate() {
+        let mut clock = ManualClock::new(0.0);
+        let mut time = Time::new(0.01);
+        time.tick(&mut clock);
+
+        assert_eq!(frame(&mut time, &mut clock, 0.025), 2);
+        assert!((time.alpha() - 0.5).abs() < 1e-4);
+        // leftover 0.005 + 0.006 makes one more step
+        assert_eq!(frame(&mut time, &mut clock, 0.006), 1);
+        assert_eq!(frame(&mut time, &mut clock, 0.001), 0);
+        assert_eq!(time.fixed_step_count(), 3);
+        assert_eq!(time.fixed_steps_this_frame(), 0);
+        assert_eq!(time.frame_count(), 4);
+    }
+
+    #[test]
+    fn same_steps_regardless_of_frame_rate() {
+        // powers of two so the sums are exact
+        let mut slow = (Time::new(0.0625), ManualClock::new(0.0));
+        let mut fast = (Time::new(0.0625), ManualClock::new(0.0));
+        slow.0.tick(&mut slow.1);
+        fast.0.tick(&mut fast.1);
+        for _ in 0..10 { frame(&mut slow.0, &mut slow.1, 0.25); }
+        for _ in 0..80 { frame(&mut fast.0, &mut fast.1, 0.03125); }
+        assert_eq!(slow.0.fixed_step_count(), 40);
+        assert_eq!(fast.0.fixed_step_count(), 40);
+    }
+
+    #[test]
+    fn max_frame_time_clamps() {
+        let mut clock = ManualClock::new(0.0);
+        let mut time = Time::new(0.1);
+        time.set_max_frame_time(0.25);
+        time.tick(&mut clock);
+        assert_eq!(frame(&mut time, &mut clock, 10.0), 2);
+        assert_eq!(time.delta(), 0.25);
+        assert!(approx_eq(time.real_elapsed(), 10.0));
+    }
+
+    #[test]
+    fn scale_and_pause() {
+        let mut clock = ManualClock::new(0.0);
+        let mut time = Time::new(0.1);
+        time.tick(&mut clock);
+
+        time.set_time_scale(0.5);
+        assert_eq!(frame(&mut time, &mut clock, 0.2), 1);
+        assert!((time.delta() - 0.1).abs() < 1e-6);
+        assert!((time.unscaled_delta() - 0.2).abs() < 1e-6);
+
+        time.pause();
+        assert_eq!(frame(&mut time, &mut clock, 0.2), 0);
+        assert_eq!(time.delta(), 0.0);
+        assert!(approx_eq(time.elapsed(), 0.1));
+
+        time.resume();
+        time.set_time_scale(1.0);
+        assert_eq!(frame(&mut time, &mut clock, 0.2), 2);
+    }
+
+    #[test]
+    fn clock_going_backwards() {
+        let mut clock = ManualClock::new(5.0);
+        let mut time = Time::default();
+        time.tick(&mut clock);
+        clock.now = 4.0;
+        time.tick(&mut clock);
+        assert_eq!(time.delta(), 0.0);
+    }
+}
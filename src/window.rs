@@ -0,0 +1,249 @@
+use std::path::Path;
+
+use glfw::{Context as _, GlfwReceiver, PixelImage, WindowEvent};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, config::Config};
+
+// how the window sits on its monitor. borderless covers the whole monitor without changing
+// its video mode, fullscreen takes the monitor over (video_mode, or whatever it is running at)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+impl From<glfw::VidMode> for VideoMode {
+    fn from(mode: glfw::VidMode) -> Self {
+        Self { width: mode.width, height: mode.height, refresh_rate: mode.refresh_rate }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    // what Window::set_monitor and config.monitor take, the primary monitor is 0
+    pub index: usize,
+    pub name: String,
+    // virtual screen coordinates of the top left corner
+    pub position: (i32, i32),
+    pub physical_size_mm: (i32, i32),
+    pub content_scale: (f32, f32),
+    pub current_mode: Option<VideoMode>,
+    pub modes: Vec<VideoMode>,
+}
+
+// the game window. everything that changes how it looks or where it is goes through here,
+// so fullscreen switches and the cursor stay consistent. raw() has the rest of glfw's window
+pub struct Window {
+    window: glfw::PWindow,
+    title: String,
+    mode: WindowMode,
+    monitor: usize,
+    video_mode: Option<VideoMode>,
+    // position and size to go back to when leaving borderless or fullscreen
+    windowed: (i32, i32, i32, i32),
+    swap_interval: u32,
+    cursor_grabbed: bool,
+}
+impl Window {
+    // a window as the config describes it, with its context made current
+    pub fn create(glfw: &mut glfw::Glfw, config: &Config) -> Result<(Self, GlfwReceiver<(f64, WindowEvent)>), Error> {
+        let (mut window, events) = glfw.create_window(config.width, config.height, &config.title, glfw::WindowMode::Windowed)
+            .ok_or(Error::WindowError("Failed to create GLFW Window.".to_string()))?;
+        window.make_current();
+        let (x, y) = window.get_pos();
+        let mut window = Self {
+            window,
+            title: config.title.clone(),
+            mode: WindowMode::Windowed,
+            monitor: config.monitor,
+            video_mode: None,
+            windowed: (x, y, config.width as i32, config.height as i32),
+            swap_interval: 0,
+            cursor_grabbed: false,
+        };
+        // a hidden window keeps glfw's defaults, it never swaps to a screen
+        if !config.headless {
+            window.set_vsync(config.vsync);
+            window.set_mode(config.window_mode);
+            if let Some(icon) = &config.icon { window.set_icon(config.asset(icon))?; }
+        }
+        Ok((window, events))
+    }
+
+    // every connected monitor, primary first
+    pub fn monitors(&mut self) -> Vec<MonitorInfo> {
+        self.window.glfw.clone().with_connected_monitors(|_, monitors| {
+            monitors.iter().enumerate().map(|(index, monitor)| MonitorInfo {
+                index,
+                name: monitor.get_name().unwrap_or_default(),
+                position: monitor.get_pos(),
+                physical_size_mm: monitor.get_physical_size(),
+                content_scale: monitor.get_content_scale(),
+                current_mode: monitor.get_video_mode().map(VideoMode::from),
+                modes: monitor.get_video_modes().into_iter().map(VideoMode::from).collect(),
+            }).collect()
+        })
+    }
+
+    pub fn mode(&self) -> WindowMode { self.mode }
+    // without any monitor (no display server) the window stays windowed
+    pub fn set_mode(&mut self, mode: WindowMode) {
+        if self.mode == WindowMode::Windowed {
+            let ((x, y), (width, height)) = (self.window.get_pos(), self.window.get_size());
+            self.windowed = (x, y, width, height);
+        }
+        let (index, video_mode, windowed) = (self.monitor, self.video_mode, self.windowed);
+        let window = &mut self.window;
+        self.mode = window.glfw.clone().with_connected_monitors(|_, monitors| {
+            let monitor = monitors.get(index).or(monitors.first());
+            let current = monitor.and_then(|m| m.get_video_mode()).map(VideoMode::from);
+            match (mode, monitor, current) {
+                (WindowMode::Fullscreen, Some(monitor), Some(current)) => {
+                    let video_mode = video_mode.unwrap_or(current);
+                    window.set_monitor(
+                        glfw::WindowMode::FullScreen(monitor), 0, 0,
+                        video_mode.width, video_mode.height, Some(video_mode.refresh_rate),
+                    );
+                    WindowMode::Fullscreen
+                },
+                (WindowMode::Borderless, Some(monitor), Some(current)) => {
+                    let (x, y) = monitor.get_pos();
+                    window.set_decorated(false);
+                    window.set_monitor(glfw::WindowMode::Windowed, x, y, current.width, current.height, None);
+                    WindowMode::Borderless
+                },
+                _ => {
+                    let (x, y, width, height) = windowed;
+                    window.set_decorated(true);
+                    window.set_monitor(glfw::WindowMode::Windowed, x, y, width as u32, height as u32, None);
+                    WindowMode::Windowed
+                },
+            }
+        });
+    }
+    // between windowed and fullscreen, borderless counts as fullscreen
+    pub fn toggle_fullscreen(&mut self) {
+        self.set_mode(if self.mode == WindowMode::Windowed { WindowMode::Fullscreen } else { WindowMode::Windowed });
+    }
+    pub fn monitor(&self) -> usize { self.monitor }
+    // index into monitors(), takes effect right away unless windowed
+    pub fn set_monitor(&mut self, index: usize) {
+        self.monitor = index;
+        if self.mode != WindowMode::Windowed { self.set_mode(self.mode) }
+    }
+    pub fn video_mode(&self) -> Option<VideoMode> { self.video_mode }
+    // for fullscreen, None uses what the monitor is already running at
+    pub fn set_video_mode(&mut self, video_mode: Option<VideoMode>) {
+        self.video_mode = video_mode;
+        if self.mode == WindowMode::Fullscreen { self.set_mode(self.mode) }
+    }
+
+    pub fn swap_interval(&self) -> u32 { self.swap_interval }
+    pub fn vsync(&self) -> bool { self.swap_interval > 0 }
+    // 0 swaps right away, n waits for n screen refreshes. needs the context current
+    pub fn set_swap_interval(&mut self, interval: u32) {
+        self.swap_interval = interval;
+        let interval = if interval == 0 { glfw::SwapInterval::None } else { glfw::SwapInterval::Sync(interval) };
+        self.window.glfw.set_swap_interval(interval);
+    }
+    pub fn set_vsync(&mut self, vsync: bool) { self.set_swap_interval(vsync as u32) }
+
+    pub fn title(&self) -> &str { &self.title }
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        self.window.set_title(title);
+    }
+    // any image file, the window manager scales it as needed
+    pub fn set_icon(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let icon = image::open(path)
+            .map_err(|e| Error::WindowError(format!("couldn't load icon {}: {}", path.display(), e)))?;
+        self.set_icon_image(&icon.to_rgba8());
+        Ok(())
+    }
+    pub fn set_icon_image(&mut self, icon: &RgbaImage) {
+        self.window.set_icon_from_pixels(vec![icon_pixels(icon)]);
+    }
+
+    // a grabbed cursor is hidden and locked to the window, mouse motion only counts while it is.
+    // the grab survives focus changes and fullscreen switches, only release_cursor ends it
+    pub fn cursor_grabbed(&self) -> bool { self.cursor_grabbed }
+    pub fn grab_cursor(&mut self) {
+        self.cursor_grabbed = true;
+        self.apply_cursor();
+    }
+    pub fn release_cursor(&mut self) {
+        self.cursor_grabbed = false;
+        self.apply_cursor();
+    }
+    pub fn toggle_cursor_grab(&mut self) {
+        if self.cursor_grabbed { self.release_cursor() } else { self.grab_cursor() }
+    }
+    // puts glfw back in line with the policy, e.g. after the window got focus back
+    pub fn apply_cursor(&mut self) {
+        let mode = if self.cursor_grabbed { glfw::CursorMode::Disabled } else { glfw::CursorMode::Normal };
+        self.window.set_cursor_mode(mode);
+        if self.window.glfw.supports_raw_motion() {
+            self.window.set_raw_mouse_motion(self.cursor_grabbed);
+        }
+    }
+
+    pub fn should_close(&self) -> bool { self.window.should_close() }
+    pub fn set_should_close(&mut self, close: bool) { self.window.set_should_close(close) }
+    pub fn swap_buffers(&mut self) { self.window.swap_buffers() }
+    pub fn make_current(&mut self) { self.window.make_current() }
+    pub fn cursor_pos(&self) -> (f64, f64) { self.window.get_cursor_pos() }
+    pub fn raw(&mut self) -> &mut glfw::PWindow { &mut self.window }
+}
+
+// glfw wants rgba bytes, packed into u32s as they lie in memory
+fn icon_pixels(icon: &RgbaImage) -> PixelImage {
+    PixelImage {
+        width: icon.width(),
+        height: icon.height(),
+        pixels: icon.pixels().map(|p| u32::from_ne_bytes(p.0)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use image::{Rgba, RgbaImage};
+
+    use crate::window::{VideoMode, WindowMode, icon_pixels};
+
+    #[test]
+    fn mode_names() {
+        #[derive(serde::Deserialize)]
+        struct Settings { mode: WindowMode }
+        let mode = |text| toml::from_str::<Settings>(text).map(|s| s.mode);
+        assert_eq!(mode("mode = \"borderless\"").unwrap(), WindowMode::Borderless);
+        assert_eq!(mode("mode = \"fullscreen\"").unwrap(), WindowMode::Fullscreen);
+        assert!(mode("mode = \"Fullscreen\"").is_err());
+    }
+
+    #[test]
+    fn video_mode_from_glfw() {
+        let mode = glfw::VidMode { width: 1920, height: 1080, red_bits: 8, green_bits: 8, blue_bits: 8, refresh_rate: 144 };
+        assert_eq!(VideoMode::from(mode), VideoMode { width: 1920, height: 1080, refresh_rate: 144 });
+    }
+
+    #[test]
+    fn icon_bytes_keep_their_order() {
+        let icon = RgbaImage::from_pixel(2, 1, Rgba([1, 2, 3, 4]));
+        let pixels = icon_pixels(&icon);
+        assert_eq!((pixels.width, pixels.height), (2, 1));
+        let bytes: Vec<u8> = pixels.pixels.iter().flat_map(|p| p.to_ne_bytes()).collect();
+        assert_eq!(bytes, icon.into_raw());
+    }
+}
@@ -0,0 +1,20 @@
+// shared by the integration tests. they run headless games, so they need a gl context
+// (a display or osmesa) and one test at a time:
+// cargo test --features gl-tests -- --test-threads=1
+#![allow(dead_code)] // every test file compiles this, not every one uses all of it
+
+use engine::{
+    config::Config,
+    game::{Game, Player},
+    math::{Quaternion, Vector},
+    vector,
+};
+
+// a 32x32 game with the player at the origin
+pub fn headless_game() -> Game {
+    headless_game_with(Config::headless(32, 32))
+}
+pub fn headless_game_with(config: Config) -> Game {
+    let player = Player::new(vector!(0.0, 0.0, 0.0), Quaternion::IDENTITY, 1.0, 1.0);
+    Game::with_config(player, config).unwrap()
+}
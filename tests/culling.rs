@@ -0,0 +1,33 @@
+// cargo test --features gl-tests --test culling -- --test-threads=1
+#![cfg(feature = "gl-tests")]
+
+mod common;
+
+use engine::{
+    Error,
+    app::{App, Context},
+    math::{Transform, Vector},
+    renderer::{FrameStats, objects::Cube},
+    vector,
+};
+
+// one cube in front of the camera, the rest behind it or far off to the side
+struct Cubes;
+impl App for Cubes {
+    fn init(&mut self, ctx: &mut Context) -> Result<(), Error> {
+        let positions = [vector!(0.0, 0.0, 10.0), vector!(0.0, 0.0, -10.0), vector!(100.0, 0.0, 10.0), vector!(0.0, -100.0, 10.0)];
+        for (i, position) in positions.into_iter().enumerate() {
+            let cube = Cube::new((0.5, 0.5, 0.5), Transform::IDENTITY, vector!(1.0, 1.0, 1.0, 1.0), Vec::new(), gl::STATIC_DRAW);
+            ctx.scene.add_mesh(&format!("cube{}", i), Transform::from_position(position), cube.mesh);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn offscreen_meshes_are_culled() {
+    let mut game = common::headless_game();
+    game.init(&mut Cubes).unwrap();
+    game.step_frames(&mut Cubes, 1).unwrap();
+    assert_eq!(game.renderer().stats(), FrameStats { drawn: 1, culled: 3 });
+}
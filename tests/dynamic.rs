@@ -0,0 +1,89 @@
+// cargo test --features gl-tests --test dynamic -- --test-threads=1
+#![cfg(feature = "gl-tests")]
+
+mod common;
+
+use engine::{
+    Error,
+    app::{App, Context},
+    game::Game,
+    math::{Transform, Vector, Vector3},
+    renderer::{
+        FrameStats, Vertex,
+        dynamic::{DynamicMesh, Streaming},
+        layout::VertexFormat,
+        resources::live_resources,
+    },
+    vector,
+};
+
+fn vertex(x: f32, y: f32, z: f32) -> Vertex { Vertex::new([x, y, z], [1.0; 4], [0.0; 2]) }
+fn positions(mesh: &DynamicMesh) -> Vec<Vector3> { Vertex::positions(&mesh.read_back::<Vertex>().unwrap()) }
+fn game() -> Game {
+    common::headless_game()
+}
+
+#[test]
+fn updates_and_growth() {
+    let _game = game();
+    for streaming in [Streaming::SubData, Streaming::Orphan, Streaming::Ring(3), Streaming::Ring(1)] {
+        let mut mesh = DynamicMesh::new::<Vertex>(gl::TRIANGLES, 2, streaming).unwrap();
+        mesh.update(&[vertex(0.0, 0.0, 0.0), vertex(1.0, 0.0, 0.0), vertex(0.0, 1.0, 0.0)]).unwrap();
+        assert_eq!(mesh.capacity(), 4, "{:?}", streaming);
+        assert_eq!(positions(&mesh), vec![vector!(0.0, 0.0, 0.0), vector!(1.0, 0.0, 0.0), vector!(0.0, 1.0, 0.0)]);
+
+        // a range in the middle keeps the rest, one past the end appends
+        mesh.update_range(1, &[vertex(2.0, 0.0, 0.0)]).unwrap();
+        mesh.update_range(3, &[vertex(0.0, 0.0, 5.0), vertex(0.0, 0.0, 6.0)]).unwrap();
+        assert_eq!(mesh.vertex_count(), 5);
+        assert_eq!(positions(&mesh), vec![
+            vector!(0.0, 0.0, 0.0), vector!(2.0, 0.0, 0.0), vector!(0.0, 1.0, 0.0),
+            vector!(0.0, 0.0, 5.0), vector!(0.0, 0.0, 6.0),
+        ], "{:?}", streaming);
+        assert_eq!(mesh.bounds().max, vector!(2.0, 1.0, 6.0));
+        assert!(mesh.update_range(6, &[vertex(0.0, 0.0, 0.0)]).is_err());
+        assert_eq!(unsafe { gl::GetError() }, gl::NO_ERROR, "{:?}", streaming);
+    }
+}
+
+#[test]
+fn ring_segments_rotate() {
+    let _game = game();
+    let mut mesh = DynamicMesh::new::<Vertex>(gl::LINES, 2, Streaming::Ring(2)).unwrap();
+    for i in 0..5 {
+        mesh.update(&[vertex(i as f32, 0.0, 0.0), vertex(i as f32, 1.0, 0.0)]).unwrap();
+        assert_eq!(positions(&mesh)[0], vector!(i as f32, 0.0, 0.0));
+    }
+    assert_eq!(mesh.capacity(), 2);
+}
+
+// a line that moves in front of the camera every frame
+struct Lines { entity: Option<engine::ecs::Entity>, frame: u32 }
+impl App for Lines {
+    fn init(&mut self, ctx: &mut Context) -> Result<(), Error> {
+        let mesh = DynamicMesh::new::<Vertex>(gl::LINES, 4, Streaming::Ring(3))?;
+        let entity = ctx.world.spawn();
+        ctx.world.insert(entity, mesh)?;
+        ctx.world.insert(entity, Transform::from_position(vector!(0.0, 0.0, 5.0)))?;
+        self.entity = Some(entity);
+        Ok(())
+    }
+    fn update(&mut self, ctx: &mut Context) {
+        let x = self.frame as f32*0.1;
+        let mut mesh = ctx.world.get_mut::<DynamicMesh>(self.entity.unwrap()).unwrap();
+        mesh.update(&[vertex(x, 0.0, 0.0), vertex(x, 1.0, 0.0)]).unwrap();
+        self.frame += 1;
+    }
+}
+
+#[test]
+fn entities_with_dynamic_meshes_are_drawn() {
+    let before = live_resources();
+    let mut game = game();
+    let mut lines = Lines { entity: None, frame: 0 };
+    game.init(&mut lines).unwrap();
+    game.step_frames(&mut lines, 5).unwrap();
+    assert_eq!(game.renderer().stats(), FrameStats { drawn: 1, culled: 0 });
+    drop(game);
+    assert_eq!(live_resources(), before);
+}
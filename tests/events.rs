@@ -0,0 +1,34 @@
+// cargo test --features gl-tests --test events -- --test-threads=1
+#![cfg(feature = "gl-tests")]
+
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use engine::{
+    app::App,
+    event::EventBus,
+    input::{GamepadEvent, SimulatedGamepads},
+};
+
+struct Idle;
+impl App for Idle {}
+
+#[test]
+fn gamepad_hot_plug_reaches_the_bus() {
+    let mut game = common::headless_game();
+    let pads = SimulatedGamepads::new();
+    game.set_gamepad_backend(pads.clone());
+    game.init(&mut Idle).unwrap();
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = seen.clone();
+    game.world.resource_mut::<EventBus>().unwrap()
+        .subscribe(move |event: &GamepadEvent| log.borrow_mut().push(event.clone()));
+
+    pads.connect(1, "test pad");
+    game.step_frames(&mut Idle, 3).unwrap();
+    pads.disconnect(1);
+    game.step_frames(&mut Idle, 2).unwrap();
+    assert_eq!(*seen.borrow(), vec![GamepadEvent::Connected(1, "test pad".to_string()), GamepadEvent::Disconnected(1)]);
+}
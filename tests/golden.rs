@@ -0,0 +1,41 @@
+// renders real frames, so it needs a gl context (a display or osmesa):
+// cargo test --features gl-tests --test golden -- --test-threads=1
+// UPDATE_GOLDEN=1 writes the references into tests/golden
+#![cfg(feature = "gl-tests")]
+
+use engine::{
+    golden::{MeshDescription, SceneDescription, Shape, Tolerance, assert_golden, compare, render},
+    math::{Quaternion, Transform, Vector},
+    vector,
+};
+
+fn textured_cubes_scene() -> SceneDescription {
+    let mesh = |position, rotation| MeshDescription {
+        shape: Shape::Cube((0.5, 0.5, 0.5)),
+        transform: Transform::from_position_rotation(position, rotation),
+        color: vector!(1.0, 1.0, 1.0, 1.0),
+        textures: vec!["textures/container.jpg", "textures/awesomeface.png"],
+    };
+    // the view puts the eye at minus the camera position (like the player's), so this
+    // looks at the cubes from z = -3
+    SceneDescription::new(128, 128)
+        .with_camera(vector!(0.0, 0.0, 3.0), Quaternion::IDENTITY, 60.0)
+        .with_mesh(mesh(vector!(0.0, 0.0, 0.0), Quaternion::from_angle_vect(30.0, vector!(1.0, 1.0, 0.0))))
+        .with_mesh(mesh(vector!(1.2, 0.5, 1.0), Quaternion::IDENTITY))
+        .with_frames(2)
+}
+
+#[test]
+fn textured_cubes() {
+    let img = render(&textured_cubes_scene()).unwrap();
+    assert_golden("textured_cubes", &img, &Tolerance::default()).unwrap();
+}
+
+#[test]
+fn camera_position_is_used() {
+    let scene = textured_cubes_scene();
+    let moved = SceneDescription { camera_position: vector!(0.5, 0.0, 4.0), ..scene.clone() };
+    let (img, moved) = (render(&scene).unwrap(), render(&moved).unwrap());
+    let c = compare(&moved, &img, &Tolerance::default()).unwrap();
+    assert!(!c.passes(&Tolerance::default()), "moving the camera didn't change the image");
+}
@@ -0,0 +1,81 @@
+// cargo test --features gl-tests --test material -- --test-threads=1
+#![cfg(feature = "gl-tests")]
+
+mod common;
+
+use std::rc::Rc;
+
+use engine::{
+    Error,
+    app::{App, Context},
+    config::Config,
+    math::{Transform, Vector},
+    renderer::{Vertex, material::{Material, MaterialParam}, objects::StaticMesh, textures::Texture},
+    vector, vertex_layout,
+};
+
+// only a position, the default shader also wants a color and texture coordinates
+#[repr(C)]
+struct Point { position: [f32; 3] }
+vertex_layout!(Point { position: Position });
+
+fn points() -> StaticMesh {
+    let vertices = vec![Point { position: [0.0, 0.0, 5.0] }, Point { position: [1.0, 0.0, 5.0] }, Point { position: [0.0, 1.0, 5.0] }];
+    StaticMesh::new(vertices, vec![0, 1, 2], Transform::IDENTITY, Vec::new(), gl::STATIC_DRAW)
+}
+
+fn triangle() -> StaticMesh {
+    let vertex = |x, y| Vertex::new([x, y, -5.0], [1.0; 4], [0.0; 2]);
+    StaticMesh::new(vec![vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(0.0, 1.0)], vec![0, 1, 2], Transform::IDENTITY, Vec::new(), gl::STATIC_DRAW)
+}
+
+// one mesh the default material can draw, and the same mismatched one added every way there is
+struct MixedScene;
+impl App for MixedScene {
+    fn init(&mut self, ctx: &mut Context) -> Result<(), Error> {
+        ctx.renderer.set_culling(false);
+        ctx.scene.add_mesh("triangle", Transform::IDENTITY, triangle());
+        ctx.scene.add_mesh("points", Transform::IDENTITY, points());
+        let node = ctx.scene.add("assigned", Transform::IDENTITY);
+        ctx.scene.get_mut(node).unwrap().mesh = Some(points());
+        let entity = ctx.world.spawn();
+        ctx.world.insert(entity, points())?;
+        Ok(())
+    }
+}
+
+#[test]
+fn params_are_checked_against_the_program() {
+    let game = common::headless_game();
+    let config = Config::headless(32, 32);
+    let program = game.renderer().default_material().program().clone();
+
+    let names: Vec<_> = game.renderer().default_material().program().uniforms().map(|u| u.name.clone()).collect();
+    for name in ["texture1", "texture2", "model", "view", "perspective"] {
+        assert!(names.iter().any(|n| n == name), "{} missing from {:?}", name, names);
+    }
+
+    let texture = Rc::new(Texture::from_file(config.asset("textures/container.jpg")).unwrap());
+    let mut material = Material::new(program)
+        .with("texture1", texture.clone()).unwrap()
+        .with("texture2", texture).unwrap();
+    assert!(matches!(material.get("texture1"), Some(MaterialParam::Texture(_))));
+    // no such uniform, and the wrong type for one that exists
+    assert!(material.set("roughness", 0.5).is_err());
+    assert!(material.set("texture1", vector!(1.0, 0.0, 0.0)).is_err());
+    assert!(material.set("texture1", 3).is_ok());
+}
+
+#[test]
+fn meshes_that_dont_fit_are_skipped() {
+    let mut game = common::headless_game();
+    let default = game.renderer().default_material().clone();
+    assert!(points().set_material(Some(default)).is_err());
+
+    // reported by the first frame that tries to draw the layout, and never drawn
+    game.init(&mut MixedScene).unwrap();
+    assert!(matches!(game.step_frames(&mut MixedScene, 1), Err(Error::LayoutError(_))));
+    assert_eq!(game.renderer().stats().drawn, 1);
+    game.step_frames(&mut MixedScene, 1).unwrap();
+    assert_eq!(game.renderer().stats().drawn, 1);
+}
@@ -0,0 +1,92 @@
+// cargo test --features gl-tests --test replay -- --test-threads=1
+#![cfg(feature = "gl-tests")]
+
+mod common;
+
+use engine::{
+    app::{App, Context},
+    config::Config,
+    event::{EventBus, RecordingEvent, ScreenshotEvent},
+    game::{Game, Player},
+    input::{Button, InputEvent, Recording},
+    math::{Quaternion, Vector, Vector3},
+    vector,
+};
+use glfw::Key;
+
+// walks along move_z and turns with look_x
+struct Walker;
+impl App for Walker {
+    fn update(&mut self, ctx: &mut Context) {
+        let (forward, turn) = (ctx.input().axis("move_z"), ctx.input().axis("look_x"));
+        let delta = ctx.time().delta();
+        let mut player = ctx.player().unwrap();
+        player.rotate(Quaternion::from_radian_vect(turn*0.01, vector!(0.0, 1.0, 0.0)));
+        player.translate(vector!(0.0, 0.0, forward*delta));
+    }
+}
+
+fn new_game() -> Game {
+    let mut game = common::headless_game();
+    game.init(&mut Walker).unwrap();
+    game
+}
+
+fn position(game: &Game) -> Vector3 {
+    game.world.get::<Player>(game.player).unwrap().transform.position
+}
+
+#[test]
+fn replay_reproduces_session() {
+    let mut game = new_game();
+    game.start_recording();
+    game.send_input(InputEvent::Press(Button::Key(Key::W)));
+    game.step_frames(&mut Walker, 10).unwrap();
+    game.send_input(InputEvent::MouseMotion(40.0, 0.0));
+    game.send_input(InputEvent::Release(Button::Key(Key::W)));
+    game.step_frames(&mut Walker, 5).unwrap();
+    let recording = game.stop_recording().unwrap();
+    assert_eq!(recording.len(), 15);
+    let recorded = position(&game);
+    assert!(recorded.0[2] > 0.0);
+
+    // through a file, into a game that never saw the live input
+    let recording = Recording::from_json(&recording.to_json().unwrap()).unwrap();
+    let mut replayed = new_game();
+    replayed.replay(&mut Walker, recording).unwrap();
+    assert!(!replayed.is_replaying());
+    assert_eq!(position(&replayed), recorded);
+}
+
+// the record and screenshot hotkeys say how they went on the bus instead of printing it
+#[test]
+fn hotkeys_report_on_the_bus() {
+    let dir = std::env::temp_dir().join(format!("engine_hotkeys_{}", std::process::id()));
+    let config = Config {
+        replay_dir: dir.join("replays").display().to_string(),
+        screenshot_dir: dir.join("screenshots").display().to_string(),
+        ..Config::headless(32, 32)
+    };
+    let mut game = common::headless_game_with(config);
+    game.init(&mut Walker).unwrap();
+    // a frame on its own so the next tap is a new press
+    let tap = |game: &mut Game, key| {
+        game.step_frames(&mut Walker, 1).unwrap();
+        game.send_input(InputEvent::Press(Button::Key(key)));
+        game.send_input(InputEvent::Release(Button::Key(key)));
+        game.step_frames(&mut Walker, 1).unwrap();
+    };
+    let recording = |game: &Game| game.world.resource::<EventBus>().unwrap().iter::<RecordingEvent>().last().cloned();
+
+    tap(&mut game, Key::F9);
+    assert!(matches!(recording(&game), Some(RecordingEvent::Started)));
+    tap(&mut game, Key::F9);
+    let saved = match recording(&game) { Some(RecordingEvent::Saved(path)) => path, e => panic!("{:?}", e) };
+    assert!(Recording::load(&saved).is_ok());
+
+    tap(&mut game, Key::F12);
+    let screenshot = game.world.resource::<EventBus>().unwrap().iter::<ScreenshotEvent>().last().cloned();
+    let saved = match screenshot { Some(ScreenshotEvent::Saved(path)) => path, e => panic!("{:?}", e) };
+    assert!(saved.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,46 @@
+// cargo test --features gl-tests --test resources -- --test-threads=1
+#![cfg(feature = "gl-tests")]
+
+mod common;
+
+use std::rc::Rc;
+
+use engine::{
+    Error,
+    app::{App, Context},
+    math::{Transform, Vector},
+    renderer::{objects::Cube, resources::{context_alive, live_resources}, textures::Texture},
+    vector,
+};
+
+// a few cubes that all share one texture
+struct Cubes;
+impl App for Cubes {
+    fn init(&mut self, ctx: &mut Context) -> Result<(), Error> {
+        let texture = Rc::new(Texture::from_file(ctx.config.asset("textures/container.jpg"))?);
+        for i in 0..3 {
+            let cube = Cube::new((0.5, 0.5, 0.5), Transform::IDENTITY, vector!(1.0, 1.0, 1.0, 1.0), vec![texture.clone()], gl::STATIC_DRAW);
+            ctx.scene.add_mesh(&format!("cube{}", i), Transform::from_position(vector!(i as f32, 0.0, 10.0)), cube.mesh);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn dropping_the_game_frees_everything() {
+    let before = live_resources();
+    let mut game = common::headless_game();
+    let empty = live_resources();
+    game.init(&mut Cubes).unwrap();
+    game.step_frames(&mut Cubes, 1).unwrap();
+
+    let loaded = live_resources();
+    // shared, not loaded once per cube
+    assert_eq!(loaded.textures, empty.textures+1);
+    assert_eq!(loaded.vertex_arrays, empty.vertex_arrays+3);
+    assert!(context_alive());
+
+    drop(game);
+    assert!(!context_alive());
+    assert_eq!(live_resources(), before);
+}
@@ -0,0 +1,34 @@
+// cargo test --features gl-tests --test shaders -- --test-threads=1
+#![cfg(feature = "gl-tests")]
+
+mod common;
+
+use engine::{
+    math::{Matrix4x4, Vector},
+    renderer::uniforms::Uniform,
+    vector,
+};
+
+#[test]
+fn uniforms_are_reflected_and_typed() {
+    let game = common::headless_game();
+    let program = game.renderer().default_material().program();
+
+    let model = program.uniform("model").unwrap();
+    assert_eq!((model.ty, model.size), (gl::FLOAT_MAT4, 1));
+    assert!(program.uniform("texture1").unwrap().is_sampler());
+    assert_eq!(program.attributes().len(), 3);
+    assert!(program.uniform_blocks().is_empty());
+
+    // no NUL terminator needed, and a missing uniform isn't fatal
+    assert!(Uniform::from_name("model", program).is_active());
+    assert!(!Uniform::from_name("unused", program).is_active());
+
+    program.use_program();
+    assert!(program.set("model", Matrix4x4::IDENTITY).is_ok());
+    assert!(program.set("texture1", 0).is_ok());
+    // only warns
+    assert!(program.set("unused", 1.0).is_ok());
+    assert!(program.set("model", 1.0).is_err());
+    assert!(program.set("texture1", vector!(0.0, 0.0)).is_err());
+}